@@ -0,0 +1,128 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::future::FusedFuture;
+
+use crate::{
+    stop::{stop_pair, StopHandle, StopSignal},
+    BufferStorage, Forwarder, ForwarderError, ForwarderParts,
+};
+
+/// Future that runs a [`Forwarder`] until it completes or is aborted with
+/// an [`AbortHandle`]. Created by [`Forwarder::abortable`].
+///
+/// On success, the output is the total number of bytes written.
+pub struct Abortable<R, W, B, C> {
+    // Taken once the forward completes or is aborted
+    forwarder: Option<Forwarder<R, W, B, C>>,
+    signal: StopSignal,
+}
+
+/// Handle for aborting a forward immediately. Unlike a
+/// [`StopHandle`][crate::StopHandle], nothing more is read or written once
+/// the forward is aborted; the streams and any buffered data are handed
+/// back instead. Dropping the handle doesn't abort the forward.
+#[derive(Debug, Clone)]
+pub struct AbortHandle {
+    inner: StopHandle,
+}
+
+impl AbortHandle {
+    /// Abort the forward. It fails with [`AbortError::Aborted`] the next
+    /// time it's polled.
+    pub fn abort(&self) {
+        self.inner.stop()
+    }
+
+    /// Returns true if [`abort`][Self::abort] has been called
+    #[inline]
+    #[must_use]
+    pub fn is_aborted(&self) -> bool {
+        self.inner.is_stopped()
+    }
+}
+
+/// Everything that was left of an aborted forward, so that the connection
+/// can be repurposed
+pub type AbortedForward<R, W, B> = ForwarderParts<R, W, B>;
+
+/// Error from an [`Abortable`]
+#[derive(Debug)]
+pub enum AbortError<R, W, B> {
+    /// The forward failed before it was aborted
+    Forward(ForwarderError),
+
+    /// The forward was aborted
+    Aborted(AbortedForward<R, W, B>),
+}
+
+impl<R, W, B, C> Forwarder<R, W, B, C> {
+    /// Make the forward abortable, with an [`AbortHandle`] that tears it
+    /// down immediately and hands back the reader, writer, buffer, and
+    /// whatever data was still buffered. Dropping a forwarder instead
+    /// discards the buffered data. For a stop that writes out the buffered
+    /// data first, see [`with_stop_handle`][Self::with_stop_handle].
+    ///
+    /// The parts are recovered by moving them out of the forwarder, so it
+    /// has to be `Unpin`.
+    pub fn abortable(self) -> (Abortable<R, W, B, C>, AbortHandle) {
+        let (handle, signal) = stop_pair();
+
+        let abortable = Abortable {
+            forwarder: Some(self),
+            signal,
+        };
+
+        (abortable, AbortHandle { inner: handle })
+    }
+}
+
+impl<R, W, B, C> Future for Abortable<R, W, B, C>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+    Forwarder<R, W, B, C>: Unpin,
+{
+    type Output = Result<u64, AbortError<R, W, B>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        // Check for an abort first, so that no more I/O happens after it
+        if Pin::new(&mut this.signal).poll(cx).is_ready() {
+            let forwarder = this
+                .forwarder
+                .take()
+                .expect("Abortable polled after completion");
+
+            return Poll::Ready(Err(AbortError::Aborted(forwarder.into_parts())));
+        }
+
+        let forwarder = this
+            .forwarder
+            .as_mut()
+            .expect("Abortable polled after completion");
+
+        let result = futures::ready!(Pin::new(forwarder).poll(cx));
+        this.forwarder = None;
+        Poll::Ready(result.map_err(AbortError::Forward))
+    }
+}
+
+impl<R, W, B, C> FusedFuture for Abortable<R, W, B, C>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+    Forwarder<R, W, B, C>: Unpin,
+{
+    fn is_terminated(&self) -> bool {
+        self.forwarder.is_none()
+    }
+}
@@ -0,0 +1,65 @@
+use crate::buffer::DuplexBuffer;
+
+/// The number of consecutive polls that have to end with a full buffer
+/// before it's grown
+const GROW_AFTER_FULL_POLLS: u32 = 4;
+
+/// Grows a `Vec` buffer while it's the bottleneck, and optionally shrinks it
+/// back down while the forward is idle. The resizing is captured as function
+/// pointers, so that the forwarder doesn't need to know its buffer is a
+/// `Vec`.
+pub struct AdaptiveBuffer<B> {
+    initial: usize,
+    max: usize,
+
+    // The number of consecutive polls that ended with the buffer full
+    full_polls: u32,
+
+    pub shrink_when_idle: bool,
+
+    grow_to: fn(&mut DuplexBuffer<B>, usize),
+    shrink_to: fn(&mut DuplexBuffer<B>, usize) -> bool,
+}
+
+impl AdaptiveBuffer<Vec<u8>> {
+    pub fn new(initial: usize, max: usize) -> Self {
+        Self {
+            initial,
+            max,
+            full_polls: 0,
+            shrink_when_idle: false,
+            grow_to: DuplexBuffer::grow_to,
+            shrink_to: DuplexBuffer::shrink_to,
+        }
+    }
+}
+
+impl<B> AdaptiveBuffer<B> {
+    /// Update the buffer after a poll that left it full or not. Returns true
+    /// if the buffer grew.
+    pub fn after_poll(&mut self, buffer: &mut DuplexBuffer<B>, full: bool) -> bool {
+        if !full {
+            self.full_polls = 0;
+            return false;
+        }
+
+        self.full_polls += 1;
+
+        let capacity = buffer.capacity();
+        if self.full_polls < GROW_AFTER_FULL_POLLS || capacity >= self.max {
+            return false;
+        }
+
+        self.full_polls = 0;
+        (self.grow_to)(buffer, capacity.saturating_mul(2).clamp(1, self.max));
+        true
+    }
+
+    /// Shrink the buffer back to its initial size, if shrinking is enabled
+    /// and there's nothing buffered
+    pub fn on_idle(&mut self, buffer: &mut DuplexBuffer<B>) {
+        if self.shrink_when_idle && buffer.capacity() > self.initial {
+            (self.shrink_to)(buffer, self.initial);
+        }
+    }
+}
@@ -0,0 +1,318 @@
+#[cfg(feature = "futures-timer")]
+use std::time::Duration;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{
+    io::{ReadHalf, WriteHalf},
+    AsyncRead, AsyncReadExt, AsyncWrite,
+};
+#[cfg(feature = "futures-timer")]
+use futures_timer::Delay;
+use pin_project::pin_project;
+
+use crate::{BufferStorage, Forwarder, ForwarderError};
+
+/// One of the two directions of a [`copy_bidirectional`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Data read from the first stream and written to the second
+    AToB,
+
+    /// Data read from the second stream and written to the first
+    BToA,
+}
+
+/// An error from one direction of a [`copy_bidirectional`]
+#[derive(Debug)]
+pub struct BidirectionalError {
+    /// The direction that failed
+    pub direction: Direction,
+    pub source: ForwarderError,
+
+    /// The number of bytes written from the first stream to the second
+    /// before the copy stopped
+    pub a_to_b: u64,
+
+    /// The number of bytes written from the second stream to the first
+    /// before the copy stopped
+    pub b_to_a: u64,
+}
+
+/// What a [`copy_bidirectional`] does with the other direction when one
+/// direction fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnDirectionError {
+    /// Stop the other direction immediately
+    #[default]
+    Abort,
+
+    /// Let the other direction run to completion before reporting the error
+    Drain,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DirectionState {
+    Running,
+
+    // The direction failed or was aborted, so its writer still needs to be
+    // closed
+    Closing,
+
+    // The direction's writer has been closed, either by the forwarder after
+    // the reader's EOF, or by us after a failure
+    Finished,
+}
+
+type Half<R, W, B> = Forwarder<ReadHalf<R>, WriteHalf<W>, B>;
+
+/// Future that forwards data in both directions between two streams. Created
+/// by [`copy_bidirectional`].
+///
+/// When one direction's reader reaches EOF, that direction closes its writer
+/// once everything has been written, so that the EOF propagates; the other
+/// direction keeps going. On success, the output is the number of bytes
+/// forwarded in each direction, as `(a_to_b, b_to_a)`. On failure, the
+/// error also carries both counts.
+#[pin_project]
+pub struct CopyBidirectional<A, B, BufAB, BufBA> {
+    #[pin]
+    a_to_b: Half<A, B, BufAB>,
+
+    #[pin]
+    b_to_a: Half<B, A, BufBA>,
+
+    a_to_b_state: DirectionState,
+    b_to_a_state: DirectionState,
+
+    // The first error from either direction
+    error: Option<BidirectionalError>,
+
+    on_error: OnDirectionError,
+
+    // If false, the other direction is stopped as soon as one direction
+    // reaches EOF
+    half_close: bool,
+
+    // How long the other direction may keep going after one direction
+    // reaches EOF, and the timer for it once it's started
+    #[cfg(feature = "futures-timer")]
+    linger: Option<Duration>,
+    #[cfg(feature = "futures-timer")]
+    #[pin]
+    linger_timer: Option<Delay>,
+}
+
+/// A [`CopyBidirectional`] whose directions use the same kind of buffer, for
+/// full-duplex proxying between two streams.
+pub type BidiForwarder<A, B, Buf> = CopyBidirectional<A, B, Buf, Buf>;
+
+/// Forward data in both directions between `a` and `b`, until both
+/// directions reach EOF. Each direction has its own buffer, so they can be
+/// sized independently: a direction that carries bulk data can use a large
+/// buffer, while one that carries small requests can use a small one.
+pub fn copy_bidirectional<A, B, BufAB, BufBA>(
+    a: A,
+    b: B,
+    a_to_b_buffer: BufAB,
+    b_to_a_buffer: BufBA,
+) -> CopyBidirectional<A, B, BufAB, BufBA>
+where
+    A: AsyncRead + AsyncWrite,
+    B: AsyncRead + AsyncWrite,
+    BufAB: BufferStorage,
+    BufBA: BufferStorage,
+{
+    let (a_reader, a_writer) = a.split();
+    let (b_reader, b_writer) = b.split();
+
+    CopyBidirectional {
+        a_to_b: Forwarder::new(a_reader, b_writer, a_to_b_buffer).half_close_on_eof(true),
+        b_to_a: Forwarder::new(b_reader, a_writer, b_to_a_buffer).half_close_on_eof(true),
+        a_to_b_state: DirectionState::Running,
+        b_to_a_state: DirectionState::Running,
+        error: None,
+        on_error: OnDirectionError::Abort,
+        half_close: true,
+        #[cfg(feature = "futures-timer")]
+        linger: None,
+        #[cfg(feature = "futures-timer")]
+        linger_timer: None,
+    }
+}
+
+impl<A, B, BufAB, BufBA> CopyBidirectional<A, B, BufAB, BufBA> {
+    /// Choose what happens to the other direction when one direction fails.
+    /// Either way, both streams are closed before the error is returned.
+    /// Defaults to [`OnDirectionError::Abort`].
+    pub fn on_error(self, on_error: OnDirectionError) -> Self {
+        Self { on_error, ..self }
+    }
+
+    /// If true, when one direction reaches EOF, its EOF is propagated by
+    /// closing the peer's writer, and the other direction keeps going until
+    /// it also reaches EOF. If false, the other direction is stopped and its
+    /// writer is closed right away, so the whole copy ends with the first
+    /// EOF. Defaults to true.
+    pub fn half_close(self, half_close: bool) -> Self {
+        Self { half_close, ..self }
+    }
+
+    /// With half-close, give the other direction at most `linger` to reach
+    /// EOF after the first direction does. If it hasn't by then, it's
+    /// stopped and its writer is closed, and the copy completes
+    /// successfully.
+    #[cfg(feature = "futures-timer")]
+    pub fn linger(self, linger: Duration) -> Self {
+        Self {
+            linger: Some(linger),
+            ..self
+        }
+    }
+}
+
+/// Drive one direction of a bidirectional copy. Returns true if the direction
+/// failed.
+fn poll_direction<R, W, B>(
+    mut forwarder: Pin<&mut Half<R, W, B>>,
+    state: &mut DirectionState,
+    direction: Direction,
+    error: &mut Option<BidirectionalError>,
+    cx: &mut Context<'_>,
+) -> bool
+where
+    R: AsyncRead,
+    W: AsyncWrite,
+    B: BufferStorage,
+{
+    let mut failed = false;
+
+    if *state == DirectionState::Running {
+        match forwarder.as_mut().poll(cx) {
+            Poll::Pending => {}
+            Poll::Ready(Ok(_)) => *state = DirectionState::Finished,
+            Poll::Ready(Err(source)) => {
+                error.get_or_insert(BidirectionalError {
+                    direction,
+                    source,
+                    a_to_b: 0,
+                    b_to_a: 0,
+                });
+                *state = DirectionState::Closing;
+                failed = true;
+            }
+        }
+    }
+
+    failed
+}
+
+/// Make a best-effort attempt to close the writer of a direction that
+/// didn't finish on its own
+fn poll_close_direction<R, W, B>(
+    forwarder: Pin<&mut Half<R, W, B>>,
+    state: &mut DirectionState,
+    cx: &mut Context<'_>,
+) where
+    W: AsyncWrite,
+{
+    if *state == DirectionState::Closing && forwarder.get_writer_pin_mut().poll_close(cx).is_ready()
+    {
+        *state = DirectionState::Finished;
+    }
+}
+
+impl<A, B, BufAB, BufBA> Future for CopyBidirectional<A, B, BufAB, BufBA>
+where
+    A: AsyncRead + AsyncWrite,
+    B: AsyncRead + AsyncWrite,
+    BufAB: BufferStorage,
+    BufBA: BufferStorage,
+{
+    type Output = Result<(u64, u64), BidirectionalError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        let a_to_b_failed = poll_direction(
+            this.a_to_b.as_mut(),
+            this.a_to_b_state,
+            Direction::AToB,
+            this.error,
+            cx,
+        );
+
+        if a_to_b_failed
+            && *this.on_error == OnDirectionError::Abort
+            && *this.b_to_a_state == DirectionState::Running
+        {
+            *this.b_to_a_state = DirectionState::Closing;
+        }
+
+        let b_to_a_failed = poll_direction(
+            this.b_to_a.as_mut(),
+            this.b_to_a_state,
+            Direction::BToA,
+            this.error,
+            cx,
+        );
+
+        if b_to_a_failed
+            && *this.on_error == OnDirectionError::Abort
+            && *this.a_to_b_state == DirectionState::Running
+        {
+            *this.a_to_b_state = DirectionState::Closing;
+        }
+
+        // Once one direction has reached EOF, the other either keeps going on
+        // its own, for up to the linger time, or is stopped
+        let lingering = match (*this.a_to_b_state, *this.b_to_a_state) {
+            (DirectionState::Finished, DirectionState::Running) => Some(&mut *this.b_to_a_state),
+            (DirectionState::Running, DirectionState::Finished) => Some(&mut *this.a_to_b_state),
+            _ => None,
+        };
+
+        if let Some(state) = lingering.filter(|_| this.error.is_none()) {
+            if !*this.half_close {
+                *state = DirectionState::Closing;
+            }
+
+            #[cfg(feature = "futures-timer")]
+            if let Some(linger) = *this.linger {
+                if this.linger_timer.is_none() {
+                    this.linger_timer.set(Some(Delay::new(linger)));
+                }
+
+                if let Some(timer) = this.linger_timer.as_mut().as_pin_mut() {
+                    if timer.poll(cx).is_ready() {
+                        *state = DirectionState::Closing;
+                    }
+                }
+            }
+        }
+
+        poll_close_direction(this.a_to_b.as_mut(), this.a_to_b_state, cx);
+        poll_close_direction(this.b_to_a.as_mut(), this.b_to_a_state, cx);
+
+        if *this.a_to_b_state != DirectionState::Finished
+            || *this.b_to_a_state != DirectionState::Finished
+        {
+            return Poll::Pending;
+        }
+
+        let a_to_b = this.a_to_b.debug_state().bytes_written;
+        let b_to_a = this.b_to_a.debug_state().bytes_written;
+
+        Poll::Ready(match this.error.take() {
+            Some(error) => Err(BidirectionalError {
+                a_to_b,
+                b_to_a,
+                ..error
+            }),
+            None => Ok((a_to_b, b_to_a)),
+        })
+    }
+}
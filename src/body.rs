@@ -0,0 +1,115 @@
+use std::{
+    error::Error,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Buf;
+use futures::AsyncRead;
+use http::HeaderMap;
+use http_body::Body;
+use pin_project::pin_project;
+
+use crate::{callback::Callback, BufferStorage, Forwarder};
+
+type TrailersCallback = Callback<dyn FnMut(HeaderMap) + Send>;
+
+/// Adapts an [`http_body::Body`] into a reader, so that it can be the source
+/// of a [`Forwarder`]. Data frames are copied into the reader's buffer as
+/// they're read, and a frame that's larger than the buffer is carried over
+/// to later reads. Trailers are passed to the callback set with
+/// [`on_trailers`][Self::on_trailers], or ignored if there isn't one.
+///
+/// The body's errors are converted into `io::Error`.
+#[pin_project]
+pub struct BodyReader<Bd: Body> {
+    #[pin]
+    body: Bd,
+
+    // The unread remainder of the most recent data frame
+    chunk: Option<Bd::Data>,
+
+    on_trailers: Option<TrailersCallback>,
+}
+
+impl<Bd: Body> BodyReader<Bd> {
+    pub fn new(body: Bd) -> Self {
+        Self {
+            body,
+            chunk: None,
+            on_trailers: None,
+        }
+    }
+
+    /// Call `callback` with the body's trailers, if it has any
+    pub fn on_trailers(mut self, callback: impl FnMut(HeaderMap) + Send + 'static) -> Self {
+        self.on_trailers = Some(Callback::new(Box::new(callback)));
+        self
+    }
+
+    /// Get a reference to the body
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Bd {
+        &self.body
+    }
+}
+
+impl<Bd> AsyncRead for BodyReader<Bd>
+where
+    Bd: Body,
+    Bd::Error: Into<Box<dyn Error + Send + Sync>>,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut this = self.project();
+
+        // An empty read would look like EOF, so don't pull any frames for it
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        loop {
+            if let Some(chunk) = this.chunk.as_mut().filter(|chunk| chunk.has_remaining()) {
+                let n = chunk.remaining().min(buf.len());
+                chunk.copy_to_slice(&mut buf[..n]);
+                return Poll::Ready(Ok(n));
+            }
+
+            let frame = match futures::ready!(this.body.as_mut().poll_frame(cx)) {
+                None => return Poll::Ready(Ok(0)),
+                Some(Err(err)) => return Poll::Ready(Err(io::Error::other(err))),
+                Some(Ok(frame)) => frame,
+            };
+
+            match frame.into_data() {
+                Ok(data) => *this.chunk = Some(data),
+                Err(frame) => {
+                    if let (Ok(trailers), Some(callback)) =
+                        (frame.into_trailers(), this.on_trailers.as_mut())
+                    {
+                        (callback.get_mut())(trailers);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<Bd, W, B> Forwarder<BodyReader<Bd>, W, B>
+where
+    Bd: Body,
+    Bd::Error: Into<Box<dyn Error + Send + Sync>>,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+{
+    /// Create a new `Forwarder` that reads from an HTTP body. To handle the
+    /// body's trailers, create the [`BodyReader`] directly instead.
+    pub fn from_body(body: Bd, writer: W, buffer: B) -> Self {
+        Self::new(BodyReader::new(body), writer, buffer)
+    }
+}
@@ -1,6 +1,54 @@
-use std::num::NonZeroUsize;
+use std::{
+    io::{IoSlice, IoSliceMut},
+    mem::MaybeUninit,
+    num::NonZeroUsize,
+    ops::{Deref, DerefMut},
+    slice,
+};
+
+/// The most overflow buffers that will be used. Together with the two halves
+/// of the ring, this bounds the number of slices in a scatter read or gather
+/// write, so that they can be built without allocating.
+pub const MAX_OVERFLOW_BUFFERS: usize = 14;
+
+/// A fixed-capacity list of I/O slices for a scatter read or gather write
+pub struct IoSlices<T> {
+    slices: [T; MAX_OVERFLOW_BUFFERS + 2],
+    len: usize,
+}
+
+impl<T> IoSlices<T> {
+    pub fn collect(iter: impl IntoIterator<Item = T>, mut empty: impl FnMut() -> T) -> Self {
+        let mut slices: [T; MAX_OVERFLOW_BUFFERS + 2] = std::array::from_fn(|_| empty());
+        let mut len = 0;
+
+        for (dest, slice) in slices.iter_mut().zip(iter) {
+            *dest = slice;
+            len += 1;
+        }
+
+        Self { slices, len }
+    }
+}
+
+impl<T> Deref for IoSlices<T> {
+    type Target = [T];
+
+    #[inline]
+    fn deref(&self) -> &[T] {
+        &self.slices[..self.len]
+    }
+}
+
+impl<T> DerefMut for IoSlices<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.slices[..self.len]
+    }
+}
 
 /// Indexes into a single shared buffer
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Copy, Clone, Default)]
 enum BufferHeads {
     #[default]
@@ -72,6 +120,21 @@ impl BufferHeads {
         }
     }
 
+    /// The number of bytes that have been read into the buffer but not yet
+    /// written out of it
+    #[inline]
+    #[must_use]
+    pub fn buffered(&self, max: usize) -> usize {
+        match *self {
+            BufferHeads::ReadReady => 0,
+            BufferHeads::WriteReady(_) => max,
+            BufferHeads::DuplexReady {
+                write_head,
+                read_head,
+            } => (read_head + max - write_head) % max,
+        }
+    }
+
     #[inline]
     #[must_use]
     pub fn read_ready(&self) -> bool {
@@ -91,10 +154,184 @@ impl BufferHeads {
     }
 }
 
-#[derive(Default, Clone, Copy)]
+/// The backing memory for a ring buffer. This is implemented for anything
+/// that is `AsMut<[u8]>`, including borrowed slices like `&mut [u8]`, as
+/// well as for [`UninitBuffer`], which avoids zeroing its memory up front.
+pub trait BufferStorage {
+    /// The total size of the backing memory. This must not change.
+    fn capacity(&mut self) -> usize;
+
+    /// Get the backing memory as bytes. The returned slice must be at least
+    /// `min_len` long, but may be shorter than the capacity, if the rest of
+    /// the memory hasn't been initialized yet.
+    fn as_bytes(&mut self, min_len: usize) -> &mut [u8];
+}
+
+impl<T: AsMut<[u8]>> BufferStorage for T {
+    #[inline]
+    fn capacity(&mut self) -> usize {
+        self.as_mut().len()
+    }
+
+    #[inline]
+    fn as_bytes(&mut self, _min_len: usize) -> &mut [u8] {
+        self.as_mut()
+    }
+}
+
+/// A heap-allocated buffer that isn't zeroed when it's allocated. Instead,
+/// it's initialized incrementally, as the ring actually needs to read data
+/// into it, so a large buffer that only ever sees a little data is never
+/// fully zeroed.
+pub struct UninitBuffer {
+    buffer: Box<[MaybeUninit<u8>]>,
+
+    // The length of the prefix of `buffer` that has been initialized
+    initialized: usize,
+}
+
+impl UninitBuffer {
+    /// The minimum number of bytes we initialize at a time, so that we don't
+    /// hand tiny slices to readers
+    const MIN_INIT: usize = 4096;
+
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buffer: Box::new_uninit_slice(capacity),
+            initialized: 0,
+        }
+    }
+}
+
+impl std::fmt::Debug for UninitBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UninitBuffer")
+            .field("capacity", &self.buffer.len())
+            .field("initialized", &self.initialized)
+            .finish()
+    }
+}
+
+impl BufferStorage for UninitBuffer {
+    #[inline]
+    fn capacity(&mut self) -> usize {
+        self.buffer.len()
+    }
+
+    fn as_bytes(&mut self, min_len: usize) -> &mut [u8] {
+        let capacity = self.buffer.len();
+        assert!(
+            min_len <= capacity,
+            "requested more than the buffer capacity"
+        );
+
+        if min_len > self.initialized {
+            // Grow the initialized region geometrically, so that the total
+            // cost of initialization is linear in the amount of the buffer
+            // that's actually used.
+            let target = min_len
+                .max(self.initialized * 2)
+                .max(Self::MIN_INIT)
+                .min(capacity);
+
+            self.buffer[self.initialized..target].fill(MaybeUninit::new(0));
+            self.initialized = target;
+        }
+
+        // SAFETY: the first `initialized` bytes of the buffer have been
+        // initialized, either just now or in a previous call, and
+        // MaybeUninit<u8> has the same layout as u8. We only ever write
+        // initialized bytes through the returned slice.
+        unsafe { slice::from_raw_parts_mut(self.buffer.as_mut_ptr().cast(), self.initialized) }
+    }
+}
+
+/// Extra buffers that a read can scatter into after the ring's own read
+/// region. Data in the overflow is logically *after* everything in the ring,
+/// so the ring doesn't accept any more reads until the overflow has been
+/// fully written out.
+#[derive(Debug, Clone, Default)]
+struct Overflow {
+    buffers: Vec<Box<[u8]>>,
+    capacity: usize,
+
+    // The overflow is treated as a single linear region spanning all the
+    // buffers; data occupies `consumed..filled` in that region
+    filled: usize,
+    consumed: usize,
+}
+
+impl Overflow {
+    #[inline]
+    fn len(&self) -> usize {
+        self.filled - self.consumed
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.filled == self.consumed
+    }
+
+    /// All of the data in the overflow, in order
+    fn filled_slices(&self) -> impl Iterator<Item = &[u8]> {
+        let (consumed, filled) = (self.consumed, self.filled);
+        let mut offset = 0;
+
+        self.buffers.iter().filter_map(move |buffer| {
+            let start = offset;
+            offset += buffer.len();
+
+            let from = consumed.clamp(start, offset) - start;
+            let to = filled.clamp(start, offset) - start;
+
+            (from < to).then(|| &buffer[from..to])
+        })
+    }
+
+    /// Call `f` on each contiguous region of the last `len` bytes of data
+    fn for_each_newest_mut(&mut self, len: usize, mut f: impl FnMut(&mut [u8])) {
+        let (newest, filled) = (self.filled - len, self.filled);
+        let mut offset = 0;
+
+        for buffer in &mut self.buffers {
+            let start = offset;
+            offset += buffer.len();
+
+            let from = newest.clamp(start, offset) - start;
+            let to = filled.clamp(start, offset) - start;
+
+            if from < to {
+                f(&mut buffer[from..to]);
+            }
+        }
+    }
+
+    fn advance_read(&mut self, amount: usize) {
+        self.filled += amount;
+        debug_assert!(self.filled <= self.capacity);
+    }
+
+    fn advance_write(&mut self, amount: usize) {
+        self.consumed += amount;
+        debug_assert!(self.consumed <= self.filled);
+
+        if self.is_empty() {
+            self.filled = 0;
+            self.consumed = 0;
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct DuplexBuffer<B> {
     buffer: B,
     heads: BufferHeads,
+
+    // The capacity of `buffer`, which only changes when a `Vec` buffer is
+    // explicitly grown or shrunk
+    capacity: usize,
+
+    overflow: Overflow,
 }
 
 /// A pair of pairs of buffers representing the current state
@@ -104,6 +341,32 @@ pub struct Buffers<'a> {
     pub write: [&'a [u8]; 2],
 }
 
+impl Buffers<'_> {
+    /// The total space available to read data into
+    #[inline]
+    #[must_use]
+    pub fn readable_len(&self) -> usize {
+        let [b1, b2] = &self.read;
+        pair_len(&[b1, b2])
+    }
+
+    /// The total amount of buffered data available to be written out
+    #[inline]
+    #[must_use]
+    pub fn writable_len(&self) -> usize {
+        pair_len(&self.write)
+    }
+}
+
+/// Cut `slice` short to at most `remaining` bytes, and take its length out
+/// of `remaining`
+#[inline]
+pub fn truncate_mut<'a>(slice: &'a mut [u8], remaining: &mut usize) -> &'a mut [u8] {
+    let len = slice.len().min(*remaining);
+    *remaining -= len;
+    &mut slice[..len]
+}
+
 /// Split a buffer into 3 units, at the given points: [..point1], [point1..point2], [point2..]
 #[inline]
 fn split_thrice(buffer: &mut [u8], point1: usize, point2: usize) -> [&mut [u8]; 3] {
@@ -119,77 +382,600 @@ fn split_thrice(buffer: &mut [u8], point1: usize, point2: usize) -> [&mut [u8];
     [b1, b2, b3]
 }
 
-impl<B: AsMut<[u8]>> DuplexBuffer<B> {
-    pub fn new(buffer: B) -> Self {
+/// Get the read and write regions of a ring buffer with the given heads.
+/// This is a free function, rather than a method on `DuplexBuffer`, so that
+/// it can borrow just the storage.
+fn get_buffers<B: BufferStorage>(
+    buffer: &mut B,
+    heads: BufferHeads,
+    capacity: usize,
+) -> Buffers<'_> {
+    // The storage may not be fully initialized, so figure out how much of
+    // it we need: all of the buffered data, plus at least some room to
+    // read. If we get less than the full capacity, the read region is
+    // truncated to what we got.
+    let min_len = match heads {
+        BufferHeads::ReadReady => capacity.min(1),
+        BufferHeads::DuplexReady {
+            write_head,
+            read_head,
+        } if write_head < read_head => read_head + 1,
+        _ => capacity,
+    };
+
+    let buffer = buffer.as_bytes(min_len);
+    let len = buffer.len();
+
+    match heads {
+        BufferHeads::ReadReady => Buffers {
+            read: [buffer, &mut []],
+            write: [&[], &[]],
+        },
+        BufferHeads::WriteReady(point) => {
+            let (head, tail) = buffer.split_at(point);
+            Buffers {
+                read: [&mut [], &mut []],
+                write: [tail, head],
+            }
+        }
+        BufferHeads::DuplexReady {
+            write_head,
+            read_head,
+        } => {
+            // DuplexReady is only constructed when the heads differ; equal
+            // heads here would mean a bug in the BufferHeads transitions,
+            // and would produce a silently wrong split.
+            debug_assert!(write_head != read_head);
+
+            let [b1, b2, b3] = split_thrice(buffer, write_head, read_head);
+            match write_head < read_head {
+                // If the buffer was truncated, the read region doesn't
+                // reach the end of the buffer, so it can't wrap around.
+                true => Buffers {
+                    read: [b3, if len == capacity { b1 } else { &mut [] }],
+                    write: [b2, &[]],
+                },
+                false => Buffers {
+                    read: [b2, &mut []],
+                    write: [b3, b1],
+                },
+            }
+        }
+    }
+}
+
+impl<B: BufferStorage> DuplexBuffer<B> {
+    pub fn new(mut buffer: B) -> Self {
         Self {
+            capacity: buffer.capacity(),
             buffer,
             heads: BufferHeads::default(),
+            overflow: Overflow::default(),
+        }
+    }
+
+    /// Add extra buffers that reads can scatter into once the ring's read
+    /// region is full. Only the first [`MAX_OVERFLOW_BUFFERS`] are used.
+    pub fn set_overflow(&mut self, mut buffers: Vec<Box<[u8]>>) {
+        debug_assert!(self.overflow.is_empty());
+        buffers.truncate(MAX_OVERFLOW_BUFFERS);
+
+        self.overflow = Overflow {
+            capacity: buffers.iter().map(|buffer| buffer.len()).sum(),
+            buffers,
+            filled: 0,
+            consumed: 0,
+        };
+    }
+
+    /// Get all of the regions a scatter read should read into: the ring's
+    /// read region, followed by the overflow buffers, along with the length
+    /// of the ring's part. If there's a `limit`, the regions are cut short
+    /// after that many bytes. These are only available while the overflow
+    /// is empty; otherwise, no reads are possible.
+    pub fn scatter_read_slices(
+        &mut self,
+        limit: Option<usize>,
+    ) -> (usize, IoSlices<IoSliceMut<'_>>) {
+        if !self.overflow.is_empty() {
+            return (0, IoSlices::collect([], || IoSliceMut::new(&mut [])));
         }
+
+        let mut remaining = limit.unwrap_or(usize::MAX);
+        let [b1, b2] = get_buffers(&mut self.buffer, self.heads, self.capacity).read;
+        let [b1, b2] = [b1, b2].map(|slice| truncate_mut(slice, &mut remaining));
+        let ring_len = b1.len() + b2.len();
+
+        let slices = IoSlices::collect(
+            [b1, b2]
+                .into_iter()
+                .chain(
+                    self.overflow
+                        .buffers
+                        .iter_mut()
+                        .map(|buffer| truncate_mut(buffer, &mut remaining)),
+                )
+                .filter(|slice| !slice.is_empty())
+                .map(IoSliceMut::new),
+            || IoSliceMut::new(&mut []),
+        );
+
+        (ring_len, slices)
+    }
+
+    /// Advance after a scatter read of `amount` bytes, where the ring's part
+    /// of the read was `ring_len` bytes long.
+    pub fn advance_scatter_read(&mut self, amount: NonZeroUsize, ring_len: usize) {
+        if let Some(ring_amount) = NonZeroUsize::new(amount.get().min(ring_len)) {
+            self.advance_read(ring_amount);
+        }
+
+        self.overflow
+            .advance_read(amount.get().saturating_sub(ring_len));
+    }
+
+    /// Copy out all of the buffered data, in order
+    pub fn buffered_data(&mut self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.buffered_len());
+
+        for slice in self.gather_write_slices().iter() {
+            data.extend_from_slice(slice);
+        }
+
+        data
+    }
+
+    /// Get all of the buffered data, from the ring and then the overflow,
+    /// for a gather write.
+    pub fn gather_write_slices(&mut self) -> IoSlices<IoSlice<'_>> {
+        let [b1, b2] = get_buffers(&mut self.buffer, self.heads, self.capacity).write;
+
+        IoSlices::collect(
+            [b1, b2]
+                .into_iter()
+                .chain(self.overflow.filled_slices())
+                .filter(|slice| !slice.is_empty())
+                .map(IoSlice::new),
+            || IoSlice::new(&[]),
+        )
     }
 }
 
-impl<B: AsMut<[u8]>> DuplexBuffer<B> {
+impl<B: BufferStorage> DuplexBuffer<B> {
     pub fn get_buffers(&mut self) -> Buffers<'_> {
-        let buffer = self.buffer.as_mut();
+        get_buffers(&mut self.buffer, self.heads, self.capacity)
+    }
 
-        match self.heads {
-            BufferHeads::ReadReady => Buffers {
-                read: [buffer, &mut []],
-                write: [&[], &[]],
-            },
-            BufferHeads::WriteReady(point) => {
-                let (head, tail) = buffer.split_at(point);
-                Buffers {
-                    read: [&mut [], &mut []],
-                    write: [tail, head],
-                }
-            }
-            BufferHeads::DuplexReady {
-                write_head,
-                read_head,
-            } => {
-                let [b1, b2, b3] = split_thrice(buffer, write_head, read_head);
-                match write_head < read_head {
-                    true => Buffers {
-                        read: [b3, b1],
-                        write: [b2, &[]],
-                    },
-                    false => Buffers {
-                        read: [b2, &mut []],
-                        write: [b3, b1],
-                    },
+    /// Call `f` on each contiguous region of the most recently read `len`
+    /// bytes, in order. The data may be split across the end of the ring,
+    /// and into the overflow. `len` must be no more than what's buffered.
+    pub fn for_each_newest_mut(&mut self, len: usize, mut f: impl FnMut(&mut [u8])) {
+        debug_assert!(len <= self.buffered_len());
+
+        // The overflow holds the newest data, if there's any in it
+        let overflow_len = len.min(self.overflow.len());
+        let ring_len = len - overflow_len;
+
+        if ring_len > 0 {
+            // The newest data in the ring ends where the next read will go
+            let end = match self.heads {
+                BufferHeads::ReadReady => unreachable!("newest data of an empty ring"),
+                BufferHeads::WriteReady(point) => point,
+                BufferHeads::DuplexReady { read_head, .. } => read_head,
+            };
+
+            match end.checked_sub(ring_len) {
+                Some(start) => f(&mut self.buffer.as_bytes(end)[start..end]),
+
+                // The data wraps around the end of the ring
+                None => {
+                    let start = self.capacity - (ring_len - end);
+                    let buffer = self.buffer.as_bytes(self.capacity);
+                    let (head, tail) = buffer.split_at_mut(end);
+
+                    f(&mut tail[start - end..self.capacity - end]);
+
+                    if !head.is_empty() {
+                        f(head);
+                    }
                 }
             }
         }
+
+        if overflow_len > 0 {
+            self.overflow.for_each_newest_mut(overflow_len, f);
+        }
+    }
+
+    /// Copy all of `data` into the read region of the buffer, as though it
+    /// had been read. Returns false, without copying anything, if there
+    /// isn't room for all of it.
+    pub fn prefill(&mut self, mut data: &[u8]) -> bool {
+        if data.len() > self.capacity - self.buffered_len() {
+            return false;
+        }
+
+        // The storage might only expose part of its memory at a time, so
+        // this can take a few rounds
+        while !data.is_empty() {
+            let n = self.fill(data);
+            debug_assert!(n > 0);
+            data = &data[n..];
+        }
+
+        true
+    }
+
+    /// Rotate the ring so that all of the buffered data starts at offset 0,
+    /// in a single contiguous span. Afterwards, `get_buffers().write[0]`
+    /// holds everything in the ring and `write[1]` is empty. This doesn't
+    /// affect the overflow.
+    pub fn compact(&mut self) {
+        let write_head = match self.heads {
+            BufferHeads::ReadReady => return,
+            BufferHeads::WriteReady(point) => point,
+            BufferHeads::DuplexReady { write_head, .. } => write_head,
+        };
+
+        let buffered = self.heads.buffered(self.capacity);
+
+        // Compacting touches the whole ring anyway, so it's fine to make the
+        // storage initialize all of it
+        self.buffer.as_bytes(self.capacity)[..self.capacity].rotate_left(write_head);
+
+        self.heads = match buffered == self.capacity {
+            true => BufferHeads::WriteReady(0),
+            false => BufferHeads::DuplexReady {
+                write_head: 0,
+                read_head: buffered,
+            },
+        };
+    }
+
+    /// Copy as much of `data` as will fit into the read region of the
+    /// buffer, advancing the read head past it. Returns the number of bytes
+    /// copied.
+    pub fn fill(&mut self, data: &[u8]) -> usize {
+        let [b1, b2] = self.get_buffers().read;
+
+        let n1 = data.len().min(b1.len());
+        b1[..n1].copy_from_slice(&data[..n1]);
+
+        let data = &data[n1..];
+        let n2 = data.len().min(b2.len());
+        b2[..n2].copy_from_slice(&data[..n2]);
+
+        if let Some(amount) = NonZeroUsize::new(n1 + n2) {
+            self.advance_read(amount);
+        }
+
+        n1 + n2
+    }
+}
+
+impl DuplexBuffer<Vec<u8>> {
+    /// Grow the ring to `capacity`, keeping the buffered data. The data is
+    /// compacted to the front, so that all of the new space is free space
+    /// after it.
+    pub fn grow_to(&mut self, capacity: usize) {
+        debug_assert!(capacity >= self.capacity);
+
+        self.compact();
+        self.buffer.resize(capacity, 0);
+
+        let buffered = self.heads.buffered(self.capacity);
+        self.capacity = capacity;
+
+        if buffered > 0 {
+            self.heads = BufferHeads::DuplexReady {
+                write_head: 0,
+                read_head: buffered,
+            };
+        }
+    }
+
+    /// Shrink the ring to `capacity`, releasing its memory. Returns false,
+    /// without doing anything, if there's buffered data.
+    pub fn shrink_to(&mut self, capacity: usize) -> bool {
+        if self.heads.write_ready() {
+            return false;
+        }
+
+        self.buffer.truncate(capacity);
+        self.buffer.shrink_to_fit();
+        self.capacity = self.buffer.len();
+        true
+    }
+}
+
+impl<B> DuplexBuffer<B> {
+    /// Give up the ring's backing memory. Any buffered data is left in it,
+    /// and anything in the overflow is lost.
+    #[inline]
+    pub fn into_inner(self) -> B {
+        self.buffer
     }
 
     /// Returns true if we're able to read more data into the buffers
     #[inline]
     #[must_use]
     pub fn read_ready(&self) -> bool {
-        self.heads.read_ready()
+        // Reads into the ring have to wait until the overflow is drained, so
+        // that data stays in order. An empty ring is never ready, since a
+        // zero-length read would look like EOF.
+        self.overflow.is_empty()
+            && ((self.capacity > 0 && self.heads.read_ready()) || self.has_overflow())
     }
 
     /// Returns true if we're able to write more data out of the buffers
     #[inline]
     #[must_use]
     pub fn write_ready(&self) -> bool {
-        self.heads.write_ready()
+        self.heads.write_ready() || !self.overflow.is_empty()
+    }
+
+    /// The number of bytes that have been read into the buffer but not yet
+    /// written out of it
+    #[inline]
+    #[must_use]
+    pub fn buffered_len(&self) -> usize {
+        self.heads.buffered(self.capacity) + self.overflow.len()
+    }
+
+    /// The total size of the buffer
+    #[inline]
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The current offsets of the read and write heads, which are the points
+    /// where the next read into or write out of the buffer will happen. The
+    /// read head is `None` if the buffer is full, and the write head is
+    /// `None` if it's empty.
+    #[inline]
+    #[must_use]
+    pub fn heads(&self) -> (Option<usize>, Option<usize>) {
+        match self.heads {
+            BufferHeads::ReadReady => (Some(0), None),
+            BufferHeads::WriteReady(point) => (None, Some(point)),
+            BufferHeads::DuplexReady {
+                write_head,
+                read_head,
+            } => (Some(read_head), Some(write_head)),
+        }
     }
 
     #[inline]
     pub fn advance_read(&mut self, amount: NonZeroUsize) {
-        self.heads = self.heads.advance_read(amount, self.buffer.as_mut().len())
+        self.heads = self.heads.advance_read(amount, self.capacity)
     }
 
+    /// Advance past `amount` bytes that were written out of the buffer. The
+    /// data in the ring is always written before the data in the overflow.
     #[inline]
     pub fn advance_write(&mut self, amount: NonZeroUsize) {
-        self.heads = self.heads.advance_write(amount, self.buffer.as_mut().len())
+        let ring_buffered = self.heads.buffered(self.capacity);
+
+        if let Some(ring_amount) = NonZeroUsize::new(amount.get().min(ring_buffered)) {
+            self.heads = self.heads.advance_write(ring_amount, self.capacity)
+        }
+
+        if amount.get() > ring_buffered {
+            self.overflow.advance_write(amount.get() - ring_buffered);
+        }
+    }
+
+    /// True if this buffer has any overflow capacity
+    #[inline]
+    #[must_use]
+    pub fn has_overflow(&self) -> bool {
+        self.overflow.capacity > 0
     }
 }
 
+/// The total length of a pair of slices, such as the two halves of a wrapped
+/// ring buffer
 #[inline]
 #[must_use]
 pub const fn pair_len(&[b1, b2]: &[&[u8]; 2]) -> usize {
     b1.len() + b2.len()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_thrice_equal_points_at_start() {
+        let mut buffer = [1, 2, 3, 4];
+        let [b1, b2, b3] = split_thrice(&mut buffer, 0, 0);
+
+        assert_eq!(b1, &[]);
+        assert_eq!(b2, &[]);
+        assert_eq!(b3, &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn split_thrice_equal_points_at_end() {
+        let mut buffer = [1, 2, 3, 4];
+        let [b1, b2, b3] = split_thrice(&mut buffer, 4, 4);
+
+        assert_eq!(b1, &[1, 2, 3, 4]);
+        assert_eq!(b2, &[]);
+        assert_eq!(b3, &[]);
+    }
+
+    #[test]
+    fn split_thrice_equal_points_in_middle() {
+        let mut buffer = [1, 2, 3, 4];
+        let [b1, b2, b3] = split_thrice(&mut buffer, 2, 2);
+
+        assert_eq!(b1, &[1, 2]);
+        assert_eq!(b2, &[]);
+        assert_eq!(b3, &[3, 4]);
+    }
+
+    #[test]
+    fn split_thrice_unordered_points() {
+        let mut buffer = [1, 2, 3, 4];
+        let [b1, b2, b3] = split_thrice(&mut buffer, 3, 1);
+
+        assert_eq!(b1, &[1]);
+        assert_eq!(b2, &[2, 3]);
+        assert_eq!(b3, &[4]);
+    }
+
+    #[test]
+    fn duplex_heads_never_equal() {
+        let max = 8;
+        let mut heads = BufferHeads::default();
+
+        // Walk the heads through a variety of transitions, including wraps,
+        // and make sure we never build a DuplexReady with equal heads.
+        for step in 1..200 {
+            let amount = NonZeroUsize::new(step % 5 + 1).unwrap();
+
+            heads = match step % 3 {
+                0 if heads.write_ready() => {
+                    let available = heads.buffered(max);
+                    heads.advance_write(amount.min(NonZeroUsize::new(available).unwrap()), max)
+                }
+                _ if heads.read_ready() => {
+                    let available = max - heads.buffered(max);
+                    heads.advance_read(amount.min(NonZeroUsize::new(available).unwrap()), max)
+                }
+                _ => heads,
+            };
+
+            if let BufferHeads::DuplexReady {
+                write_head,
+                read_head,
+            } = heads
+            {
+                assert_ne!(write_head, read_head);
+            }
+        }
+    }
+
+    #[test]
+    fn uninit_buffer_initializes_incrementally() {
+        let mut buffer = DuplexBuffer::new(UninitBuffer::new(100_000));
+
+        assert_eq!(buffer.fill(b"hello"), 5);
+        assert_eq!(buffer.buffer.initialized, UninitBuffer::MIN_INIT);
+        assert_eq!(buffer.get_buffers().write, [&b"hello"[..], &[]]);
+
+        // Filling past the initialized region initializes more of it
+        let data = vec![1; 10_000];
+        assert_eq!(buffer.fill(&data), UninitBuffer::MIN_INIT - 5);
+        assert_eq!(buffer.buffer.initialized, UninitBuffer::MIN_INIT);
+        assert_eq!(buffer.fill(&data), UninitBuffer::MIN_INIT);
+        assert_eq!(buffer.buffer.initialized, UninitBuffer::MIN_INIT * 2);
+        assert_eq!(buffer.buffered_len(), UninitBuffer::MIN_INIT * 2);
+    }
+
+    #[test]
+    fn partial_write_across_wrap() {
+        let mut buffer = DuplexBuffer::new([0; 8]);
+
+        // Wrap the buffered data around the end of the ring
+        assert_eq!(buffer.fill(b"abcdefgh"), 8);
+        buffer.advance_write(NonZeroUsize::new(6).unwrap());
+        assert_eq!(buffer.fill(b"ijkl"), 4);
+        assert_eq!(buffer.get_buffers().write, [&b"gh"[..], &b"ijkl"[..]]);
+
+        // A write that takes the whole tail and part of the head
+        buffer.advance_write(NonZeroUsize::new(3).unwrap());
+        assert_eq!(buffer.heads(), (Some(4), Some(1)));
+        assert_eq!(buffer.get_buffers().write, [&b"jkl"[..], &[]]);
+
+        // The space freed on both sides of the wrap is readable again
+        let [r1, r2] = buffer.get_buffers().read;
+        assert_eq!((r1.len(), r2.len()), (4, 1));
+
+        buffer.advance_write(NonZeroUsize::new(3).unwrap());
+        assert_eq!(buffer.heads(), (Some(0), None));
+        assert_eq!(buffer.buffered_len(), 0);
+    }
+
+    #[test]
+    fn compact_wrapped_data() {
+        let mut buffer = DuplexBuffer::new([0; 8]);
+
+        assert_eq!(buffer.fill(b"abcdefgh"), 8);
+        buffer.advance_write(NonZeroUsize::new(6).unwrap());
+        assert_eq!(buffer.fill(b"ijk"), 3);
+
+        buffer.compact();
+        assert_eq!(buffer.heads(), (Some(5), Some(0)));
+        assert_eq!(buffer.get_buffers().write, [&b"ghijk"[..], &[]]);
+
+        // The free space is contiguous too
+        let [r1, r2] = buffer.get_buffers().read;
+        assert_eq!((r1.len(), r2.len()), (3, 0));
+    }
+
+    #[test]
+    fn compact_full_buffer() {
+        let mut buffer = DuplexBuffer::new(UninitBuffer::new(4));
+
+        assert_eq!(buffer.fill(b"abcd"), 4);
+        buffer.advance_write(NonZeroUsize::new(1).unwrap());
+        assert_eq!(buffer.fill(b"e"), 1);
+
+        buffer.compact();
+        assert_eq!(buffer.heads(), (None, Some(0)));
+        assert_eq!(buffer.get_buffers().write, [&b"bcde"[..], &[]]);
+    }
+
+    #[test]
+    fn partial_write_exactly_to_wrap() {
+        let mut buffer = DuplexBuffer::new([0; 8]);
+
+        assert_eq!(buffer.fill(b"abcdefgh"), 8);
+        buffer.advance_write(NonZeroUsize::new(6).unwrap());
+        assert_eq!(buffer.fill(b"ijkl"), 4);
+
+        // A write that ends exactly at the end of the ring
+        buffer.advance_write(NonZeroUsize::new(2).unwrap());
+        assert_eq!(buffer.heads(), (Some(4), Some(0)));
+        assert_eq!(buffer.get_buffers().write, [&b"ijkl"[..], &[]]);
+    }
+
+    #[test]
+    fn newest_data_wraps_around_the_ring() {
+        let mut buffer = DuplexBuffer::new([0u8; 8]);
+        buffer.fill(&[1, 2, 3, 4, 5, 6]);
+        buffer.advance_write(NonZeroUsize::new(5).unwrap());
+        buffer.fill(&[7, 8, 9, 10]);
+
+        let mut chunks = Vec::new();
+        buffer.for_each_newest_mut(5, |chunk| chunks.push(chunk.to_vec()));
+        assert_eq!(chunks, [vec![6, 7, 8], vec![9, 10]]);
+
+        let mut chunks = Vec::new();
+        buffer.for_each_newest_mut(2, |chunk| chunks.push(chunk.to_vec()));
+        assert_eq!(chunks, [vec![9, 10]]);
+    }
+
+    #[test]
+    fn newest_data_spills_into_the_overflow() {
+        let mut buffer = DuplexBuffer::new([0u8; 4]);
+        buffer.set_overflow(vec![Box::new([0; 2]), Box::new([0; 4])]);
+
+        let (ring_len, mut slices) = buffer.scatter_read_slices(None);
+        let mut data = 1..;
+        for slice in slices.iter_mut() {
+            slice.fill_with(|| data.next().unwrap());
+        }
+        buffer.advance_scatter_read(NonZeroUsize::new(7).unwrap(), ring_len);
+
+        let mut chunks = Vec::new();
+        buffer.for_each_newest_mut(5, |chunk| {
+            chunk.reverse();
+            chunks.push(chunk.to_vec())
+        });
+        assert_eq!(chunks, [vec![4, 3], vec![6, 5], vec![7]]);
+    }
+}
@@ -0,0 +1,312 @@
+use std::num::{NonZeroU32, NonZeroUsize};
+
+use futures::{AsyncRead, AsyncWrite};
+
+use crate::{vectored::DEFAULT_MAX_IOV, BufferStorage, Forwarder, UninitBuffer};
+
+/// The size of the buffer allocated by [`ForwarderBuilder::start`], unless
+/// it's overridden with [`ForwarderBuilder::buffer_size`]
+const DEFAULT_BUFFER_SIZE: usize = 8 * 1024;
+
+/// The configurable behavior of a [`Forwarder`]. This is kept separate from
+/// the I/O state so that it can be built up ahead of time and reused.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub max_iov: NonZeroUsize,
+    pub min_read: usize,
+    pub flush_policy: FlushPolicy,
+    pub yield_budget: Option<NonZeroU32>,
+    pub half_close_on_eof: bool,
+    pub writer_close: WriterClosePolicy,
+    pub strict_write_accounting: bool,
+    pub flush_on_eof: bool,
+    pub flush_on_read_stall: bool,
+    pub read_limit: Option<u64>,
+    pub exact: bool,
+    pub max_write_size: Option<NonZeroUsize>,
+    pub max_stalled_polls: Option<NonZeroU32>,
+}
+
+/// When to flush the writer while data is being forwarded. Whatever the
+/// policy, a flush happens at the end of a poll, after the writes it made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlushPolicy {
+    /// Don't flush while forwarding
+    #[default]
+    Never,
+
+    /// Flush after every poll that wrote some data, so that it reaches its
+    /// destination as soon as possible. This trades throughput for latency.
+    EveryWrite,
+
+    /// Flush once at least this many bytes have been written since the last
+    /// flush
+    AfterBytes(u64),
+}
+
+impl FlushPolicy {
+    /// Returns true if, with `unflushed` bytes written since the last flush,
+    /// it's time to flush
+    #[inline]
+    #[must_use]
+    pub(crate) fn is_due(self, unflushed: u64) -> bool {
+        match self {
+            FlushPolicy::Never => false,
+            FlushPolicy::EveryWrite => unflushed > 0,
+            FlushPolicy::AfterBytes(bytes) => unflushed > 0 && unflushed >= bytes,
+        }
+    }
+}
+
+/// What to do when the writer stops accepting data, by returning `Ok(0)`
+/// from a write
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WriterClosePolicy {
+    /// Always fail with
+    /// [`ForwarderError::WriteClosedEarly`][crate::ForwarderError::WriteClosedEarly]
+    #[default]
+    Error,
+
+    /// If the writer closes after everything that was buffered before the
+    /// most recent read has been written, while the reader is still live,
+    /// treat it as the consumer asking us to stop, and complete
+    /// successfully. The data from the most recent read isn't written, but
+    /// it's left in the buffer, where it can be recovered with
+    /// [`Forwarder::into_parts`][crate::Forwarder::into_parts]. If the
+    /// writer closes partway through the buffered data, or after the reader
+    /// has reached EOF, that's still an error.
+    GracefulWhenDrained,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_iov: DEFAULT_MAX_IOV,
+            min_read: 0,
+            flush_policy: FlushPolicy::Never,
+            yield_budget: None,
+            half_close_on_eof: false,
+            writer_close: WriterClosePolicy::Error,
+            strict_write_accounting: false,
+            flush_on_eof: false,
+            flush_on_read_stall: false,
+            read_limit: None,
+            exact: false,
+            max_write_size: None,
+            max_stalled_polls: None,
+        }
+    }
+}
+
+/// Errors from [`ForwarderBuilder::build`], for invalid or conflicting
+/// configurations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// The buffer has no capacity, so nothing could ever be forwarded
+    EmptyBuffer,
+
+    /// The read aggregation threshold can never be reached, because it's
+    /// larger than the buffer.
+    ReadAggregationTooLarge { min_read: usize, capacity: usize },
+}
+
+/// Builder for a [`Forwarder`], collecting all of its configuration in one
+/// place. The same builder can be used as a template to build any number of
+/// forwarders, each with its own I/O and buffer.
+#[derive(Debug, Clone)]
+pub struct ForwarderBuilder {
+    config: Config,
+
+    // The size of the buffers allocated by `start`
+    buffer_size: usize,
+
+    // Stored as a duration rather than an instant, so that each forwarder
+    // built from this template gets its own deadline
+    #[cfg(feature = "futures-timer")]
+    timeout: Option<std::time::Duration>,
+}
+
+impl Default for ForwarderBuilder {
+    fn default() -> Self {
+        Self {
+            config: Config::default(),
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            #[cfg(feature = "futures-timer")]
+            timeout: None,
+        }
+    }
+}
+
+impl ForwarderBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the size of the buffer that [`start`][Self::start] allocates for
+    /// each forwarder. Defaults to 8 KiB.
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = size;
+        self
+    }
+
+    /// Set the maximum number of slices passed to a single vectored write.
+    /// Defaults to 1024, which is a common `IOV_MAX`.
+    pub fn max_iov(mut self, max_iov: NonZeroUsize) -> Self {
+        self.config.max_iov = max_iov;
+        self
+    }
+
+    /// Delay writes until at least `min_read` bytes have been buffered. See
+    /// [`Forwarder::with_read_aggregation`].
+    pub fn read_aggregation(mut self, min_read: usize) -> Self {
+        self.config.min_read = min_read;
+        self
+    }
+
+    /// Flush the writer after every poll that wrote data. See
+    /// [`Forwarder::eager_flush`].
+    pub fn eager_flush(self, eager_flush: bool) -> Self {
+        self.flush_policy(match eager_flush {
+            true => FlushPolicy::EveryWrite,
+            false => FlushPolicy::Never,
+        })
+    }
+
+    /// Choose when the writer is flushed while data is being forwarded. See
+    /// [`Forwarder::with_flush_policy`].
+    pub fn flush_policy(mut self, policy: FlushPolicy) -> Self {
+        self.config.flush_policy = policy;
+        self
+    }
+
+    /// Yield to the executor after every `ops` reads and writes. See
+    /// [`Forwarder::with_yield_budget`].
+    pub fn yield_budget(mut self, ops: NonZeroU32) -> Self {
+        self.config.yield_budget = Some(ops);
+        self
+    }
+
+    /// Close the writer once the reader reaches EOF and everything has been
+    /// written. See [`Forwarder::half_close_on_eof`].
+    pub fn half_close_on_eof(mut self, half_close: bool) -> Self {
+        self.config.half_close_on_eof = half_close;
+        self
+    }
+
+    /// Choose what happens when the writer stops accepting data. See
+    /// [`Forwarder::with_writer_close_policy`].
+    pub fn writer_close_policy(mut self, policy: WriterClosePolicy) -> Self {
+        self.config.writer_close = policy;
+        self
+    }
+
+    /// Re-offer exactly the same bytes after a write returns `Pending`. See
+    /// [`Forwarder::strict_write_accounting`].
+    pub fn strict_write_accounting(mut self, strict: bool) -> Self {
+        self.config.strict_write_accounting = strict;
+        self
+    }
+
+    /// Flush the writer once the reader reaches EOF. See
+    /// [`Forwarder::flush_on_eof`].
+    pub fn flush_on_eof(mut self, flush: bool) -> Self {
+        self.config.flush_on_eof = flush;
+        self
+    }
+
+    /// Flush the writer whenever the reader stalls with nothing buffered.
+    /// See [`Forwarder::flush_on_read_stall`].
+    pub fn flush_on_read_stall(mut self, flush: bool) -> Self {
+        self.config.flush_on_read_stall = flush;
+        self
+    }
+
+    /// Stop reading after `limit` bytes. See
+    /// [`Forwarder::with_read_limit`].
+    pub fn read_limit(mut self, limit: u64) -> Self {
+        self.config.read_limit = Some(limit);
+        self
+    }
+
+    /// Forward exactly `len` bytes, failing if the reader ends early. See
+    /// [`Forwarder::copy_exact`].
+    pub fn copy_exact(mut self, len: u64) -> Self {
+        self.config.read_limit = Some(len);
+        self.config.exact = true;
+        self
+    }
+
+    /// Offer the writer at most `max` bytes per write. See
+    /// [`Forwarder::with_max_write_size`].
+    pub fn max_write_size(mut self, max: NonZeroUsize) -> Self {
+        self.config.max_write_size = Some(max);
+        self
+    }
+
+    /// Fail after this many consecutive polls in which no data moved. See
+    /// [`Forwarder::max_polls_without_progress`].
+    pub fn max_polls_without_progress(mut self, polls: NonZeroU32) -> Self {
+        self.config.max_stalled_polls = Some(polls);
+        self
+    }
+
+    /// Fail if the forward hasn't completed within `timeout` of being built.
+    /// Each forwarder gets its own deadline, computed when it's built; see
+    /// [`Forwarder::with_deadline`].
+    #[cfg(feature = "futures-timer")]
+    pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Build a [`Forwarder`] with this configuration, after checking that
+    /// the configuration makes sense for the given buffer.
+    pub fn build<R, W, B>(
+        &self,
+        reader: R,
+        writer: W,
+        mut buffer: B,
+    ) -> Result<Forwarder<R, W, B>, BuildError>
+    where
+        R: AsyncRead,
+        W: AsyncWrite,
+        B: BufferStorage,
+    {
+        let capacity = buffer.capacity();
+
+        if capacity == 0 {
+            return Err(BuildError::EmptyBuffer);
+        }
+
+        if self.config.min_read > capacity {
+            return Err(BuildError::ReadAggregationTooLarge {
+                min_read: self.config.min_read,
+                capacity,
+            });
+        }
+
+        let forwarder = Forwarder::with_config(reader, writer, buffer, self.config.clone());
+
+        #[cfg(feature = "futures-timer")]
+        let forwarder = match self.timeout {
+            Some(timeout) => forwarder.with_deadline(std::time::Instant::now() + timeout),
+            None => forwarder,
+        };
+
+        Ok(forwarder)
+    }
+
+    /// Build a [`Forwarder`] with this configuration and a freshly allocated
+    /// buffer of the configured [`buffer_size`][Self::buffer_size].
+    pub fn start<R, W>(
+        &self,
+        reader: R,
+        writer: W,
+    ) -> Result<Forwarder<R, W, UninitBuffer>, BuildError>
+    where
+        R: AsyncRead,
+        W: AsyncWrite,
+    {
+        self.build(reader, writer, UninitBuffer::new(self.buffer_size))
+    }
+}
@@ -0,0 +1,13 @@
+use crate::sync_cell::SyncCell;
+
+/// A boxed user callback, like `dyn FnMut(usize) + Send`
+pub type Callback<F> = SyncCell<Box<F>>;
+
+/// A callback that gets the size of each read or write
+pub type CountCallback = Callback<dyn FnMut(usize) + Send>;
+
+/// A callback that sees each chunk of data
+pub type ChunkCallback = Callback<dyn FnMut(&[u8]) + Send>;
+
+/// A callback that can modify each chunk of data
+pub type ChunkMutCallback = Callback<dyn FnMut(&mut [u8]) + Send>;
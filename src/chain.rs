@@ -0,0 +1,95 @@
+use std::{
+    io::{self, IoSliceMut},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::AsyncRead;
+use pin_project::pin_project;
+
+use crate::{BufferStorage, Forwarder};
+
+/// A reader that reads each reader from an iterator to completion, one after
+/// the other, as a single continuous stream. Created by [`forward_chain`].
+#[pin_project]
+#[derive(Debug)]
+pub struct ReaderChain<I: Iterator> {
+    #[pin]
+    current: Option<I::Item>,
+    readers: I,
+}
+
+impl<I: Iterator> ReaderChain<I>
+where
+    I::Item: AsyncRead,
+{
+    pub fn new(readers: impl IntoIterator<IntoIter = I>) -> Self {
+        let mut readers = readers.into_iter();
+
+        Self {
+            current: readers.next(),
+            readers,
+        }
+    }
+}
+
+impl<I: Iterator> AsyncRead for ReaderChain<I>
+where
+    I::Item: AsyncRead,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_read_vectored(cx, &mut [IoSliceMut::new(buf)])
+    }
+
+    fn poll_read_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let mut this = self.project();
+
+        // An empty read would be indistinguishable from EOF, so make sure we
+        // don't advance to the next reader because of it
+        if bufs.iter().all(|buf| buf.is_empty()) {
+            return Poll::Ready(Ok(0));
+        }
+
+        while let Some(reader) = this.current.as_mut().as_pin_mut() {
+            match futures::ready!(reader.poll_read_vectored(cx, bufs))? {
+                // This reader is done; move on to the next one. Any data
+                // that was read from it is already in the caller's buffer.
+                0 => {
+                    let next = this.readers.next();
+                    this.current.set(next);
+                }
+                n => return Poll::Ready(Ok(n)),
+            }
+        }
+
+        Poll::Ready(Ok(0))
+    }
+}
+
+/// A [`Forwarder`] that forwards a sequence of readers into a single writer.
+pub type ForwardChain<I, W, B> = Forwarder<ReaderChain<I>, W, B>;
+
+/// Forward each of `readers` into `writer` in order, as a single continuous
+/// stream. Bytes that are still buffered when one reader reaches EOF are
+/// carried over, so nothing is lost at the transition to the next reader.
+///
+/// A single forwarder and buffer are used for the whole sequence, so this is
+/// the way to concatenate parts, like a multi-part download, without
+/// creating a new forwarder for each part.
+pub fn forward_chain<I, W, B>(readers: I, writer: W, buffer: B) -> ForwardChain<I::IntoIter, W, B>
+where
+    I: IntoIterator,
+    I::Item: AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+{
+    Forwarder::new(ReaderChain::new(readers), writer, buffer)
+}
@@ -0,0 +1,242 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex, PoisonError},
+    task::{Context, Poll},
+};
+
+use futures::future::FusedFuture;
+use pin_project::pin_project;
+
+use crate::{callback::Callback, BufferStorage, Forwarder, ForwarderError};
+
+/// Something that every forwarded byte can be fed into, like a hasher. This
+/// is implemented for any `FnMut(&[u8])`, and, with the `digest` feature,
+/// for [`Digest`].
+pub trait Checksum {
+    fn update(&mut self, data: &[u8]);
+}
+
+impl<F: FnMut(&[u8])> Checksum for F {
+    #[inline]
+    fn update(&mut self, data: &[u8]) {
+        self(data)
+    }
+}
+
+/// Adapts any of the `digest` crate's hashers, like `sha2::Sha256`, to
+/// [`Checksum`]
+#[cfg(feature = "digest")]
+#[derive(Debug, Clone, Default)]
+pub struct Digest<D>(pub D);
+
+#[cfg(feature = "digest")]
+impl<D: digest::Update> Checksum for Digest<D> {
+    #[inline]
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data)
+    }
+}
+
+/// Future that runs a [`Forwarder`] while feeding every forwarded byte into
+/// a [`Checksum`]. Created by [`Forwarder::checksum`].
+///
+/// On success, the output is the total number of bytes written, along with
+/// the checksum.
+#[pin_project]
+pub struct Checksummed<R, W, B, C, H> {
+    #[pin]
+    forwarder: Forwarder<R, W, B, C>,
+
+    // Shared with the forwarder's callback; taken once the forward succeeds
+    checksum: Arc<Mutex<Option<H>>>,
+}
+
+impl<R, W, B, C> Forwarder<R, W, B, C> {
+    /// Feed every forwarded byte into `checksum`, like a hasher, and hand it
+    /// back along with the number of bytes written once the forward
+    /// succeeds. This is for getting the checksum of what was sent without
+    /// reading the source twice. The data is checksummed after
+    /// [`map_in_place`][Self::map_in_place], so it's exactly what's written.
+    pub fn checksum<H>(mut self, checksum: H) -> Checksummed<R, W, B, C, H>
+    where
+        H: Checksum + Send + 'static,
+    {
+        let checksum = Arc::new(Mutex::new(Some(checksum)));
+        let shared = checksum.clone();
+
+        self.inner.checksum = Some(Callback::new(Box::new(move |data: &[u8]| {
+            let mut checksum = shared.lock().unwrap_or_else(PoisonError::into_inner);
+
+            if let Some(checksum) = checksum.as_mut() {
+                checksum.update(data);
+            }
+        })));
+
+        Checksummed {
+            forwarder: self,
+            checksum,
+        }
+    }
+}
+
+/// Future that runs a [`Forwarder`] while checksumming the forwarded data,
+/// and fails if the checksum doesn't match what's expected. Created by
+/// [`Forwarder::verify_checksum`].
+///
+/// On success, the output is the total number of bytes written.
+#[pin_project]
+pub struct Verified<R, W, B, C, H, F> {
+    #[pin]
+    checksummed: Checksummed<R, W, B, C, H>,
+
+    // Taken once the forward succeeds
+    verify: Option<(F, Vec<u8>)>,
+}
+
+impl<R, W, B, C> Forwarder<R, W, B, C> {
+    /// Checksum the forwarded data like [`checksum`][Self::checksum], and
+    /// once the forward is otherwise done, fail with
+    /// [`ForwarderError::DigestMismatch`] instead of reporting success if
+    /// `finish` doesn't turn the checksum into `expected`. This makes the
+    /// forward a verified copy. Note that everything has been written by the
+    /// time the mismatch is found, so the writer has to be discarded by the
+    /// caller.
+    pub fn verify_checksum<H, F, O>(
+        self,
+        checksum: H,
+        finish: F,
+        expected: impl Into<Vec<u8>>,
+    ) -> Verified<R, W, B, C, H, F>
+    where
+        H: Checksum + Send + 'static,
+        F: FnOnce(H) -> O,
+        O: AsRef<[u8]>,
+    {
+        Verified {
+            checksummed: self.checksum(checksum),
+            verify: Some((finish, expected.into())),
+        }
+    }
+
+    /// Verify the forwarded data against `expected` with the `digest`
+    /// crate's hasher `D`, like `sha2::Sha256`. See
+    /// [`verify_checksum`][Self::verify_checksum].
+    #[cfg(feature = "digest")]
+    #[allow(clippy::type_complexity)]
+    pub fn verify_digest<D>(
+        self,
+        expected: impl Into<Vec<u8>>,
+    ) -> Verified<R, W, B, C, Digest<D>, fn(Digest<D>) -> Vec<u8>>
+    where
+        D: digest::Digest + digest::Update + Send + 'static,
+    {
+        self.verify_checksum(
+            Digest(D::new()),
+            |Digest(digest)| digest.finalize().to_vec(),
+            expected,
+        )
+    }
+}
+
+impl<R, W, B, C, H, F> Verified<R, W, B, C, H, F> {
+    /// Get a reference to the underlying forwarder
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Forwarder<R, W, B, C> {
+        self.checksummed.get_ref()
+    }
+}
+
+impl<R, W, B, C, H, F, O> Future for Verified<R, W, B, C, H, F>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+    F: FnOnce(H) -> O,
+    O: AsRef<[u8]>,
+{
+    type Output = Result<u64, ForwarderError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let (bytes_written, checksum) = futures::ready!(this.checksummed.poll(cx))?;
+
+        let (finish, expected) = this
+            .verify
+            .take()
+            .expect("Verified polled after completion");
+
+        let actual = finish(checksum);
+        let actual = actual.as_ref();
+
+        Poll::Ready(if actual == expected.as_slice() {
+            Ok(bytes_written)
+        } else {
+            Err(ForwarderError::DigestMismatch {
+                expected,
+                actual: actual.to_vec(),
+            })
+        })
+    }
+}
+
+impl<R, W, B, C, H, F, O> FusedFuture for Verified<R, W, B, C, H, F>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+    F: FnOnce(H) -> O,
+    O: AsRef<[u8]>,
+{
+    fn is_terminated(&self) -> bool {
+        self.verify.is_none() || self.checksummed.is_terminated()
+    }
+}
+
+impl<R, W, B, C, H> Checksummed<R, W, B, C, H> {
+    /// Get a reference to the underlying forwarder
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Forwarder<R, W, B, C> {
+        &self.forwarder
+    }
+}
+
+impl<R, W, B, C, H> Future for Checksummed<R, W, B, C, H>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+{
+    type Output = Result<(u64, H), ForwarderError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let bytes_written = futures::ready!(this.forwarder.poll(cx))?;
+
+        let checksum = this
+            .checksum
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+            .expect("Checksummed polled after completion");
+
+        Poll::Ready(Ok((bytes_written, checksum)))
+    }
+}
+
+impl<R, W, B, C, H> FusedFuture for Checksummed<R, W, B, C, H>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+{
+    fn is_terminated(&self) -> bool {
+        self.forwarder.is_terminated()
+    }
+}
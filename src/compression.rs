@@ -0,0 +1,117 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::AsyncWrite;
+use pin_project::pin_project;
+
+use crate::Transform;
+
+/// One of `async-compression`'s writers, writing into a `Vec`, which we can
+/// take its output from
+pub trait CodecWriter: AsyncWrite {
+    fn output(self: Pin<&mut Self>) -> &mut Vec<u8>;
+}
+
+macro_rules! codec_writers {
+    ($($feature:literal => [$($codec:ident),*],)*) => {$($(
+        #[cfg(feature = $feature)]
+        impl CodecWriter for async_compression::futures::write::$codec<Vec<u8>> {
+            fn output(self: Pin<&mut Self>) -> &mut Vec<u8> {
+                self.get_pin_mut().get_mut()
+            }
+        }
+    )*)*};
+}
+
+codec_writers! {
+    "gzip" => [GzipEncoder, GzipDecoder],
+    "zstd" => [ZstdEncoder, ZstdDecoder],
+    "brotli" => [BrotliEncoder, BrotliDecoder],
+}
+
+/// A [`Transform`] that compresses or decompresses data with one of
+/// `async-compression`'s writers, like
+/// `Codec::new(GzipEncoder::new(Vec::new()))`. Which codecs are available
+/// depends on the `gzip`, `zstd`, and `brotli` features. The codec's own
+/// settings, like the compression level, are set when it's created.
+///
+/// Codecs buffer data internally, so output isn't necessarily produced
+/// until they're finished, when the forwarder's writer is closed.
+#[pin_project]
+#[derive(Debug)]
+pub struct Codec<E> {
+    #[pin]
+    codec: E,
+
+    // How much of the codec's output has already been handed out
+    taken: usize,
+
+    closed: bool,
+}
+
+impl<E: CodecWriter> Codec<E> {
+    /// Wrap a codec. It has to be writing into an empty `Vec`.
+    pub fn new(codec: E) -> Self {
+        Self {
+            codec,
+            taken: 0,
+            closed: false,
+        }
+    }
+}
+
+impl<E: CodecWriter> Codec<E> {
+    /// Copy as much of the codec's output as fits into `output`
+    fn take_output(self: Pin<&mut Self>, output: &mut [u8]) -> usize {
+        let this = self.project();
+        let produced = this.codec.output();
+
+        let n = output.len().min(produced.len() - *this.taken);
+        output[..n].copy_from_slice(&produced[*this.taken..*this.taken + n]);
+        *this.taken += n;
+
+        if *this.taken == produced.len() {
+            produced.clear();
+            *this.taken = 0;
+        }
+
+        n
+    }
+}
+
+impl<E: CodecWriter> Transform for Codec<E> {
+    fn poll_transform(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Poll<io::Result<(usize, usize)>> {
+        // Hand out what the codec already produced before giving it more
+        let produced = self.as_mut().take_output(output);
+        if produced > 0 {
+            return Poll::Ready(Ok((0, produced)));
+        }
+
+        let consumed = futures::ready!(self.as_mut().project().codec.poll_write(cx, input))?;
+        let produced = self.take_output(output);
+
+        Poll::Ready(Ok((consumed, produced)))
+    }
+
+    fn poll_finish(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        output: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if !self.closed {
+            let this = self.as_mut().project();
+            futures::ready!(this.codec.poll_close(cx))?;
+            *this.closed = true;
+        }
+
+        Poll::Ready(Ok(self.take_output(output)))
+    }
+}
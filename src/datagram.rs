@@ -0,0 +1,257 @@
+use std::{
+    future::Future,
+    io,
+    num::NonZeroU64,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::future::FusedFuture;
+use pin_project::pin_project;
+
+use crate::{BufferStorage, ForwarderError, MAX_CYCLES_PER_POLL};
+
+/// A source of whole messages, like a connected datagram socket. Unlike
+/// `AsyncRead`, each successful receive is exactly one message, and an empty
+/// message is just an empty message, not EOF.
+pub trait AsyncRecv {
+    /// Attempt to receive one message into `buf`, and return its length. If
+    /// the message is larger than `buf`, it may be truncated.
+    fn poll_recv(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>>;
+}
+
+/// A destination for whole messages, like a connected datagram socket.
+/// Unlike `AsyncWrite`, each successful send is exactly one message.
+pub trait AsyncSend {
+    /// Attempt to send `buf` as one message, and return the number of bytes
+    /// sent.
+    fn poll_send(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8])
+        -> Poll<io::Result<usize>>;
+}
+
+impl<T: AsyncRecv + Unpin + ?Sized> AsyncRecv for &mut T {
+    fn poll_recv(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut **self).poll_recv(cx, buf)
+    }
+}
+
+impl<T: AsyncSend + Unpin + ?Sized> AsyncSend for &mut T {
+    fn poll_send(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut **self).poll_send(cx, buf)
+    }
+}
+
+/// Implement the datagram traits for tokio's sockets, which have to be
+/// connected
+#[cfg(feature = "tokio")]
+macro_rules! tokio_datagram {
+    ($($(#[$attr:meta])* $socket:ty,)*) => {$(
+        $(#[$attr])*
+        impl AsyncRecv for $socket {
+            fn poll_recv(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &mut [u8],
+            ) -> Poll<io::Result<usize>> {
+                let mut buf = tokio::io::ReadBuf::new(buf);
+                futures::ready!(<$socket>::poll_recv(&self, cx, &mut buf))?;
+                Poll::Ready(Ok(buf.filled().len()))
+            }
+        }
+
+        $(#[$attr])*
+        impl AsyncSend for $socket {
+            fn poll_send(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &[u8],
+            ) -> Poll<io::Result<usize>> {
+                <$socket>::poll_send(&self, cx, buf)
+            }
+        }
+    )*};
+}
+
+#[cfg(feature = "tokio")]
+tokio_datagram! {
+    tokio::net::UdpSocket,
+    #[cfg(unix)]
+    tokio::net::UnixDatagram,
+}
+
+/// Future that forwards messages from an [`AsyncRecv`] to an [`AsyncSend`]
+/// one at a time, never splitting or merging them. Created by
+/// [`DatagramForwarder::new`].
+///
+/// Each message is received into the buffer and sent before the next one is
+/// received, so the buffer has to be as large as the largest message; for
+/// UDP, that's 65,507 bytes. A message that's only partly sent fails the
+/// forward.
+///
+/// Datagram sources have no EOF, so the forward runs until it fails, or
+/// until the number of messages set with
+/// [`with_max_messages`][Self::with_max_messages] have been forwarded. On
+/// success, the output is the number of messages forwarded.
+#[pin_project]
+pub struct DatagramForwarder<R, S, B> {
+    #[pin]
+    receiver: R,
+
+    #[pin]
+    sender: S,
+
+    buffer: B,
+
+    // The length of a received message that hasn't been sent yet
+    pending: Option<usize>,
+
+    max_messages: Option<NonZeroU64>,
+    messages: u64,
+    bytes: u64,
+    done: bool,
+}
+
+impl<R: AsyncRecv, S: AsyncSend, B: BufferStorage> DatagramForwarder<R, S, B> {
+    pub fn new(receiver: R, sender: S, buffer: B) -> Self {
+        Self {
+            receiver,
+            sender,
+            buffer,
+            pending: None,
+            max_messages: None,
+            messages: 0,
+            bytes: 0,
+            done: false,
+        }
+    }
+
+    /// Finish successfully once `max` messages have been forwarded
+    pub fn with_max_messages(self, max: NonZeroU64) -> Self {
+        Self {
+            max_messages: Some(max),
+            ..self
+        }
+    }
+}
+
+impl<R, S, B> DatagramForwarder<R, S, B> {
+    /// The number of messages that have been forwarded so far
+    #[inline]
+    #[must_use]
+    pub fn messages(&self) -> u64 {
+        self.messages
+    }
+
+    /// The total size of the messages that have been forwarded so far
+    #[inline]
+    #[must_use]
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+impl<R, S, B> Future for DatagramForwarder<R, S, B>
+where
+    R: AsyncRecv,
+    S: AsyncSend,
+    B: BufferStorage,
+{
+    type Output = Result<u64, ForwarderError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        assert!(!*this.done, "DatagramForwarder polled after completion");
+
+        let capacity = this.buffer.capacity();
+
+        let mut cycles = 0;
+
+        let result = loop {
+            if let Some(max) = *this.max_messages {
+                if *this.messages >= max.get() {
+                    break Ok(*this.messages);
+                }
+            }
+
+            // Don't hog the executor with a flood of messages; come back for
+            // the rest after yielding
+            if cycles == MAX_CYCLES_PER_POLL {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            cycles += 1;
+
+            let buffer = this.buffer.as_bytes(capacity);
+
+            match *this.pending {
+                None => match futures::ready!(this.receiver.as_mut().poll_recv(cx, buffer)) {
+                    Ok(len) => *this.pending = Some(len),
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                    Err(error) => {
+                        break Err(ForwarderError::Read {
+                            error,
+                            bytes_read: *this.bytes,
+                            bytes_written: *this.bytes,
+                        })
+                    }
+                },
+                Some(len) => {
+                    match futures::ready!(this.sender.as_mut().poll_send(cx, &buffer[..len])) {
+                        Ok(sent) if sent == len => {
+                            *this.pending = None;
+                            *this.messages += 1;
+                            *this.bytes += len as u64;
+                        }
+                        Ok(sent) => {
+                            break Err(ForwarderError::Write {
+                                error: io::Error::new(
+                                    io::ErrorKind::WriteZero,
+                                    format!("only {sent} bytes of a {len} byte message were sent"),
+                                ),
+                                bytes_read: *this.bytes + len as u64,
+                                bytes_written: *this.bytes,
+                                unforwarded: buffer[..len].to_vec(),
+                            })
+                        }
+                        Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                        Err(error) => {
+                            break Err(ForwarderError::Write {
+                                error,
+                                bytes_read: *this.bytes + len as u64,
+                                bytes_written: *this.bytes,
+                                unforwarded: buffer[..len].to_vec(),
+                            })
+                        }
+                    }
+                }
+            }
+        };
+
+        *this.done = true;
+        Poll::Ready(result)
+    }
+}
+
+impl<R, S, B> FusedFuture for DatagramForwarder<R, S, B>
+where
+    R: AsyncRecv,
+    S: AsyncSend,
+    B: BufferStorage,
+{
+    fn is_terminated(&self) -> bool {
+        self.done
+    }
+}
@@ -0,0 +1,116 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::future::FusedFuture;
+use pin_project::pin_project;
+
+use crate::{BufferStorage, Forwarder, ForwarderError};
+
+/// Future that races a [`Forwarder`] against a deadline future. Created by
+/// [`Forwarder::race_deadline`].
+///
+/// On success, the output is the total number of bytes written.
+#[pin_project]
+pub struct RaceDeadline<R, W, B, C, D> {
+    // Taken once the race is decided
+    forwarder: Option<Forwarder<R, W, B, C>>,
+
+    #[pin]
+    deadline: D,
+}
+
+/// Error from a [`RaceDeadline`]
+#[derive(Debug)]
+pub enum DeadlineError<R, W> {
+    /// The forward failed before the deadline
+    Forward(ForwarderError),
+
+    /// The deadline completed before the forward did. `copied` is the number
+    /// of bytes that were written before that happened, and `unwritten` is
+    /// the data that had been read but was still buffered, in order. The
+    /// reader is `None` if it had already reached EOF. The writer isn't
+    /// flushed or closed.
+    Expired {
+        copied: u64,
+        reader: Option<R>,
+        writer: W,
+        unwritten: Vec<u8>,
+    },
+}
+
+impl<R, W, B, C> Forwarder<R, W, B, C> {
+    /// Race the forward against `deadline`, which can be any future, like a
+    /// runtime's sleep or a shutdown signal. If `deadline` completes first,
+    /// the forward stops immediately and fails with
+    /// [`DeadlineError::Expired`], which hands back the reader and writer
+    /// along with the progress made so far. Unlike racing with `select!`,
+    /// nothing is lost. If both are ready in the same poll, the forward
+    /// wins.
+    ///
+    /// The streams are recovered by moving them out of the forwarder, so it
+    /// has to be `Unpin`.
+    pub fn race_deadline<D>(self, deadline: D) -> RaceDeadline<R, W, B, C, D>
+    where
+        D: Future,
+    {
+        RaceDeadline {
+            forwarder: Some(self),
+            deadline,
+        }
+    }
+}
+
+impl<R, W, B, C, D> Future for RaceDeadline<R, W, B, C, D>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+    D: Future,
+    Forwarder<R, W, B, C>: Unpin,
+{
+    type Output = Result<u64, DeadlineError<R, W>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        let forwarder = this
+            .forwarder
+            .as_mut()
+            .expect("RaceDeadline polled after completion");
+
+        if let Poll::Ready(result) = Pin::new(forwarder).poll(cx) {
+            *this.forwarder = None;
+            return Poll::Ready(result.map_err(DeadlineError::Forward));
+        }
+
+        futures::ready!(this.deadline.poll(cx));
+
+        let mut forwarder = this.forwarder.take().unwrap();
+        let unwritten = forwarder.inner.pump.buffer.buffered_data();
+
+        Poll::Ready(Err(DeadlineError::Expired {
+            copied: forwarder.inner.counters.bytes_written,
+            reader: forwarder.inner.reader,
+            writer: forwarder.inner.writer,
+            unwritten,
+        }))
+    }
+}
+
+impl<R, W, B, C, D> FusedFuture for RaceDeadline<R, W, B, C, D>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+    D: Future,
+    Forwarder<R, W, B, C>: Unpin,
+{
+    fn is_terminated(&self) -> bool {
+        self.forwarder.is_none()
+    }
+}
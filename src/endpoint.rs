@@ -0,0 +1,178 @@
+use std::{
+    io::{self, IoSlice, IoSliceMut},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{AsyncRead, AsyncWrite};
+use pin_project::pin_project;
+
+use crate::{vectored::read_vectored_pair, BufferStorage, Forwarder};
+
+/// Something that data can be forwarded from. This is the minimal interface
+/// the forwarder needs from a source: fill some of the free space in the
+/// ring buffer, which is given as a pair of slices because it may wrap
+/// around. It's implemented for every `AsyncRead`, and can be implemented
+/// directly for custom endpoints, like shared-memory queues or FFI handles,
+/// that don't fit `AsyncRead`. Wrap them in an [`Endpoint`] to forward them.
+pub trait Source {
+    /// Attempt to fill the start of `bufs`, in order, and return the number
+    /// of bytes filled. `Ok(0)` means EOF.
+    fn poll_fill(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: [&mut [u8]; 2],
+    ) -> Poll<io::Result<usize>>;
+}
+
+/// Something that data can be forwarded into. This is the minimal interface
+/// the forwarder needs from a destination: drain some of the buffered data,
+/// which is given as a pair of slices because it may wrap around the ring.
+/// It's implemented for every `AsyncWrite`, and can be implemented directly
+/// for custom endpoints. Wrap them in an [`Endpoint`] to forward into them.
+pub trait Destination {
+    /// Attempt to drain the start of `bufs`, in order, and return the number
+    /// of bytes drained. `Ok(0)` means the destination is closed.
+    fn poll_drain(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: [&[u8]; 2],
+    ) -> Poll<io::Result<usize>>;
+
+    /// Attempt to make sure everything drained so far has been delivered.
+    /// By default, there's nothing to do.
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    /// Attempt to flush and close the destination. By default, this just
+    /// flushes.
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+impl<T: AsyncRead + ?Sized> Source for T {
+    fn poll_fill(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: [&mut [u8]; 2],
+    ) -> Poll<io::Result<usize>> {
+        read_vectored_pair(self, cx, bufs)
+    }
+}
+
+impl<T: AsyncWrite + ?Sized> Destination for T {
+    fn poll_drain(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        [b1, b2]: [&[u8]; 2],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_write_vectored(cx, &[IoSlice::new(b1), IoSlice::new(b2)])
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_flush(self, cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_close(self, cx)
+    }
+}
+
+/// Adapts a custom [`Source`] or [`Destination`] into an `AsyncRead` or
+/// `AsyncWrite`, so that it can be forwarded from or into. The forwarder's
+/// reads and writes of the ring buffer's two halves are passed straight
+/// through as slice pairs.
+#[pin_project]
+#[derive(Debug)]
+pub struct Endpoint<T> {
+    #[pin]
+    inner: T,
+}
+
+impl<T> Endpoint<T> {
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Source> AsyncRead for Endpoint<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_fill(cx, [buf, &mut []])
+    }
+
+    fn poll_read_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let mut bufs = bufs.iter_mut().filter(|buf| !buf.is_empty());
+        let b1 = bufs.next().map_or(&mut [][..], |buf| &mut **buf);
+        let b2 = bufs.next().map_or(&mut [][..], |buf| &mut **buf);
+
+        self.project().inner.poll_fill(cx, [b1, b2])
+    }
+}
+
+impl<T: Destination> AsyncWrite for Endpoint<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_drain(cx, [buf, &[]])
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        // An endpoint only handles two slices at once, which is all the
+        // forwarder ever uses without overflow buffers
+        let mut bufs = bufs.iter().filter(|buf| !buf.is_empty());
+        let b1 = bufs.next().map_or(&[][..], |buf| &**buf);
+        let b2 = bufs.next().map_or(&[][..], |buf| &**buf);
+
+        self.project().inner.poll_drain(cx, [b1, b2])
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_close(cx)
+    }
+}
+
+impl<R, W, B> Forwarder<Endpoint<R>, Endpoint<W>, B>
+where
+    R: Source,
+    W: Destination,
+    B: BufferStorage,
+{
+    /// Create a new `Forwarder` between custom endpoints. To forward between
+    /// a custom endpoint and an ordinary reader or writer, wrap just the
+    /// custom one in an [`Endpoint`] and use [`Forwarder::new`].
+    pub fn from_endpoints(source: R, destination: W, buffer: B) -> Self {
+        Self::new(Endpoint::new(source), Endpoint::new(destination), buffer)
+    }
+}
@@ -0,0 +1,70 @@
+use futures::{AsyncRead, AsyncWrite};
+
+use crate::{BufferStorage, BuildError, Forwarder, ForwarderBuilder, UninitBuffer};
+
+/// Extension trait for creating a [`Forwarder`] from a reader, so that a
+/// forward can be written as `reader.forward_to(writer, buffer)`. It's
+/// implemented for every `AsyncRead`.
+pub trait ForwardExt: AsyncRead + Sized {
+    /// Create a [`Forwarder`] that forwards everything from this reader into
+    /// `writer`, using `buffer`. This is the same as [`Forwarder::new`].
+    fn forward_to<W, B>(self, writer: W, buffer: B) -> Forwarder<Self, W, B>
+    where
+        W: AsyncWrite,
+        B: BufferStorage,
+    {
+        Forwarder::new(self, writer, buffer)
+    }
+
+    /// Create a [`Forwarder`] that forwards everything from this reader into
+    /// `writer`, configured by `builder`, with a freshly allocated buffer.
+    /// This is the same as [`ForwarderBuilder::start`].
+    fn forward_to_with<W>(
+        self,
+        writer: W,
+        builder: &ForwarderBuilder,
+    ) -> Result<Forwarder<Self, W, UninitBuffer>, BuildError>
+    where
+        W: AsyncWrite,
+    {
+        builder.start(self, writer)
+    }
+}
+
+impl<R: AsyncRead> ForwardExt for R {}
+
+/// Extension trait for creating a [`Forwarder`] from a writer, so that a
+/// forward can be written as `writer.receive_from(reader, buffer)`. It's
+/// implemented for every `AsyncWrite`.
+///
+/// Writer-side configuration, like
+/// [`with_flush_policy`][Forwarder::with_flush_policy] or
+/// [`with_writer_close_policy`][Forwarder::with_writer_close_policy], can
+/// be chained onto the result as usual.
+pub trait ReceiveExt: AsyncWrite + Sized {
+    /// Create a [`Forwarder`] that forwards everything from `reader` into
+    /// this writer, using `buffer`. This is the same as [`Forwarder::new`].
+    fn receive_from<R, B>(self, reader: R, buffer: B) -> Forwarder<R, Self, B>
+    where
+        R: AsyncRead,
+        B: BufferStorage,
+    {
+        Forwarder::new(reader, self, buffer)
+    }
+
+    /// Create a [`Forwarder`] that forwards everything from `reader` into
+    /// this writer, configured by `builder`, with a freshly allocated
+    /// buffer. This is the same as [`ForwarderBuilder::start`].
+    fn receive_from_with<R>(
+        self,
+        reader: R,
+        builder: &ForwarderBuilder,
+    ) -> Result<Forwarder<R, Self, UninitBuffer>, BuildError>
+    where
+        R: AsyncRead,
+    {
+        builder.start(reader, self)
+    }
+}
+
+impl<W: AsyncWrite> ReceiveExt for W {}
@@ -0,0 +1,109 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::future::FusedFuture;
+use pin_project::pin_project;
+
+use crate::{BufferStorage, Forwarder, ForwarderError};
+
+/// Future that runs a [`Forwarder`], switching to a fallback writer whenever
+/// the current one fails. Created by [`Forwarder::with_failover`].
+///
+/// On success, the output is the total number of bytes written, across all
+/// the writers.
+#[pin_project]
+pub struct Failover<R, W, B, C, I> {
+    #[pin]
+    forwarder: Forwarder<R, W, B, C>,
+    fallbacks: I,
+    failovers: usize,
+}
+
+impl<R, W, B, C> Forwarder<R, W, B, C> {
+    /// Instead of failing when a write fails, or when the writer stops
+    /// accepting data, switch to the next of `fallbacks` and carry on. The
+    /// data that was still buffered is written to the new writer, so nothing
+    /// is lost, but data that the failed writer accepted isn't sent again.
+    /// Once the fallbacks run out, the forward fails with the last writer's
+    /// error.
+    ///
+    /// Failed writers are dropped without being flushed or closed. Errors
+    /// from reading, or from flushing or closing the writer, aren't retried.
+    pub fn with_failover<I>(self, fallbacks: I) -> Failover<R, W, B, C, I::IntoIter>
+    where
+        I: IntoIterator<Item = W>,
+    {
+        Failover {
+            forwarder: self,
+            fallbacks: fallbacks.into_iter(),
+            failovers: 0,
+        }
+    }
+}
+
+impl<R, W, B, C, I> Failover<R, W, B, C, I> {
+    /// Get a reference to the underlying forwarder
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Forwarder<R, W, B, C> {
+        &self.forwarder
+    }
+
+    /// The number of times a fallback writer has been switched to
+    #[inline]
+    #[must_use]
+    pub fn failovers(&self) -> usize {
+        self.failovers
+    }
+}
+
+impl<R, W, B, C, I> Future for Failover<R, W, B, C, I>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+    I: Iterator<Item = W>,
+{
+    type Output = Result<u64, ForwarderError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        loop {
+            let result = futures::ready!(this.forwarder.as_mut().poll(cx));
+
+            let writer_failed = matches!(
+                result,
+                Err(ForwarderError::Write { .. } | ForwarderError::WriteClosedEarly { .. })
+            );
+
+            if !writer_failed {
+                return Poll::Ready(result);
+            }
+
+            let Some(writer) = this.fallbacks.next() else {
+                return Poll::Ready(result);
+            };
+
+            this.forwarder.as_mut().replace_writer(writer);
+            *this.failovers += 1;
+        }
+    }
+}
+
+impl<R, W, B, C, I> FusedFuture for Failover<R, W, B, C, I>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+    I: Iterator<Item = W>,
+{
+    fn is_terminated(&self) -> bool {
+        self.forwarder.is_terminated()
+    }
+}
@@ -0,0 +1,56 @@
+use std::io;
+
+use bytes::Bytes;
+use futures::{stream, AsyncRead, AsyncWrite, TryStreamExt};
+use tokio_util::codec::{Decoder, Encoder, FramedRead, FramedWrite};
+
+use crate::{forward_stream, forward_to_sink, BufferStorage, ForwardStream, ForwardToSink};
+
+/// The frames of a `FramedRead`, as a stream of [`Bytes`] for
+/// [`forward_stream`]
+pub type FrameChunks<R, D> = stream::MapErr<
+    stream::MapOk<FramedRead<R, D>, fn(<D as Decoder>::Item) -> Bytes>,
+    fn(<D as Decoder>::Error) -> io::Error,
+>;
+
+/// Forward every frame decoded by `frames` into the raw `writer`, then flush
+/// the writer. Each frame is written straight out of the codec's own buffer,
+/// without being copied again. Use [`TokioIo`][crate::TokioIo] for a writer
+/// that implements tokio's `AsyncWrite`.
+pub fn forward_from_framed<R, D, W>(
+    frames: FramedRead<R, D>,
+    writer: W,
+) -> ForwardStream<FrameChunks<R, D>, W>
+where
+    R: tokio::io::AsyncRead,
+    D: Decoder,
+    D::Item: Into<Bytes>,
+    D::Error: Into<io::Error>,
+    W: AsyncWrite,
+{
+    let frames = frames
+        .map_ok(Into::into as fn(D::Item) -> Bytes)
+        .map_err(Into::into as fn(D::Error) -> io::Error);
+
+    forward_stream(frames, writer)
+}
+
+/// Forward everything from the raw `reader` into `frames`, encoding each
+/// chunk that's read as a frame, and flush it once the reader reaches EOF.
+/// The chunks are at most the size of the buffer. Use
+/// [`TokioIo`][crate::TokioIo] for a reader that implements tokio's
+/// `AsyncRead`.
+pub fn forward_into_framed<R, W, E, B>(
+    reader: R,
+    frames: FramedWrite<W, E>,
+    buffer: B,
+) -> ForwardToSink<R, FramedWrite<W, E>, B>
+where
+    R: AsyncRead,
+    W: tokio::io::AsyncWrite,
+    E: Encoder<Bytes>,
+    E::Error: Into<io::Error>,
+    B: BufferStorage,
+{
+    forward_to_sink(reader, frames, buffer)
+}
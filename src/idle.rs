@@ -0,0 +1,52 @@
+use std::{
+    future::Future,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use crate::sleep::Sleeper;
+
+/// Fails a forward once no data has moved for a while, using sleeps from a
+/// user-supplied factory, so that it works with any runtime's timers. Like
+/// the `ActivityTimer`, activity is just recorded, and the sleep is renewed
+/// lazily when it finishes early. The clock starts when the timer is first
+/// polled.
+pub struct IdleTimeout {
+    duration: Duration,
+    last_activity: Option<Instant>,
+    sleeper: Sleeper,
+}
+
+impl IdleTimeout {
+    pub fn new<F, S>(duration: Duration, sleep: F) -> Self
+    where
+        F: FnMut(Duration) -> S + Send + 'static,
+        S: Future<Output = ()> + Send + 'static,
+    {
+        Self {
+            duration,
+            last_activity: None,
+            sleeper: Sleeper::new(sleep),
+        }
+    }
+
+    /// Record that some data moved just now
+    #[inline]
+    pub fn touch(&mut self) {
+        self.last_activity = Some(Instant::now());
+    }
+
+    /// Resolves once `duration` has passed since the most recent activity
+    pub fn poll_expired(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let last_activity = *self.last_activity.get_or_insert_with(Instant::now);
+
+        loop {
+            let elapsed = last_activity.elapsed();
+            if elapsed >= self.duration {
+                return Poll::Ready(());
+            }
+
+            futures::ready!(self.sleeper.poll_sleep(cx, self.duration - elapsed));
+        }
+    }
+}
@@ -1,97 +1,1428 @@
+mod abort;
+mod adaptive;
+mod bidi;
+#[cfg(feature = "http-body")]
+mod body;
 mod buffer;
+mod builder;
+mod callback;
+mod chain;
+mod checksum;
+#[cfg(feature = "async-compression")]
+mod compression;
+mod datagram;
+mod deadline;
+mod endpoint;
+mod ext;
+mod failover;
+#[cfg(feature = "tokio")]
+mod framed;
+mod idle;
+mod map_err;
+mod merge;
+#[cfg(feature = "testing")]
+pub mod mock;
+mod parts;
+mod pause;
+mod pool;
+mod probe;
+mod progress;
+mod pump;
+mod rate;
+mod reconnect;
+#[cfg(feature = "bytes")]
+mod records;
+mod rotate;
+mod sink;
+#[cfg(feature = "bytes")]
+mod sink_writer;
+mod sleep;
+mod state;
+mod stop;
+#[cfg(feature = "bytes")]
+mod stream;
+mod summary;
+mod sync;
+mod sync_cell;
+mod tap;
+mod tee;
+#[cfg(feature = "futures-timer")]
+mod timer;
+#[cfg(feature = "tokio")]
+mod tokio_io;
+mod transform;
+mod vectored;
 
 use std::{
     future::Future,
-    io::{self, IoSlice, IoSliceMut},
-    num::NonZeroUsize,
+    io,
+    num::{NonZeroU32, NonZeroU64, NonZeroUsize},
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 
+#[cfg(feature = "futures-timer")]
+use crate::timer::{ActivityTimer, StallWarning};
+use futures::future::{self, BoxFuture, FusedFuture, LocalBoxFuture};
+#[cfg(feature = "futures-timer")]
+use futures_timer::Delay;
 use pin_project::pin_project;
 
-use crate::buffer::{pair_len, DuplexBuffer};
+pub use crate::{
+    abort::{AbortError, AbortHandle, Abortable, AbortedForward},
+    bidi::{
+        copy_bidirectional, BidiForwarder, BidirectionalError, CopyBidirectional, Direction,
+        OnDirectionError,
+    },
+    buffer::{pair_len, BufferStorage, UninitBuffer},
+    builder::{BuildError, FlushPolicy, ForwarderBuilder, WriterClosePolicy},
+    chain::{forward_chain, ForwardChain, ReaderChain},
+    checksum::{Checksum, Checksummed, Verified},
+    datagram::{AsyncRecv, AsyncSend, DatagramForwarder},
+    deadline::{DeadlineError, RaceDeadline},
+    endpoint::{Destination, Endpoint, Source},
+    ext::{ForwardExt, ReceiveExt},
+    failover::Failover,
+    map_err::MapErr,
+    merge::{MergeError, MergeForwarder},
+    parts::ForwarderParts,
+    pause::PauseHandle,
+    pool::{BufferPool, PooledBuffer, VecPool},
+    probe::WriteProbe,
+    progress::{ProgressEvent, ProgressStream},
+    pump::DuplexPump,
+    rate::RateLimiter,
+    reconnect::{Backoff, Reconnect},
+    rotate::RotatingForwarder,
+    sink::BufferSink,
+    state::{ForwardProgress, ForwardStats, ForwarderPhase, ForwarderState, WaitingOn},
+    stop::{StopHandle, StopSignal},
+    summary::{ForwardSummary, Summarize},
+    sync::forward_sync,
+    tap::ForwardTap,
+    tee::{TeeError, TeeForwarder},
+    transform::{Transform, TransformWriter},
+};
+
+#[cfg(feature = "bytes")]
+pub use crate::{
+    records::{forward_records, RecordForwarder},
+    sink_writer::{forward_to_sink, ForwardToSink, SinkWriter},
+    stream::{forward_stream, ForwardStream},
+};
+
+#[cfg(feature = "tokio")]
+pub use crate::{
+    framed::{forward_from_framed, forward_into_framed, FrameChunks},
+    tokio_io::TokioIo,
+};
+
+#[cfg(feature = "async-compression")]
+pub use crate::compression::{Codec, CodecWriter};
+
+#[cfg(feature = "async-compression")]
+pub use async_compression;
+
+#[cfg(feature = "digest")]
+pub use crate::checksum::Digest;
+
+#[cfg(feature = "http-body")]
+pub use crate::body::BodyReader;
+
+use crate::{
+    adaptive::AdaptiveBuffer,
+    builder::Config,
+    callback::{Callback, ChunkCallback, ChunkMutCallback, CountCallback},
+    idle::IdleTimeout,
+    pause::{pause_pair, PauseState},
+    probe::Probe,
+    rate::RateLimit,
+    stop::stop_pair,
+};
+
+/// The maximum number of read-then-write cycles in a single poll, when no
+/// yield budget is set
+const MAX_CYCLES_PER_POLL: u32 = 16;
+
+/// Running tallies of notable I/O events, for diagnostics
+#[derive(Debug, Default, Clone, Copy)]
+struct Counters {
+    bytes_read: u64,
+    bytes_written: u64,
+
+    // The number of successful reads and writes
+    reads: u64,
+    writes: u64,
+
+    would_block_reads: u64,
+    would_block_writes: u64,
+    interrupted_reads: u64,
+    interrupted_writes: u64,
+
+    // The number of consecutive polls in which no data was read or written
+    stalled_polls: u32,
+}
+
+impl Counters {
+    /// A read error, with how far the forward got before it
+    fn read_error(&self, error: io::Error) -> ForwarderError {
+        ForwarderError::Read {
+            error,
+            bytes_read: self.bytes_read,
+            bytes_written: self.bytes_written,
+        }
+    }
+
+    /// A write error, with how far the forward got before it and the data
+    /// that was still buffered
+    fn write_error(&self, error: io::Error, unforwarded: Vec<u8>) -> ForwarderError {
+        ForwarderError::Write {
+            error,
+            bytes_read: self.bytes_read,
+            bytes_written: self.bytes_written,
+            unforwarded,
+        }
+    }
+}
 
+/// Everything in a [`Forwarder`] apart from its cancel signal, so that
+/// [`with_cancel`][Forwarder::with_cancel] can change the signal's type by
+/// moving the rest over as a whole
 #[pin_project]
-pub struct Forwarder<R, W, B> {
+struct Inner<R, W, B> {
     #[pin]
     reader: Option<R>,
 
     #[pin]
     writer: W,
 
-    buffer: DuplexBuffer<B>,
+    pump: DuplexPump<B>,
+
+    config: Config,
+
+    counters: Counters,
+
+    // True if an eager flush returned Pending, and needs to be resumed
+    flush_pending: bool,
+
+    // The number of successful reads and writes since we were last parked
+    // waiting for I/O
+    budget_used: u32,
+
+    #[cfg(feature = "futures-timer")]
+    #[pin]
+    deadline: Option<Delay>,
+
+    // Tracks how long it's been since a read produced any data
+    #[cfg(feature = "futures-timer")]
+    complete_on_idle: Option<ActivityTimer>,
+
+    // Warns when the buffer has been full for a while with no writes
+    #[cfg(feature = "futures-timer")]
+    stall_warning: Option<StallWarning>,
+
+    // Fails the forward if no data moves for too long
+    idle_timeout: Option<IdleTimeout>,
+
+    // Limits how fast data is read
+    rate_limit: Option<RateLimit>,
+
+    // Called with the size of each successful read and write
+    on_read: Option<CountCallback>,
+    on_write: Option<CountCallback>,
+
+    // Sees each chunk of data as it's read
+    inspect: Option<ChunkCallback>,
+
+    // Modifies each chunk of data as it's read
+    map_in_place: Option<ChunkMutCallback>,
+
+    // Sees each chunk of data once it's been mapped, for checksumming
+    checksum: Option<ChunkCallback>,
+
+    // Set if the forward can be paused from elsewhere
+    pause: Option<PauseState>,
+
+    // The number of bytes written since the writer was last flushed
+    unflushed: u64,
+
+    // If true, flush the writer before completing successfully
+    flush_on_completion: bool,
+
+    // If true, close the writer before completing successfully
+    close_on_completion: bool,
+
+    // True if everything that was buffered before the most recent read has
+    // been written, for the graceful writer close policy
+    drained: bool,
+
+    // With strict write accounting, the number of bytes offered to a write
+    // that returned `Pending`, which must be offered again unchanged
+    retry_len: Option<usize>,
+
+    // Resizes the buffer based on how full it's been
+    adaptive: Option<AdaptiveBuffer<B>>,
+
+    // Checks whether the writer is still open after a zero-length write, and
+    // whether it's ready for more data
+    write_probe: Option<Probe<W>>,
+
+    // True once the forward has completed or failed, for `FusedFuture`
+    terminated: bool,
+
+    // What the most recent poll left us waiting for
+    waiting_on: WaitingOn,
+
+    // The span that covers this forward, which is entered whenever it's
+    // polled
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+#[pin_project]
+pub struct Forwarder<R, W, B, C = future::Pending<()>> {
+    #[pin]
+    inner: Inner<R, W, B>,
+
+    // A signal that we should stop reading and finish up
+    #[pin]
+    cancel: Option<C>,
 }
 
-impl<R: futures::AsyncRead, W: futures::AsyncWrite, B: AsMut<[u8]>> Forwarder<R, W, B> {
+impl<R: futures::AsyncRead, W: futures::AsyncWrite, B: BufferStorage> Forwarder<R, W, B> {
+    /// Create a new `Forwarder` that forwards everything from `reader` into
+    /// `writer`, through `buffer`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buffer` is empty, since nothing could ever be forwarded
+    /// through it. [`ForwarderBuilder::build`] returns an error instead.
     pub fn new(reader: R, writer: W, buffer: B) -> Self {
+        Self::with_config(reader, writer, buffer, Config::default())
+    }
+
+    fn with_config(reader: R, writer: W, mut buffer: B, config: Config) -> Self {
+        assert!(buffer.capacity() > 0, "forwarder buffer is empty");
+
         Self {
-            reader: Some(reader),
-            writer,
-            buffer: DuplexBuffer::new(buffer),
+            inner: Inner {
+                reader: Some(reader),
+                writer,
+                pump: DuplexPump::new(buffer),
+                config,
+                counters: Counters::default(),
+                flush_pending: false,
+                budget_used: 0,
+                #[cfg(feature = "futures-timer")]
+                deadline: None,
+                #[cfg(feature = "futures-timer")]
+                complete_on_idle: None,
+                #[cfg(feature = "futures-timer")]
+                stall_warning: None,
+                idle_timeout: None,
+                rate_limit: None,
+                on_read: None,
+                on_write: None,
+                inspect: None,
+                map_in_place: None,
+                checksum: None,
+                pause: None,
+                unflushed: 0,
+                flush_on_completion: false,
+                close_on_completion: false,
+                drained: true,
+                retry_len: None,
+                adaptive: None,
+                write_probe: None,
+                terminated: false,
+                waiting_on: WaitingOn::Nothing,
+                #[cfg(feature = "tracing")]
+                span: tracing::debug_span!("forward"),
+            },
+            cancel: None,
+        }
+    }
+
+    /// Create a new `Forwarder` whose buffer starts out holding `prefill`,
+    /// which is written before anything from the reader. This is for cases
+    /// like protocol upgrades, where some of the stream has already been
+    /// read by the time forwarding starts. The prefilled bytes aren't counted
+    /// as read from the reader. Fails if `prefill` doesn't fit in the buffer.
+    pub fn new_with_prefilled(
+        reader: R,
+        writer: W,
+        buffer: B,
+        prefill: &[u8],
+    ) -> Result<Self, PrefillTooLarge> {
+        let mut forwarder = Self::new(reader, writer, buffer);
+
+        match forwarder.inner.pump.buffer.prefill(prefill) {
+            true => {
+                forwarder.inner.drained = prefill.is_empty();
+                Ok(forwarder)
+            }
+            false => Err(PrefillTooLarge {
+                len: prefill.len(),
+                capacity: forwarder.inner.pump.capacity(),
+            }),
+        }
+    }
+}
+
+impl<R: futures::AsyncRead, W: futures::AsyncWrite, B: BufferStorage, C> Forwarder<R, W, B, C> {
+    /// Set the maximum number of slices passed to a single vectored write.
+    /// Defaults to 1024, which is a common `IOV_MAX`.
+    pub fn with_max_iov(mut self, max_iov: NonZeroUsize) -> Self {
+        self.inner.config.max_iov = max_iov;
+        self
+    }
+
+    /// Flush the writer once the reader reaches EOF and everything has been
+    /// written, before completing, for writers like a `BufWriter` that hold
+    /// on to data. If the flush fails, the forward fails with
+    /// [`ForwarderError::FlushFailed`]. To close the writer instead, use
+    /// [`half_close_on_eof`][Self::half_close_on_eof]; closing flushes too,
+    /// so there's no need for both. Defaults to false.
+    pub fn flush_on_eof(mut self, flush: bool) -> Self {
+        self.inner.config.flush_on_eof = flush;
+        self
+    }
+
+    /// Flush the writer whenever the reader has no data for us and
+    /// everything has been written, so that data doesn't sit in a buffering
+    /// writer while the reader is quiet. This keeps interactive protocols
+    /// responsive without flushing after every write. Defaults to false.
+    pub fn flush_on_read_stall(mut self, flush: bool) -> Self {
+        self.inner.config.flush_on_read_stall = flush;
+        self
+    }
+
+    /// Stop reading after `limit` bytes, as though the reader had reached
+    /// EOF there, like [`std::io::Read::take`]. Reads are cut short so that
+    /// nothing past the limit is ever read; if the forwarder only borrows
+    /// the reader, it can go on to be used for whatever comes next, like
+    /// forwarding an HTTP body with a known `Content-Length`.
+    pub fn with_read_limit(mut self, limit: u64) -> Self {
+        self.inner.config.read_limit = Some(limit);
+        self
+    }
+
+    /// Fail with [`ForwarderError::IdleTimeout`] if no data is read or
+    /// written for `duration`. The timer comes from `sleep`, which is called
+    /// with how long to sleep for, so that any runtime's timers can be used,
+    /// like `tokio::time::sleep`. Sleeps that finish early, because there
+    /// was activity in the meantime, are renewed with the time remaining.
+    pub fn with_idle_timeout<F, S>(mut self, duration: Duration, sleep: F) -> Self
+    where
+        F: FnMut(Duration) -> S + Send + 'static,
+        S: Future<Output = ()> + Send + 'static,
+    {
+        self.inner.idle_timeout = Some(IdleTimeout::new(duration, sleep));
+        self
+    }
+
+    /// Fail with [`ForwarderError::IdleTimeout`] if no data is read or
+    /// written for `duration`, using `futures-timer` for the sleeps. See
+    /// [`with_idle_timeout`][Self::with_idle_timeout].
+    #[cfg(feature = "futures-timer")]
+    pub fn idle_timeout(self, duration: Duration) -> Self {
+        self.with_idle_timeout(duration, Delay::new)
+    }
+
+    /// Fail with [`ForwarderError::IdleTimeout`] if no data is read or
+    /// written for `duration`, using `tokio::time::sleep` for the sleeps, so
+    /// the forward has to run inside a tokio runtime with the time driver
+    /// enabled. See [`with_idle_timeout`][Self::with_idle_timeout].
+    #[cfg(feature = "tokio")]
+    pub fn tokio_idle_timeout(self, duration: Duration) -> Self {
+        self.with_idle_timeout(duration, tokio::time::sleep)
+    }
+
+    /// Limit how fast data is read to `bytes_per_sec`, with a token bucket
+    /// that allows bursts of up to `burst` bytes. Once the bucket is empty,
+    /// reads wait for a hundredth of a second's worth of data at a time,
+    /// rather than for the bucket to refill, so the pacing stays smooth. The
+    /// sleeps come from `sleep`, like with
+    /// [`with_idle_timeout`][Self::with_idle_timeout].
+    pub fn with_rate_limit<F, S>(
+        self,
+        bytes_per_sec: NonZeroU64,
+        burst: NonZeroU64,
+        sleep: F,
+    ) -> Self
+    where
+        F: FnMut(Duration) -> S + Send + 'static,
+        S: Future<Output = ()> + Send + 'static,
+    {
+        self.with_shared_rate_limit(RateLimiter::new(bytes_per_sec, burst), sleep)
+    }
+
+    /// Limit how fast data is read with a [`RateLimiter`] that can be shared
+    /// with other forwarders, for a global bandwidth budget. Otherwise, this
+    /// is the same as [`with_rate_limit`][Self::with_rate_limit].
+    pub fn with_shared_rate_limit<F, S>(mut self, limiter: RateLimiter, sleep: F) -> Self
+    where
+        F: FnMut(Duration) -> S + Send + 'static,
+        S: Future<Output = ()> + Send + 'static,
+    {
+        self.inner.rate_limit = Some(RateLimit::new(limiter, sleep));
+        self
+    }
+
+    /// Limit how fast data is read, using `futures-timer` for the sleeps.
+    /// See [`with_rate_limit`][Self::with_rate_limit].
+    #[cfg(feature = "futures-timer")]
+    pub fn rate_limit(self, bytes_per_sec: NonZeroU64, burst: NonZeroU64) -> Self {
+        self.with_rate_limit(bytes_per_sec, burst, Delay::new)
+    }
+
+    /// Limit how fast data is read with a shared [`RateLimiter`], using
+    /// `futures-timer` for the sleeps. See
+    /// [`with_shared_rate_limit`][Self::with_shared_rate_limit].
+    #[cfg(feature = "futures-timer")]
+    pub fn shared_rate_limit(self, limiter: RateLimiter) -> Self {
+        self.with_shared_rate_limit(limiter, Delay::new)
+    }
+
+    /// Forward exactly `len` bytes, for length-delimited protocols where a
+    /// short stream means corruption. Like
+    /// [`with_read_limit`][Self::with_read_limit], nothing past `len` is
+    /// read, but if the forward ends before `len` bytes have been read, it
+    /// fails with [`ForwarderError::UnexpectedEof`] once everything that was
+    /// read has been written.
+    pub fn copy_exact(mut self, len: u64) -> Self {
+        self.inner.config.read_limit = Some(len);
+        self.inner.config.exact = true;
+        self
+    }
+
+    /// Fail with [`ForwarderError::NoProgress`] after `polls` consecutive
+    /// polls in which no data was read or written. This catches
+    /// pathological cases that no timer would, like a storm of `Interrupted`
+    /// errors or a waker that fires when nothing is ready. A forward that's
+    /// legitimately waiting for I/O isn't polled, so it doesn't count, but
+    /// spurious polls from the executor or an enclosing future do.
+    pub fn max_polls_without_progress(mut self, polls: NonZeroU32) -> Self {
+        self.inner.config.max_stalled_polls = Some(polls);
+        self
+    }
+
+    /// Offer the writer at most `max` bytes per write, for writers that
+    /// behave badly with large writes, or to bound the size of each chunk
+    /// that reaches the other side.
+    pub fn with_max_write_size(mut self, max: NonZeroUsize) -> Self {
+        self.inner.config.max_write_size = Some(max);
+        self
+    }
+
+    /// In debug builds, panic if `aliased` reports that the reader and the
+    /// writer are the same underlying stream, to catch accidental
+    /// self-forwards. How to tell is up to the caller, such as by comparing
+    /// file descriptors or pointers. Forwarding a stream into itself does
+    /// work, as a loopback that echoes everything back, since each poll
+    /// reads and then writes in turn; this is for when that isn't intended.
+    pub fn assert_distinct<F: FnOnce(&R, &W) -> bool>(self, aliased: F) -> Self {
+        if cfg!(debug_assertions) {
+            let reader = self
+                .inner
+                .reader
+                .as_ref()
+                .expect("a new forwarder has a reader");
+            assert!(
+                !aliased(reader, &self.inner.writer),
+                "forwarder reader and writer are the same stream"
+            );
+        }
+
+        self
+    }
+
+    /// Delay writes until at least `min_read` bytes have been buffered, to
+    /// avoid issuing a write for every tiny read. Writes will still happen
+    /// with less buffered data if the reader reaches EOF or the buffer is
+    /// full.
+    pub fn with_read_aggregation(mut self, min_read: usize) -> Self {
+        self.inner.config.min_read = min_read;
+        self
+    }
+
+    /// If true, flush the writer after every poll that successfully wrote
+    /// some data, so that it reaches its destination as soon as possible.
+    /// This trades throughput for latency. No new data is read while a flush
+    /// is in progress.
+    ///
+    /// This is the same as [`FlushPolicy::EveryWrite`].
+    pub fn eager_flush(self, eager_flush: bool) -> Self {
+        self.with_flush_policy(match eager_flush {
+            true => FlushPolicy::EveryWrite,
+            false => FlushPolicy::Never,
+        })
+    }
+
+    /// Choose when the writer is flushed while data is being forwarded.
+    /// Defaults to [`FlushPolicy::Never`].
+    pub fn with_flush_policy(mut self, policy: FlushPolicy) -> Self {
+        self.inner.config.flush_policy = policy;
+        self
+    }
+
+    /// Cooperatively yield to the executor after every `ops` successful reads
+    /// and writes, even if more I/O could be done immediately. This prevents
+    /// a busy forward from starving other work, such as the other branches
+    /// of a biased `select!`. The budget is replenished whenever the
+    /// forwarder is parked waiting for I/O.
+    pub fn with_yield_budget(mut self, ops: NonZeroU32) -> Self {
+        self.inner.config.yield_budget = Some(ops);
+        self
+    }
+
+    /// If true, close the writer once the reader reaches EOF and all of the
+    /// buffered data has been written, so that the EOF propagates to the
+    /// other side; for a TCP stream, this sends a FIN. Stopping for any other
+    /// reason, like a cancellation, doesn't close the writer. If the close
+    /// fails, the forward fails with [`ForwarderError::CloseFailed`].
+    /// Defaults to false.
+    ///
+    /// This closes the whole writer, so it's not suitable for one direction
+    /// of a duplex stream that's still being used in the other direction,
+    /// unless closing only shuts down the write side.
+    pub fn half_close_on_eof(mut self, half_close: bool) -> Self {
+        self.inner.config.half_close_on_eof = half_close;
+        self
+    }
+
+    /// Instrument the forward with `span` instead of the default `forward`
+    /// span, so that it can carry your own name and fields, like the
+    /// addresses of the connection. The span is entered whenever the
+    /// forwarder is polled.
+    #[cfg(feature = "tracing")]
+    pub fn with_span(mut self, span: tracing::Span) -> Self {
+        self.inner.span = span;
+        self
+    }
+
+    /// Choose what happens when the writer stops accepting data. By default,
+    /// it's always an error; with
+    /// [`WriterClosePolicy::GracefulWhenDrained`], a writer that closes in
+    /// between the data from separate reads, while the reader is still live,
+    /// is treated as asking for the forward to stop, and the forward
+    /// completes successfully. The data that wasn't written stays in the
+    /// buffer, for [`into_parts`][Self::into_parts].
+    pub fn with_writer_close_policy(mut self, policy: WriterClosePolicy) -> Self {
+        self.inner.config.writer_close = policy;
+        self
+    }
+
+    /// The [`AsyncWrite`][futures::AsyncWrite] contract is that a write that
+    /// returns `Pending` hasn't written anything, so we only ever advance
+    /// past the bytes that a write reports as accepted, and we offer the
+    /// same bytes again on the next write. By default, though, the retry
+    /// may also include data that was read in the meantime.
+    ///
+    /// Some writers, like TLS streams built on OpenSSL, instead take the
+    /// data internally when they return `Pending`, and require the retry to
+    /// offer exactly the same bytes; they report them as written once the
+    /// retry succeeds. If true, after a write returns `Pending` or
+    /// `WouldBlock`, the next write offers exactly the bytes that were
+    /// offered before, and nothing more. Defaults to false.
+    pub fn strict_write_accounting(mut self, strict: bool) -> Self {
+        self.inner.config.strict_write_accounting = strict;
+        self
+    }
+
+    /// Stop the forward cleanly when `signal` completes. Once it does, we
+    /// stop reading new data, but everything that's already been read is
+    /// still written and the writer is flushed before we complete
+    /// successfully, as though the reader had reached EOF.
+    pub fn with_cancel<S: Future<Output = ()>>(self, signal: S) -> Forwarder<R, W, B, S> {
+        Forwarder {
+            inner: self.inner,
+            cancel: Some(signal),
         }
     }
+
+    /// Call `callback` with the number of bytes read after each successful
+    /// read, for feeding progress into an existing reporting system. The
+    /// callback runs inside the forwarder's poll, so it should be quick.
+    pub fn on_read(mut self, callback: impl FnMut(usize) + Send + 'static) -> Self {
+        self.inner.on_read = Some(Callback::new(Box::new(callback)));
+        self
+    }
+
+    /// Call `callback` with the number of bytes written after each
+    /// successful write. See [`on_read`][Self::on_read].
+    pub fn on_write(mut self, callback: impl FnMut(usize) + Send + 'static) -> Self {
+        self.inner.on_write = Some(Callback::new(Box::new(callback)));
+        self
+    }
+
+    /// Call `inspect` on every chunk of data as it's read, before it's
+    /// written, for things like protocol sniffing or logging traffic. The
+    /// data from a single read is passed in order, but it may be split into
+    /// more than one chunk where it wraps around the end of the buffer.
+    pub fn inspect(mut self, inspect: impl FnMut(&[u8]) + Send + 'static) -> Self {
+        self.inner.inspect = Some(Callback::new(Box::new(inspect)));
+        self
+    }
+
+    /// Call `map` on every chunk of data as it's read, to modify it in place
+    /// before it's written, for simple length-preserving transformations
+    /// like XOR masking. Chunks are passed in order, so `map` can keep track
+    /// of its position in the stream, but the data from a single read may be
+    /// split into more than one chunk. Chunks are passed to
+    /// [`inspect`][Self::inspect] before they're mapped. To change the
+    /// length of the data, use a [`Transform`].
+    ///
+    /// This is also the hook for stream ciphers, like ChaCha20 or AES-CTR:
+    /// since the keystream is applied to the data in order, `map` can just
+    /// be the cipher's `apply_keystream`, and the data is encrypted right in
+    /// the forwarder's buffer, without being copied.
+    pub fn map_in_place(mut self, map: impl FnMut(&mut [u8]) + Send + 'static) -> Self {
+        self.inner.map_in_place = Some(Callback::new(Box::new(map)));
+        self
+    }
+
+    /// Create a [`StopHandle`] that gracefully stops the forward, for
+    /// server shutdown paths. This is [`with_cancel`][Self::with_cancel]
+    /// with a signal that can be triggered from anywhere: once the handle is
+    /// used, we stop reading, write out and flush whatever is buffered, and
+    /// resolve with the total number of bytes written.
+    pub fn with_stop_handle(self) -> (Forwarder<R, W, B, StopSignal>, StopHandle) {
+        let (handle, signal) = stop_pair();
+        (self.with_cancel(signal), handle)
+    }
+
+    /// Create a [`PauseHandle`] that can temporarily suspend the forward
+    /// without tearing it down. While it's paused, the forward parks itself
+    /// and isn't woken until it's resumed. Idle timers aren't checked while
+    /// the forward is paused, but a deadline still applies.
+    pub fn with_pause_handle(mut self) -> (Self, PauseHandle) {
+        let (handle, state) = pause_pair();
+        self.inner.pause = Some(state);
+        (self, handle)
+    }
+
+    /// Add overflow buffers after the ring buffer. When the reader can
+    /// provide more data than the ring has room for, a single vectored read
+    /// can scatter it across the ring's free space and these buffers. The
+    /// ring won't accept any more reads until the overflow has been written
+    /// out, to keep the data in order. At most 14 overflow buffers are used;
+    /// any beyond that are ignored.
+    pub fn with_overflow(mut self, buffers: Vec<Box<[u8]>>) -> Self {
+        self.inner.pump.buffer.set_overflow(buffers);
+        self
+    }
+
+    /// Consult the writer's [`WriteProbe`] when it returns `Ok(0)`. If it's
+    /// still writable, the zero-length write is treated as backpressure
+    /// rather than as the writer closing early. The probe is also checked
+    /// before each read while there's buffered data; if the writer isn't
+    /// ready for more, the read is skipped, so that the buffer doesn't fill
+    /// up ahead of a slow writer.
+    pub fn with_write_probe(mut self) -> Self
+    where
+        W: WriteProbe,
+    {
+        self.inner.write_probe = Some(Probe::new());
+        self
+    }
+
+    /// Check whether the writer is ready for more data, using its
+    /// [`WriteProbe`] if one was enabled with
+    /// [`with_write_probe`][Self::with_write_probe]. Without a probe, the
+    /// writer is always considered ready.
+    pub fn poll_writer_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.project().inner.project();
+
+        match this.write_probe {
+            Some(probe) => (probe.poll_write_ready)(this.writer, cx),
+            None => Poll::Ready(Ok(())),
+        }
+    }
+
+    /// Box and pin this forwarder, erasing its type, so that forwards over
+    /// different kinds of streams can be stored together.
+    pub fn boxed(self) -> BoxFuture<'static, Result<u64, ForwarderError>>
+    where
+        R: Send + 'static,
+        W: Send + 'static,
+        B: Send + 'static,
+        C: Future<Output = ()> + Send + 'static,
+    {
+        Box::pin(self)
+    }
+
+    /// Like [`boxed`][Self::boxed], but for streams that aren't `Send`.
+    pub fn boxed_local(self) -> LocalBoxFuture<'static, Result<u64, ForwarderError>>
+    where
+        R: 'static,
+        W: 'static,
+        B: 'static,
+        C: Future<Output = ()> + 'static,
+    {
+        Box::pin(self)
+    }
+
+    /// Fail with [`ForwarderError::DeadlineExceeded`] if the forward hasn't
+    /// completed by `at`. Unlike an idle timeout, the deadline is not pushed
+    /// back when data is transferred. When the deadline passes, we make a
+    /// single best-effort attempt to flush the writer before returning the
+    /// error.
+    #[cfg(feature = "futures-timer")]
+    pub fn with_deadline(mut self, at: std::time::Instant) -> Self {
+        let delay = Delay::new(at.saturating_duration_since(std::time::Instant::now()));
+
+        self.inner.deadline = Some(delay);
+        self
+    }
+
+    /// Complete successfully once no data has been read for `duration` and
+    /// everything that was read has been written. This is for
+    /// drain-and-stop use cases, like tailing a stream until it goes quiet,
+    /// where a reader that's idle but never reaches EOF should be treated as
+    /// finished rather than as an error. The idle clock starts when the
+    /// forwarder is first polled.
+    #[cfg(feature = "futures-timer")]
+    pub fn complete_on_idle(mut self, duration: std::time::Duration) -> Self {
+        self.inner.complete_on_idle = Some(ActivityTimer::new(duration));
+        self
+    }
+
+    /// Call `callback` if the buffer has been full for `duration` without
+    /// any successful writes, which usually means the writer is wedged. The
+    /// callback gets the number of buffered bytes and how long it's been
+    /// since the last successful write. This doesn't fail the forward; if
+    /// the stall continues, the callback is called again after every further
+    /// `duration`, with the total stall time so far.
+    #[cfg(feature = "futures-timer")]
+    pub fn stall_warn_after(
+        mut self,
+        duration: std::time::Duration,
+        callback: impl FnMut(usize, std::time::Duration) + Send + 'static,
+    ) -> Self {
+        self.inner.stall_warning = Some(StallWarning::new(duration, callback));
+        self
+    }
+}
+
+impl<R: futures::AsyncRead, W: futures::AsyncWrite> Forwarder<R, W, UninitBuffer> {
+    /// Create a new `Forwarder` with a freshly allocated buffer of the given
+    /// capacity. The buffer isn't zeroed up front; it's initialized
+    /// incrementally as data is read into it.
+    pub fn with_capacity(reader: R, writer: W, capacity: usize) -> Self {
+        Self::new(reader, writer, UninitBuffer::new(capacity))
+    }
+}
+
+impl<R: futures::AsyncRead, W: futures::AsyncWrite> Forwarder<R, W, Vec<u8>> {
+    /// Create a new `Forwarder` whose buffer starts out with `initial`
+    /// bytes, and grows as needed, up to `max` bytes. When the buffer has
+    /// been full at the end of several polls in a row, it's the bottleneck,
+    /// so its capacity is doubled.
+    pub fn adaptive_buffer(reader: R, writer: W, initial: usize, max: usize) -> Self {
+        let mut forwarder = Self::new(reader, writer, vec![0; initial]);
+        forwarder.inner.adaptive = Some(AdaptiveBuffer::new(initial, max));
+        forwarder
+    }
+}
+
+impl<R: futures::AsyncRead, W: futures::AsyncWrite, C> Forwarder<R, W, Vec<u8>, C> {
+    /// If true, and the buffer was created with
+    /// [`adaptive_buffer`][Forwarder::adaptive_buffer], shrink it back to its
+    /// initial size whenever it's empty and we're waiting for the reader.
+    /// This keeps idle connections cheap. Defaults to false.
+    pub fn shrink_when_idle(mut self, shrink: bool) -> Self {
+        if let Some(adaptive) = self.inner.adaptive.as_mut() {
+            adaptive.shrink_when_idle = shrink;
+        }
+        self
+    }
+}
+
+impl<R: futures::AsyncRead, W: futures::AsyncWrite, P: BufferPool>
+    Forwarder<R, W, PooledBuffer<P>>
+{
+    /// Create a new `Forwarder` with a buffer leased from `pool`. The buffer
+    /// is returned to the pool when the forwarder is dropped.
+    pub fn from_pool(reader: R, writer: W, pool: P) -> Self {
+        Self::new(reader, writer, PooledBuffer::new(pool))
+    }
+}
+
+/// Forward everything from `reader` into `writer`, through `buffer`, and
+/// return the total number of bytes written. This is a drop-in replacement
+/// for [`futures::io::copy`] with a caller-provided buffer; use
+/// [`Forwarder`] directly for more options.
+pub async fn forward<R, W, B>(reader: R, writer: W, buffer: B) -> Result<u64, ForwarderError>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+{
+    Forwarder::new(reader, writer, buffer).await
+}
+
+/// Forward from `reader` into `writer` while only borrowing them, so that
+/// both can still be used once the forward completes. This is the same as
+/// passing the references to [`Forwarder::new`]; it just spells out the
+/// `Unpin` bounds that make it work.
+pub fn forward_ref<'a, R, W, B>(
+    reader: &'a mut R,
+    writer: &'a mut W,
+    buffer: B,
+) -> Forwarder<&'a mut R, &'a mut W, B>
+where
+    R: futures::AsyncRead + Unpin + ?Sized,
+    W: futures::AsyncWrite + Unpin + ?Sized,
+    B: BufferStorage,
+{
+    Forwarder::new(reader, writer, buffer)
+}
+
+impl<R, W, B, C> Forwarder<R, W, B, C> {
+    /// Get a reference to the reader, or `None` if it's reached EOF or been
+    /// detached
+    #[inline]
+    #[must_use]
+    pub fn get_reader(&self) -> Option<&R> {
+        self.inner.reader.as_ref()
+    }
+
+    /// Get a mutable reference to the reader, or `None` if it's reached EOF
+    /// or been detached. Reading from it directly will lose that data from
+    /// the forward.
+    #[inline]
+    #[must_use]
+    pub fn get_reader_mut(&mut self) -> Option<&mut R> {
+        self.inner.reader.as_mut()
+    }
+
+    /// Get a pinned mutable reference to the reader, or `None` if it's
+    /// reached EOF or been detached
+    #[inline]
+    #[must_use]
+    pub fn get_reader_pin_mut(self: Pin<&mut Self>) -> Option<Pin<&mut R>> {
+        self.project().inner.project().reader.as_pin_mut()
+    }
+
+    /// Get a reference to the writer
+    #[inline]
+    #[must_use]
+    pub fn get_writer(&self) -> &W {
+        &self.inner.writer
+    }
+
+    /// Get a mutable reference to the writer. Writing to it directly will
+    /// interleave that data with the forward's.
+    #[inline]
+    #[must_use]
+    pub fn get_writer_mut(&mut self) -> &mut W {
+        &mut self.inner.writer
+    }
+
+    /// Get a pinned mutable reference to the writer
+    #[inline]
+    #[must_use]
+    pub fn get_writer_pin_mut(self: Pin<&mut Self>) -> Pin<&mut W> {
+        self.project().inner.project().writer
+    }
+
+    /// Swap in a fresh writer after the current one failed, so that the
+    /// forward can resume from whatever is still buffered. A write that
+    /// fails leaves the buffer unchanged, so nothing is lost.
+    pub(crate) fn replace_writer(self: Pin<&mut Self>, writer: W) {
+        let mut this = self.project().inner.project();
+
+        this.writer.set(writer);
+        *this.retry_len = None;
+        *this.flush_pending = false;
+        *this.unflushed = 0;
+        *this.terminated = false;
+    }
+
+    /// Stop reading, as though the reader had reached EOF, and hand the
+    /// reader back for reuse. This is for when the application knows the
+    /// transfer is over, like when a protocol-level end marker was seen
+    /// elsewhere. Everything already buffered is still written, and the
+    /// writer is flushed before the forward completes. Returns `None` if
+    /// the reader was already done.
+    ///
+    /// The forward has to be polled again to finish, and this doesn't wake
+    /// it. To stop a forward that's running in another task, use
+    /// [`with_stop_handle`][Self::with_stop_handle].
+    pub fn detach_reader(self: Pin<&mut Self>) -> Option<R>
+    where
+        R: Unpin,
+    {
+        let this = self.project().inner.project();
+        let reader = this.reader.get_mut().take()?;
+
+        *this.flush_on_completion = true;
+        *this.close_on_completion = this.config.half_close_on_eof;
+
+        Some(reader)
+    }
+
+    /// The number of reads that failed with `WouldBlock`
+    #[inline]
+    #[must_use]
+    pub fn would_block_reads(&self) -> u64 {
+        self.inner.counters.would_block_reads
+    }
+
+    /// The number of writes that failed with `WouldBlock`
+    #[inline]
+    #[must_use]
+    pub fn would_block_writes(&self) -> u64 {
+        self.inner.counters.would_block_writes
+    }
+
+    /// The number of reads that failed with `Interrupted`
+    #[inline]
+    #[must_use]
+    pub fn interrupted_reads(&self) -> u64 {
+        self.inner.counters.interrupted_reads
+    }
+
+    /// The number of writes that failed with `Interrupted`
+    #[inline]
+    #[must_use]
+    pub fn interrupted_writes(&self) -> u64 {
+        self.inner.counters.interrupted_writes
+    }
+
+    /// Un-wrap the ring buffer, so that all of the data that has been read
+    /// but not yet written is contiguous, and return it. This is for
+    /// interop with APIs that need a single `&[u8]`. It costs a copy of the
+    /// whole buffer, so it's not meant to be called on every poll. Data in
+    /// overflow buffers isn't included.
+    pub fn compact_buffer(self: Pin<&mut Self>) -> &[u8]
+    where
+        B: BufferStorage,
+    {
+        let buffer = &mut self.project().inner.project().pump.buffer;
+        buffer.compact();
+
+        let [contiguous, _] = buffer.get_buffers().write;
+        contiguous
+    }
+
+    /// Get a snapshot of the forward's progress, for things like status
+    /// endpoints. This is cheap enough to call whenever it's needed.
+    #[must_use]
+    pub fn stats(&self) -> ForwardStats {
+        ForwardStats {
+            bytes_read: self.inner.counters.bytes_read,
+            bytes_written: self.inner.counters.bytes_written,
+            buffered: self.inner.pump.buffered_len(),
+            reads: self.inner.counters.reads,
+            writes: self.inner.counters.writes,
+            reader_eof: self.inner.reader.is_none(),
+            waiting_on: self.inner.waiting_on,
+        }
+    }
+
+    /// Get a snapshot of the internal state of the forwarder, for logging
+    /// and debugging.
+    #[must_use]
+    pub fn debug_state(&self) -> ForwarderState {
+        let (read_head, write_head) = self.inner.pump.buffer.heads();
+
+        let phase = match (self.inner.reader.is_some(), self.inner.pump.write_ready()) {
+            (true, _) => ForwarderPhase::Forwarding,
+            (false, true) => ForwarderPhase::Draining,
+            (false, false) if self.inner.flush_pending => ForwarderPhase::Flushing,
+            (false, false) if self.inner.flush_on_completion => ForwarderPhase::Flushing,
+            (false, false) if self.inner.close_on_completion => ForwarderPhase::Flushing,
+            (false, false) => ForwarderPhase::Finished,
+        };
+
+        ForwarderState {
+            read_head,
+            write_head,
+            buffered: self.inner.pump.buffered_len(),
+            capacity: self.inner.pump.capacity(),
+            bytes_read: self.inner.counters.bytes_read,
+            bytes_written: self.inner.counters.bytes_written,
+            phase,
+        }
+    }
+}
+
+/// Error from [`Forwarder::new_with_prefilled`], when the data to prefill the
+/// buffer with doesn't fit in it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefillTooLarge {
+    pub len: usize,
+    pub capacity: usize,
 }
 
 #[derive(Debug)]
 pub enum ForwarderError {
-    Read(io::Error),
-    Write(io::Error),
-    WriteClosedEarly,
+    /// Reading failed. `bytes_read` and `bytes_written` are how far the
+    /// forward got before that, so that it can be resumed from there.
+    Read {
+        error: io::Error,
+        bytes_read: u64,
+        bytes_written: u64,
+    },
+
+    /// Writing failed. `bytes_read` and `bytes_written` are how far the
+    /// forward got before that, so that it can be resumed from there, and
+    /// `unforwarded` is the data that had been read but was still buffered,
+    /// in order, so that it can be retried elsewhere.
+    Write {
+        error: io::Error,
+        bytes_read: u64,
+        bytes_written: u64,
+        unforwarded: Vec<u8>,
+    },
+
+    /// The writer stopped accepting data (returned `Ok(0)`) before
+    /// everything was forwarded. `forwarded` is the number of bytes that
+    /// were successfully written before that happened, and `unforwarded` is
+    /// the data that had been read but was still buffered, in order, so
+    /// that it can be retried elsewhere.
+    WriteClosedEarly {
+        forwarded: u64,
+        unforwarded: Vec<u8>,
+    },
+
+    /// All the data was written, but flushing the writer failed
+    FlushFailed(io::Error),
+
+    /// All the data was written, but closing the writer failed
+    CloseFailed(io::Error),
+
+    /// The deadline set with `Forwarder::with_deadline` passed before the
+    /// forward finished
+    DeadlineExceeded,
+
+    /// No data was read or written for the duration set with
+    /// [`with_idle_timeout`][Forwarder::with_idle_timeout]
+    IdleTimeout,
+
+    /// With [`copy_exact`][Forwarder::copy_exact], the reader reached EOF
+    /// before the expected number of bytes. `copied` is the number of bytes
+    /// that were read and written before that happened.
+    UnexpectedEof {
+        copied: u64,
+    },
+
+    /// No data was read or written for the number of consecutive polls set
+    /// with [`max_polls_without_progress`][Forwarder::max_polls_without_progress].
+    /// `state` is a snapshot of the forwarder when it gave up, and the
+    /// counters say how many reads and writes were interrupted or would
+    /// have blocked over the whole forward.
+    NoProgress {
+        polls: u32,
+        state: ForwarderState,
+        interrupted_reads: u64,
+        interrupted_writes: u64,
+        would_block_reads: u64,
+        would_block_writes: u64,
+    },
+
+    /// With `forward_records`, the buffer filled up without a delimiter, so
+    /// a record didn't fit in it
+    RecordTooLong {
+        capacity: usize,
+    },
+
+    /// With [`verify_checksum`][Forwarder::verify_checksum], everything was
+    /// forwarded, but the checksum of the data didn't match
+    DigestMismatch {
+        expected: Vec<u8>,
+        actual: Vec<u8>,
+    },
 }
 
 impl ForwarderError {
     pub fn into_io_error(self) -> io::Error {
         match self {
-            Self::Read(err) => err,
-            Self::Write(err) => err,
-            Self::WriteClosedEarly => io::ErrorKind::WriteZero.into(),
+            Self::Read { error, .. } => error,
+            Self::Write { error, .. } => error,
+            Self::WriteClosedEarly { .. } => io::ErrorKind::WriteZero.into(),
+            Self::FlushFailed(err) => err,
+            Self::CloseFailed(err) => err,
+            Self::DeadlineExceeded => io::ErrorKind::TimedOut.into(),
+            Self::IdleTimeout => io::ErrorKind::TimedOut.into(),
+            Self::UnexpectedEof { .. } => io::ErrorKind::UnexpectedEof.into(),
+            Self::NoProgress { polls, .. } => io::Error::other(format!(
+                "forwarder made no progress in {polls} consecutive polls"
+            )),
+            Self::RecordTooLong { capacity } => io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("record doesn't fit in the {capacity} byte buffer"),
+            ),
+            Self::DigestMismatch { .. } => io::Error::new(
+                io::ErrorKind::InvalidData,
+                "checksum of the forwarded data didn't match",
+            ),
         }
     }
 }
 
-impl<R: futures::AsyncRead, W: futures::AsyncWrite, B: AsMut<[u8]>> Future for Forwarder<R, W, B> {
-    type Output = Result<(), ForwarderError>;
+impl<R, W, B, C> Forwarder<R, W, B, C>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+{
+    fn poll_forward(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), ForwarderError>> {
+        let this = self.project();
+        let mut cancel = this.cancel;
+        let mut this = this.inner.project();
+
+        // Check the deadline before doing any more I/O. If it's passed, give
+        // the writer one chance to flush whatever we've already written to
+        // it, so that the peer isn't left with a torn write if it can be
+        // avoided, then fail.
+        #[cfg(feature = "futures-timer")]
+        if let Some(deadline) = this.deadline.as_mut().as_pin_mut() {
+            if deadline.poll(cx).is_ready() {
+                let _ = this.writer.as_mut().poll_flush(cx);
+                return Poll::Ready(Err(ForwarderError::DeadlineExceeded));
+            }
+        }
+
+        // If an eager flush is in progress, finish it before doing anything
+        // else. We don't want to read more data until it's done.
+        if *this.flush_pending {
+            match this.writer.as_mut().poll_flush(cx) {
+                Poll::Pending => {
+                    *this.waiting_on = WaitingOn::Flush;
+                    return Poll::Pending;
+                }
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(ForwarderError::FlushFailed(err))),
+                Poll::Ready(Ok(())) => {
+                    *this.flush_pending = false;
+                    *this.unflushed = 0;
+                }
+            }
+        }
+
+        // If we've been paused, park until we're resumed. The pause state
+        // wakes us when that happens.
+        if let Some(pause) = this.pause {
+            if pause.check(cx) {
+                *this.waiting_on = WaitingOn::Paused;
+                return Poll::Pending;
+            }
+        }
+
+        // If we've used up our budget, yield to the executor before doing any
+        // more work.
+        if let Some(budget) = this.config.yield_budget {
+            if *this.budget_used >= budget.get() {
+                *this.budget_used = 0;
+                *this.waiting_on = WaitingOn::Nothing;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+        }
 
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        let mut this = self.project();
+        // Whenever we return `Pending`, something has to be set up to wake
+        // us again: either an I/O poll that returned `Pending` registered the
+        // waker, or we woke it ourselves. This tracks the former, so that we
+        // can check it in debug builds. Timers and the cancel signal don't
+        // count; they're only a backstop.
+        let mut wakeup_registered = false;
+
+        // Set to true if a read or write failed with `WouldBlock`. Unlike
+        // `Pending`, that doesn't register the waker, so we have to retry it
+        // ourselves.
+        let mut would_block = false;
+
+        // If we've been cancelled, treat it like EOF on the reader: stop
+        // reading, and finish once the buffer has drained.
+        if let Some(signal) = cancel.as_mut().as_pin_mut() {
+            if signal.poll(cx).is_ready() {
+                cancel.set(None);
+                *this.flush_on_completion = true;
+                this.reader.set(None);
+            }
+        }
 
         // Basically: attempt to read once, then attempt to write once. If
         // a read or a write succeed but there's more relevant buffer available,
         // we signal the waker immediately. Smartly call wake if a read or write
         // returns Poll::Ready and we can immediately do more work (we don't
         // want an unbounded loop in here)
+        //
+        // The exception is when a write frees up space that the reader can
+        // immediately fill: in that case we go straight back around to read
+        // into it, for a bounded number of cycles, so that both sides stay
+        // saturated.
 
         // These are set to true after a successful read or write (or in some
         // other cases) to indicate that we should immediately wake the waker
-        // because there's more work immediately possible
-        let mut write_ready = false;
-        let mut read_ready = false;
-
-        if let Some(reader) = this.reader.as_mut().as_pin_mut() {
-            let [b1, b2] = this.buffer.get_buffers().read;
-            let read_buffer_len = pair_len(&[b1, b2]);
-
-            // only perform a read if there's room
-            if read_buffer_len > 0 {
-                match reader.poll_read_vectored(cx, &mut [IoSliceMut::new(b1), IoSliceMut::new(b2)])
-                {
+        // because there's more work immediately possible. They describe the
+        // most recent cycle.
+        let mut write_ready;
+        let mut read_ready;
+
+        // Set to true if the reader has registered the waker, so that there's
+        // no point in trying it again right now
+        let mut read_blocked;
+
+        // Set to true if we actually wrote some data this poll
+        let mut wrote = false;
+
+        // These are set to true when the reader, the writer, or a timer
+        // registered the waker, for reporting what we're waiting on
+        let mut reader_waiting;
+        let mut writer_waiting;
+        let mut timer_waiting;
+
+        let mut cycles = 0;
+
+        loop {
+            write_ready = false;
+            read_ready = false;
+            read_blocked = false;
+            reader_waiting = false;
+            writer_waiting = false;
+            timer_waiting = false;
+            let mut wrote_this_cycle = false;
+
+            // If the writer has a probe, and it isn't ready for more data,
+            // don't read any more into the buffer until it is. The probe
+            // will wake us when the writer is ready.
+            if let Some(probe) = this.write_probe.as_ref() {
+                if this.pump.write_ready() {
+                    match (probe.poll_write_ready)(this.writer.as_mut(), cx) {
+                        Poll::Pending => {
+                            read_blocked = true;
+                            wakeup_registered = true;
+                            writer_waiting = true;
+                        }
+                        Poll::Ready(Ok(())) => {}
+                        Poll::Ready(Err(err)) => {
+                            return Poll::Ready(Err(this
+                                .counters
+                                .write_error(err, this.pump.buffer.buffered_data())))
+                        }
+                    }
+                }
+            }
+
+            // Once the read limit has been reached, the reader is done, as
+            // though it had reached EOF
+            let read_remaining = this
+                .config
+                .read_limit
+                .map(|limit| limit - this.counters.bytes_read);
+            let mut reader_eof = read_remaining == Some(0);
+
+            // With a rate limit, only read as much as the bucket allows. If
+            // it's empty, its sleep registered the waker.
+            let mut rate_allowance = None;
+
+            if let Some(rate) = this.rate_limit.as_mut().filter(|_| {
+                this.reader.is_some() && !read_blocked && !reader_eof && this.pump.read_ready()
+            }) {
+                match rate.poll_allowance(cx) {
+                    Poll::Ready(allowance) => rate_allowance = Some(allowance),
+                    Poll::Pending => {
+                        read_blocked = true;
+                        wakeup_registered = true;
+                        timer_waiting = true;
+                    }
+                }
+            }
+
+            if let Some(reader) = this
+                .reader
+                .as_mut()
+                .as_pin_mut()
+                .filter(|_| !read_blocked && !reader_eof && this.pump.read_ready())
+            {
+                // Only perform a read if there's room. Note whether the
+                // buffer was empty beforehand, for `drained`.
+                let was_empty = !this.pump.write_ready();
+                let limit = read_remaining
+                    .into_iter()
+                    .chain(rate_allowance)
+                    .min()
+                    .map(|n| usize::try_from(n).unwrap_or(usize::MAX));
+
+                match this.pump.poll_read_limited(cx, reader, limit) {
                     // We're waiting for more read data. This registered the
                     // waker, so we'll get polled when we can do more reading.
-                    Poll::Pending => {}
-                    Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::WouldBlock => {}
+                    Poll::Pending => {
+                        read_blocked = true;
+                        wakeup_registered = true;
+                        reader_waiting = true;
+                    }
+                    Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::WouldBlock => {
+                        this.counters.would_block_reads += 1;
+                        read_blocked = true;
+                        would_block = true;
+                    }
 
                     Poll::Ready(Ok(n)) => match NonZeroUsize::new(n) {
                         // Nothing else available to read. Clear the reader and
                         // proceed to write whatever's left in the buffer
-                        None => this.reader.set(None),
+                        None => reader_eof = true,
 
                         // Read some data. Advance the buffer, and additionally
                         // fire a signal that we want to be polled immediately to
                         // read more data if there's space available.
                         Some(n) => {
-                            this.buffer.advance_read(n);
+                            #[cfg(feature = "tracing")]
+                            if this.counters.bytes_read == 0 {
+                                tracing::trace!("read first byte");
+                            }
+
+                            *this.drained = was_empty;
+                            this.counters.bytes_read += n.get() as u64;
+                            this.counters.reads += 1;
+                            reader_eof = this.config.read_limit == Some(this.counters.bytes_read);
+                            *this.budget_used += 1;
                             read_ready = true;
+
+                            #[cfg(feature = "futures-timer")]
+                            if let Some(timer) = this.complete_on_idle {
+                                timer.touch();
+                            }
+
+                            if let Some(timeout) = this.idle_timeout {
+                                timeout.touch();
+                            }
+
+                            if let Some(rate) = this.rate_limit {
+                                rate.consume(n.get());
+                            }
+
+                            if let Some(callback) = this.on_read {
+                                (callback.get_mut())(n.get());
+                            }
+
+                            if let Some(callback) = this.inspect {
+                                let inspect = callback.get_mut();
+                                this.pump
+                                    .buffer
+                                    .for_each_newest_mut(n.get(), |chunk| inspect(chunk));
+                            }
+
+                            if let Some(callback) = this.map_in_place {
+                                this.pump
+                                    .buffer
+                                    .for_each_newest_mut(n.get(), callback.get_mut());
+                            }
+
+                            if let Some(callback) = this.checksum {
+                                let checksum = callback.get_mut();
+                                this.pump
+                                    .buffer
+                                    .for_each_newest_mut(n.get(), |chunk| checksum(chunk));
+                            }
                         }
                     },
 
@@ -99,63 +1430,455 @@ impl<R: futures::AsyncRead, W: futures::AsyncWrite, B: AsMut<[u8]>> Future for F
                     // want to potentially block forever, though, so signal
                     // the executor that we want to be polled again.
                     Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::Interrupted => {
+                        this.counters.interrupted_reads += 1;
                         read_ready = true;
                     }
 
                     // There was a real error; return it.
-                    Poll::Ready(Err(err)) => return Poll::Ready(Err(ForwarderError::Read(err))),
+                    Poll::Ready(Err(err)) => {
+                        return Poll::Ready(Err(this.counters.read_error(err)))
+                    }
                 }
             }
-        }
 
-        // The read might have advanced the buffer, so get a fresh set of write
-        // buffers
-        let [b1, b2] = this.buffer.get_buffers().write;
-        let write_buffer_len = pair_len(&[b1, b2]);
+            if reader_eof && this.reader.is_some() {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(bytes_read = this.counters.bytes_read, "reader EOF");
 
-        // Only perform a write if there's data to be written
-        if write_buffer_len > 0 {
-            match this
-                .writer
-                .poll_write_vectored(cx, &[IoSlice::new(b1), IoSlice::new(b2)])
-            {
-                // We're waiting for more availability to write. Nothing else to
-                // be done at this point.
-                Poll::Pending => {}
-                Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::WouldBlock => {}
-
-                Poll::Ready(Ok(n)) => match NonZeroUsize::new(n) {
-                    // The writer is closed before we could forward everything.
-                    // This is a problem.
-                    None => return Poll::Ready(Err(ForwarderError::WriteClosedEarly)),
-
-                    // We wrote some data. Advance the buffer, and additionally
-                    // fire a signal that we want to be polled immediately to
-                    // write more data if there's data available.
-                    Some(n) => {
-                        this.buffer.advance_write(n);
+                this.reader.set(None);
+                *this.close_on_completion = this.config.half_close_on_eof;
+                *this.flush_on_completion |= this.config.flush_on_eof;
+            }
+
+            // If we're aggregating reads, hold off on writing until enough data
+            // has been buffered. We always write if the reader is done or if
+            // there's no more room to read.
+            let write_allowed = this.reader.is_none()
+                || !this.pump.read_ready()
+                || this.pump.buffered_len() >= this.config.min_read;
+
+            // Only perform a write if there's data to be written
+            if write_allowed && this.pump.write_ready() {
+                // With strict write accounting, a retry after a `Pending`
+                // write offers exactly the same bytes as before. That's never
+                // more than the maximum write size.
+                let limit = this
+                    .retry_len
+                    .or(this.config.max_write_size.map(NonZeroUsize::get));
+                let offered = this.pump.buffered_len().min(limit.unwrap_or(usize::MAX));
+
+                let result = this.pump.poll_write_limited(
+                    cx,
+                    this.writer.as_mut(),
+                    this.config.max_iov,
+                    limit,
+                );
+
+                *this.retry_len = match result {
+                    Poll::Pending if this.config.strict_write_accounting => Some(offered),
+                    Poll::Ready(Err(ref err))
+                        if this.config.strict_write_accounting
+                            && err.kind() == io::ErrorKind::WouldBlock =>
+                    {
+                        Some(offered)
+                    }
+                    _ => None,
+                };
+
+                match result {
+                    // We're waiting for more availability to write. Nothing else to
+                    // be done at this point.
+                    Poll::Pending => {
+                        wakeup_registered = true;
+                        writer_waiting = true;
+                    }
+                    Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::WouldBlock => {
+                        this.counters.would_block_writes += 1;
+                        would_block = true;
+                    }
+
+                    Poll::Ready(Ok(n)) => match NonZeroUsize::new(n) {
+                        // The writer accepted nothing, but its probe says it's
+                        // still open, so it's just full for now. It'll wake us
+                        // when it has room.
+                        None if this.write_probe.as_ref().is_some_and(|probe| {
+                            (probe.is_writable)(this.writer.as_ref().get_ref())
+                        }) =>
+                        {
+                            this.counters.would_block_writes += 1;
+                            wakeup_registered = true;
+                            writer_waiting = true;
+                        }
+
+                        // The writer closed in between reads, and it's allowed to
+                        // stop us there. The unwritten data stays buffered, so
+                        // that it can be recovered.
+                        None if *this.drained
+                            && this.reader.is_some()
+                            && this.config.writer_close
+                                == WriterClosePolicy::GracefulWhenDrained =>
+                        {
+                            return Poll::Ready(Ok(()))
+                        }
+
+                        // The writer is closed before we could forward everything.
+                        // This is a problem.
+                        None => {
+                            #[cfg(feature = "tracing")]
+                            tracing::debug!(
+                                bytes_written = this.counters.bytes_written,
+                                buffered = this.pump.buffered_len(),
+                                "writer closed early"
+                            );
+
+                            return Poll::Ready(Err(ForwarderError::WriteClosedEarly {
+                                forwarded: this.counters.bytes_written,
+                                unforwarded: this.pump.buffer.buffered_data(),
+                            }));
+                        }
+
+                        // We wrote some data. Advance the buffer, and additionally
+                        // fire a signal that we want to be polled immediately to
+                        // write more data if there's data available.
+                        Some(n) => {
+                            *this.drained = !this.pump.write_ready();
+                            this.counters.bytes_written += n.get() as u64;
+                            this.counters.writes += 1;
+                            *this.unflushed += n.get() as u64;
+
+                            if let Some(timeout) = this.idle_timeout {
+                                timeout.touch();
+                            }
+
+                            if let Some(callback) = this.on_write {
+                                (callback.get_mut())(n.get());
+                            }
+
+                            *this.budget_used += 1;
+                            write_ready = true;
+                            wrote_this_cycle = true;
+                        }
+                    },
+
+                    Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::Interrupted => {
+                        this.counters.interrupted_writes += 1;
                         write_ready = true
                     }
-                },
 
-                Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::Interrupted => {
-                    write_ready = true
+                    Poll::Ready(Err(err)) => {
+                        return Poll::Ready(Err(this
+                            .counters
+                            .write_error(err, this.pump.buffer.buffered_data())))
+                    }
                 }
+            }
+
+            wrote |= wrote_this_cycle;
+            cycles += 1;
+
+            // Go around again if this cycle's write made room that the reader
+            // can use right away. With a yield budget, the budget bounds the
+            // cycles; otherwise there's a fixed limit. Once a flush is due,
+            // we stop to do it.
+            let within_budget = match this.config.yield_budget {
+                Some(budget) => *this.budget_used < budget.get(),
+                None => cycles < MAX_CYCLES_PER_POLL,
+            };
 
-                Poll::Ready(Err(err)) => return Poll::Ready(Err(ForwarderError::Write(err))),
+            if !(wrote_this_cycle
+                && within_budget
+                && !this.config.flush_policy.is_due(*this.unflushed)
+                && !read_blocked
+                && this.reader.is_some()
+                && this.pump.read_ready())
+            {
+                break;
+            }
+        }
+
+        // The stall clock is only reset by a successful write, and we only
+        // warn about it while the buffer is full
+        #[cfg(feature = "futures-timer")]
+        if let Some(warning) = this.stall_warning {
+            if wrote {
+                warning.touch();
+            } else if !this.pump.read_ready() {
+                warning.poll_warn(cx, this.pump.buffered_len());
+            }
+        }
+
+        // If the buffer is still full while the reader has more to give, it
+        // might be the bottleneck. If it grew, there's now room to read.
+        if let Some(adaptive) = this.adaptive {
+            let full = this.reader.is_some() && !this.pump.read_ready();
+
+            if adaptive.after_poll(&mut this.pump.buffer, full) {
+                cx.waker().wake_by_ref();
+            }
+        }
+
+        // If the flush policy calls for it, flush whatever we've written. If
+        // the flush doesn't finish immediately, it registered the waker;
+        // we'll resume it at the start of the next poll.
+        if wrote && this.config.flush_policy.is_due(*this.unflushed) {
+            match this.writer.as_mut().poll_flush(cx) {
+                Poll::Pending => {
+                    *this.flush_pending = true;
+                    *this.waiting_on = WaitingOn::Flush;
+                    return Poll::Pending;
+                }
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(ForwarderError::FlushFailed(err))),
+                Poll::Ready(Ok(())) => *this.unflushed = 0,
+            }
+        }
+
+        // If the reader has been idle for long enough, treat it as though it
+        // reached EOF. Anything still buffered, including data that was being
+        // held back for read aggregation, is then written out before we
+        // complete.
+        #[cfg(feature = "futures-timer")]
+        if let Some(timer) = this.complete_on_idle {
+            if this.reader.is_some() && timer.poll_expired(cx).is_ready() {
+                this.reader.set(None);
+
+                if this.pump.write_ready() {
+                    cx.waker().wake_by_ref();
+                }
             }
         }
 
         // We've made at most one read and one write. If, at this point, the
         // reader is done and the write buffer is empty, we're done.
-        if this.reader.is_none() && !this.buffer.write_ready() {
-            return Poll::Ready(Ok(()));
+        if this.reader.is_none() && !this.pump.write_ready() {
+            // If we needed an exact number of bytes, a short stream is
+            // corrupt, so the writer isn't closed cleanly
+            if let Some(limit) = this.config.read_limit.filter(|_| this.config.exact) {
+                if this.counters.bytes_read < limit {
+                    return Poll::Ready(Err(ForwarderError::UnexpectedEof {
+                        copied: this.counters.bytes_written,
+                    }));
+                }
+            }
+
+            // Closing the writer also flushes it. Once the close or flush is
+            // done, clear the flag, so that the forwarder reports itself as
+            // finished.
+            if *this.close_on_completion {
+                *this.waiting_on = WaitingOn::Close;
+                let result = futures::ready!(this.writer.as_mut().poll_close(cx));
+                *this.close_on_completion = false;
+                *this.flush_on_completion = false;
+                return Poll::Ready(result.map_err(ForwarderError::CloseFailed));
+            }
+
+            if !*this.flush_on_completion {
+                return Poll::Ready(Ok(()));
+            }
+
+            *this.waiting_on = WaitingOn::Flush;
+            let result = futures::ready!(this.writer.as_mut().poll_flush(cx));
+            *this.flush_on_completion = false;
+
+            #[cfg(feature = "tracing")]
+            tracing::trace!("flushed writer");
+
+            return Poll::Ready(result.map_err(ForwarderError::FlushFailed));
+        }
+
+        // Give up if no data has moved in either direction for too long
+        if let Some(timeout) = this.idle_timeout {
+            if timeout.poll_expired(cx).is_ready() {
+                return Poll::Ready(Err(ForwarderError::IdleTimeout));
+            }
+        }
+
+        // If we're waiting for the reader, and everything we have has been
+        // written, flush the writer, so that a partial message isn't held up
+        // in a buffering writer while the reader is quiet
+        if this.config.flush_on_read_stall
+            && *this.unflushed > 0
+            && read_blocked
+            && !this.pump.write_ready()
+        {
+            match this.writer.as_mut().poll_flush(cx) {
+                Poll::Pending => {
+                    *this.flush_pending = true;
+                    *this.waiting_on = WaitingOn::Flush;
+                    return Poll::Pending;
+                }
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(ForwarderError::FlushFailed(err))),
+                Poll::Ready(Ok(())) => *this.unflushed = 0,
+            }
         }
 
-        if (write_ready && this.buffer.write_ready()) || (read_ready && this.buffer.read_ready()) {
+        // A write can also unblock reads that were skipped this time around,
+        // because the ring was full or the overflow hadn't drained yet. If
+        // the reader is already registered to wake us, there's no need.
+        if (write_ready && (this.pump.write_ready() || (!read_blocked && this.pump.read_ready())))
+            || (read_ready && this.pump.read_ready())
+            || (would_block && !wakeup_registered)
+        {
+            *this.waiting_on = WaitingOn::Nothing;
             cx.waker().wake_by_ref();
+        } else {
+            *this.waiting_on = match (reader_waiting, writer_waiting) {
+                (true, true) => WaitingOn::ReaderAndWriter,
+                (true, false) => WaitingOn::Reader,
+                (false, true) => WaitingOn::Writer,
+                (false, false) if timer_waiting => WaitingOn::Timer,
+                (false, false) => WaitingOn::Nothing,
+            };
+
+            // If nothing registered the waker, nothing will ever wake us
+            // again, and the forward would hang
+            debug_assert!(
+                wakeup_registered,
+                "forwarder returned Pending without registering a wakeup"
+            );
+
+            // We're parked waiting for I/O, so the yield budget is
+            // replenished
+            *this.budget_used = 0;
+
+            if let Some(adaptive) = this.adaptive {
+                adaptive.on_idle(&mut this.pump.buffer);
+            }
+
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                bytes_read = this.counters.bytes_read,
+                bytes_written = this.counters.bytes_written,
+                buffered = this.pump.buffered_len(),
+                "waiting for I/O"
+            );
         }
 
         Poll::Pending
     }
 }
+
+impl<R, W, B, C> Forwarder<R, W, B, C>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+{
+    /// Poll the forward, like [`Future::poll`], but report how much data
+    /// moved. This returns `Ready` whenever any data was read or written,
+    /// even if the forward isn't done, and `Pending` only if nothing moved.
+    /// The forward is complete once this returns a progress with
+    /// `reader_eof` set and nothing written, with nothing left buffered.
+    pub fn poll_progress(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<ForwardProgress, ForwarderError>> {
+        let before = self.inner.counters;
+        let result = self.as_mut().poll(cx);
+
+        let progress = ForwardProgress {
+            read: (self.inner.counters.bytes_read - before.bytes_read) as usize,
+            wrote: (self.inner.counters.bytes_written - before.bytes_written) as usize,
+            reader_eof: self.inner.reader.is_none(),
+        };
+
+        match result {
+            Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
+            Poll::Ready(Ok(_)) => Poll::Ready(Ok(progress)),
+            Poll::Pending if progress.read > 0 || progress.wrote > 0 => Poll::Ready(Ok(progress)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<R, W, B, C> Future for Forwarder<R, W, B, C>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+{
+    /// On success, the total number of bytes written
+    type Output = Result<u64, ForwarderError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        #[cfg(feature = "tracing")]
+        let span = self.inner.span.clone();
+        #[cfg(feature = "tracing")]
+        let _enter = span.enter();
+
+        let before = self.inner.counters;
+
+        let mut result = self
+            .as_mut()
+            .poll_forward(cx)
+            .map_ok(|()| self.inner.counters.bytes_written);
+
+        // Keep track of how long it's been since any data moved, for the
+        // watchdog
+        if result.is_pending() {
+            let this = self.as_mut().project().inner.project();
+            let counters = this.counters;
+
+            if counters.bytes_read == before.bytes_read
+                && counters.bytes_written == before.bytes_written
+            {
+                counters.stalled_polls += 1;
+            } else {
+                counters.stalled_polls = 0;
+            }
+
+            if let Some(max) = this.config.max_stalled_polls {
+                if counters.stalled_polls >= max.get() {
+                    let counters = *counters;
+
+                    result = Poll::Ready(Err(ForwarderError::NoProgress {
+                        polls: counters.stalled_polls,
+                        state: self.debug_state(),
+                        interrupted_reads: counters.interrupted_reads,
+                        interrupted_writes: counters.interrupted_writes,
+                        would_block_reads: counters.would_block_reads,
+                        would_block_writes: counters.would_block_writes,
+                    }));
+                }
+            }
+        }
+
+        if result.is_ready() {
+            let this = self.as_mut().project().inner.project();
+            *this.terminated = true;
+            *this.waiting_on = WaitingOn::Nothing;
+        }
+
+        #[cfg(feature = "tracing")]
+        match &result {
+            Poll::Pending => {}
+            Poll::Ready(Ok(_)) => tracing::debug!(
+                bytes_read = self.inner.counters.bytes_read,
+                bytes_written = self.inner.counters.bytes_written,
+                "forward complete"
+            ),
+            Poll::Ready(Err(error)) => tracing::debug!(
+                ?error,
+                bytes_read = self.inner.counters.bytes_read,
+                bytes_written = self.inner.counters.bytes_written,
+                "forward failed"
+            ),
+        }
+
+        result
+    }
+}
+
+impl<R, W, B, C> FusedFuture for Forwarder<R, W, B, C>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+{
+    /// True once the forward has completed, successfully or not
+    fn is_terminated(&self) -> bool {
+        self.inner.terminated
+    }
+}
@@ -0,0 +1,75 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::future::FusedFuture;
+use pin_project::pin_project;
+
+use crate::{BufferStorage, Forwarder, ForwarderError};
+
+/// Future that runs a [`Forwarder`] and maps its error into the caller's own
+/// error type. Created by [`Forwarder::map_err`].
+///
+/// On success, the output is the total number of bytes written.
+#[pin_project]
+pub struct MapErr<R, W, B, C, F> {
+    #[pin]
+    forwarder: Forwarder<R, W, B, C>,
+    map: F,
+}
+
+impl<R, W, B, C> Forwarder<R, W, B, C> {
+    /// Map the forward's error with `map` as soon as it happens, so that the
+    /// `.await` produces the caller's own error type directly. `map` gets the
+    /// original [`ForwarderError`], including the underlying `io::Error`.
+    /// The mapped future outputs the total number of bytes written.
+    pub fn map_err<E, F>(self, map: F) -> MapErr<R, W, B, C, F>
+    where
+        F: FnMut(ForwarderError) -> E,
+    {
+        MapErr {
+            forwarder: self,
+            map,
+        }
+    }
+}
+
+impl<R, W, B, C, F> MapErr<R, W, B, C, F> {
+    /// Get a reference to the underlying forwarder
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Forwarder<R, W, B, C> {
+        &self.forwarder
+    }
+}
+
+impl<R, W, B, C, F, E> Future for MapErr<R, W, B, C, F>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+    F: FnMut(ForwarderError) -> E,
+{
+    type Output = Result<u64, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        this.forwarder.poll(cx).map_err(this.map)
+    }
+}
+
+impl<R, W, B, C, F, E> FusedFuture for MapErr<R, W, B, C, F>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+    F: FnMut(ForwarderError) -> E,
+{
+    fn is_terminated(&self) -> bool {
+        self.forwarder.is_terminated()
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{future::FusedFuture, AsyncRead, AsyncWrite};
+use pin_project::pin_project;
+
+use crate::{buffer::BufferStorage, pump::DuplexPump, ForwarderError, MAX_CYCLES_PER_POLL};
+
+/// An error from a [`MergeForwarder`]
+#[derive(Debug)]
+pub struct MergeError {
+    /// The index of the reader that failed, or `None` if it was the writer
+    pub reader: Option<usize>,
+    pub source: ForwarderError,
+}
+
+/// Future that forwards several readers into a single writer, through a
+/// single shared ring buffer. The readers are polled in turn, and each read
+/// lands in the buffer whole, so the output is interleaved at the
+/// granularity of the readers' chunks: a reader that produces a line per
+/// read never has its lines torn, as long as the buffer has room for a whole
+/// line when it's read. Created by [`MergeForwarder::new`].
+///
+/// Once every reader has reached EOF and everything has been written, the
+/// writer is flushed. If any reader or the writer fails, the whole forward
+/// fails. On success, the output is the total number of bytes written.
+#[pin_project]
+pub struct MergeForwarder<R, W, B> {
+    // Readers that reached EOF are removed
+    readers: Vec<Option<R>>,
+
+    #[pin]
+    writer: W,
+
+    pump: DuplexPump<B>,
+
+    // The reader to poll first next time, so that an eager reader can't
+    // starve the others
+    next: usize,
+
+    bytes_written: u64,
+    done: bool,
+}
+
+impl<R: AsyncRead + Unpin, W: AsyncWrite, B: BufferStorage> MergeForwarder<R, W, B> {
+    pub fn new(readers: impl IntoIterator<Item = R>, writer: W, buffer: B) -> Self {
+        Self {
+            readers: readers.into_iter().map(Some).collect(),
+            writer,
+            pump: DuplexPump::new(buffer),
+            next: 0,
+            bytes_written: 0,
+            done: false,
+        }
+    }
+}
+
+impl<R, W, B> MergeForwarder<R, W, B> {
+    /// The number of bytes that have been written so far
+    #[inline]
+    #[must_use]
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// The number of readers that haven't reached EOF yet
+    #[must_use]
+    pub fn active_readers(&self) -> usize {
+        self.readers
+            .iter()
+            .filter(|reader| reader.is_some())
+            .count()
+    }
+
+    /// Get a reference to the writer
+    #[inline]
+    #[must_use]
+    pub fn get_writer(&self) -> &W {
+        &self.writer
+    }
+
+    /// Recover the writer
+    pub fn into_writer(self) -> W {
+        self.writer
+    }
+}
+
+impl<R, W, B> Future for MergeForwarder<R, W, B>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite,
+    B: BufferStorage,
+{
+    type Output = Result<u64, MergeError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        assert!(!*this.done, "MergeForwarder polled after completion");
+
+        let count = this.readers.len();
+
+        let mut cycles = 0;
+
+        loop {
+            // Don't hog the executor with a reader and writer that are
+            // always ready; come back for the rest after yielding
+            if cycles == MAX_CYCLES_PER_POLL {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            cycles += 1;
+
+            let mut progress = false;
+
+            // Give each reader a turn, for as long as there's room
+            for index in (0..count).map(|i| (*this.next + i) % count) {
+                if !this.pump.read_ready() {
+                    break;
+                }
+
+                let Some(reader) = this.readers[index].as_mut() else {
+                    continue;
+                };
+
+                match this.pump.poll_pump_read(cx, Pin::new(reader)) {
+                    Poll::Pending => {}
+                    Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::WouldBlock => {
+                        cx.waker().wake_by_ref();
+                    }
+                    Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::Interrupted => {
+                        progress = true;
+                    }
+                    Poll::Ready(Err(error)) => {
+                        *this.done = true;
+                        return Poll::Ready(Err(MergeError {
+                            reader: Some(index),
+                            source: ForwarderError::Read {
+                                error,
+                                bytes_read: *this.bytes_written + this.pump.buffered_len() as u64,
+                                bytes_written: *this.bytes_written,
+                            },
+                        }));
+                    }
+                    Poll::Ready(Ok(0)) => {
+                        this.readers[index] = None;
+                        progress = true;
+                    }
+                    Poll::Ready(Ok(_)) => progress = true,
+                }
+            }
+
+            if count > 0 {
+                *this.next = (*this.next + 1) % count;
+            }
+
+            if this.pump.write_ready() {
+                let result = match this.pump.poll_pump_write(cx, this.writer.as_mut()) {
+                    Poll::Pending => Ok(()),
+                    Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::WouldBlock => {
+                        cx.waker().wake_by_ref();
+                        Ok(())
+                    }
+                    Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::Interrupted => {
+                        progress = true;
+                        Ok(())
+                    }
+                    Poll::Ready(Err(error)) => Err(ForwarderError::Write {
+                        error,
+                        bytes_read: *this.bytes_written + this.pump.buffered_len() as u64,
+                        bytes_written: *this.bytes_written,
+                        unforwarded: this.pump.buffer.buffered_data(),
+                    }),
+                    Poll::Ready(Ok(0)) => Err(ForwarderError::WriteClosedEarly {
+                        forwarded: *this.bytes_written,
+                        unforwarded: this.pump.buffer.buffered_data(),
+                    }),
+                    Poll::Ready(Ok(n)) => {
+                        *this.bytes_written += n as u64;
+                        progress = true;
+                        Ok(())
+                    }
+                };
+
+                if let Err(source) = result {
+                    *this.done = true;
+                    return Poll::Ready(Err(MergeError {
+                        reader: None,
+                        source,
+                    }));
+                }
+            }
+
+            if !progress {
+                break;
+            }
+        }
+
+        if this.readers.iter().any(Option::is_some) || this.pump.write_ready() {
+            return Poll::Pending;
+        }
+
+        let result = futures::ready!(this.writer.poll_flush(cx));
+        *this.done = true;
+
+        Poll::Ready(match result {
+            Ok(()) => Ok(*this.bytes_written),
+            Err(err) => Err(MergeError {
+                reader: None,
+                source: ForwarderError::FlushFailed(err),
+            }),
+        })
+    }
+}
+
+impl<R, W, B> FusedFuture for MergeForwarder<R, W, B>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite,
+    B: BufferStorage,
+{
+    fn is_terminated(&self) -> bool {
+        self.done
+    }
+}
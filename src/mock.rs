@@ -0,0 +1,251 @@
+//! Scriptable mock streams, for deterministically testing forwards against
+//! tricky I/O patterns. Requires the `testing` feature.
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    io::{self, IoSlice, IoSliceMut},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{AsyncRead, AsyncWrite};
+
+/// A single scripted step for a [`MockStream`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Read these bytes. If the read buffer is too small for all of them,
+    /// the rest are left for the next read. This must not be empty; use
+    /// [`Action::Eof`] for EOF.
+    Read(Vec<u8>),
+
+    /// Return `Pending`, after waking the task so that it's polled again
+    Pending,
+
+    /// Fail with `WouldBlock`
+    WouldBlock,
+
+    /// Fail with `Interrupted`
+    Interrupted,
+
+    /// Read `Ok(0)`. This is never used up, so every read after it also
+    /// reaches EOF.
+    Eof,
+
+    /// Accept up to this many bytes of a write
+    WriteAccept(usize),
+
+    /// Write `Ok(0)`, as though the writer had closed
+    WriteZero,
+}
+
+/// A reader or writer that follows a script of [`Action`]s, one per call,
+/// so that a forward can be driven deterministically through tricky I/O
+/// patterns. Reads take `Read` and `Eof` actions, writes take `WriteAccept`
+/// and `WriteZero` actions, and both can take `Pending`, `WouldBlock`, and
+/// `Interrupted`; an action of the wrong kind panics.
+///
+/// Once the script runs out, reads reach EOF and writes accept everything.
+#[derive(Debug, Clone, Default)]
+pub struct MockStream {
+    script: VecDeque<Action>,
+    vectored: bool,
+
+    written: Vec<u8>,
+    flushes: usize,
+    closed: bool,
+}
+
+impl MockStream {
+    pub fn new(script: impl IntoIterator<Item = Action>) -> Self {
+        Self {
+            script: script.into_iter().collect(),
+            vectored: true,
+            ..Self::default()
+        }
+    }
+
+    /// Make vectored reads and writes use only their first non-empty slice,
+    /// like the default implementations of `poll_read_vectored` and
+    /// `poll_write_vectored`
+    pub fn without_vectored(self) -> Self {
+        Self {
+            vectored: false,
+            ..self
+        }
+    }
+
+    /// Everything that's been written to this stream
+    #[must_use]
+    pub fn written(&self) -> &[u8] {
+        &self.written
+    }
+
+    /// The number of times this stream has been flushed
+    #[must_use]
+    pub fn flushes(&self) -> usize {
+        self.flushes
+    }
+
+    /// True if this stream has been closed
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// The number of actions left in the script
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.script.len()
+    }
+
+    /// Handle the actions that behave the same for reads and writes
+    fn common_action(action: &Action, cx: &mut Context<'_>) -> Option<Poll<io::Result<usize>>> {
+        match action {
+            Action::Pending => {
+                cx.waker().wake_by_ref();
+                Some(Poll::Pending)
+            }
+            Action::WouldBlock => Some(Poll::Ready(Err(io::ErrorKind::WouldBlock.into()))),
+            Action::Interrupted => Some(Poll::Ready(Err(io::ErrorKind::Interrupted.into()))),
+            _ => None,
+        }
+    }
+
+    fn read_into(
+        &mut self,
+        cx: &mut Context<'_>,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let action = match self.script.front_mut() {
+            None | Some(Action::Eof) => return Poll::Ready(Ok(0)),
+            Some(action) => action,
+        };
+
+        let data = match action {
+            // An empty read would look just like EOF, which is almost
+            // certainly a mistake in the script
+            Action::Read(data) if data.is_empty() => {
+                panic!("empty Action::Read; use Action::Eof for EOF")
+            }
+            Action::Read(data) => data,
+            action => match Self::common_action(action, cx) {
+                Some(result) => {
+                    self.script.pop_front();
+                    return result;
+                }
+                None => panic!("unexpected action for a read: {action:?}"),
+            },
+        };
+
+        let mut read = 0;
+
+        for buf in bufs.iter_mut().filter(|buf| !buf.is_empty()) {
+            let n = buf.len().min(data.len() - read);
+            buf[..n].copy_from_slice(&data[read..read + n]);
+            read += n;
+
+            if !self.vectored {
+                break;
+            }
+        }
+
+        data.drain(..read);
+
+        if data.is_empty() {
+            self.script.pop_front();
+        }
+
+        Poll::Ready(Ok(read))
+    }
+
+    fn write_from(
+        &mut self,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let limit = match self.script.pop_front() {
+            None => usize::MAX,
+            Some(Action::WriteAccept(limit)) => limit,
+            Some(Action::WriteZero) => return Poll::Ready(Ok(0)),
+            Some(action) => match Self::common_action(&action, cx) {
+                Some(result) => return result,
+                None => panic!("unexpected action for a write: {action:?}"),
+            },
+        };
+
+        let mut written = 0;
+
+        for buf in bufs.iter().filter(|buf| !buf.is_empty()) {
+            let n = buf.len().min(limit - written);
+            self.written.extend_from_slice(&buf[..n]);
+            written += n;
+
+            if !self.vectored {
+                break;
+            }
+        }
+
+        Poll::Ready(Ok(written))
+    }
+}
+
+impl AsyncRead for MockStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut().read_into(cx, &mut [IoSliceMut::new(buf)])
+    }
+
+    fn poll_read_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut().read_into(cx, bufs)
+    }
+}
+
+impl AsyncWrite for MockStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut().write_from(cx, &[IoSlice::new(buf)])
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut().write_from(cx, bufs)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().flushes += 1;
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().closed = true;
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Poll `future` with a no-op waker until it completes, up to `max_polls`
+/// times. Returns the output along with the number of polls it took, or
+/// `None` if it didn't complete in time.
+pub fn drive<F: Future>(future: F, max_polls: usize) -> Option<(F::Output, usize)> {
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    futures::pin_mut!(future);
+
+    (1..=max_polls).find_map(|polls| match future.as_mut().poll(&mut cx) {
+        Poll::Ready(output) => Some((output, polls)),
+        Poll::Pending => None,
+    })
+}
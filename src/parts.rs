@@ -0,0 +1,50 @@
+use crate::{BufferStorage, Forwarder};
+
+/// Everything that's left of a forwarder once it's been taken apart with
+/// [`Forwarder::into_parts`], so that the connection and the buffer can be
+/// reused
+#[derive(Debug)]
+pub struct ForwarderParts<R, W, B> {
+    /// The reader, or `None` if it had already reached EOF
+    pub reader: Option<R>,
+
+    /// The writer, which hasn't been flushed or closed
+    pub writer: W,
+
+    /// The forward's buffer. Its contents are unspecified; the data that
+    /// was still buffered is in `unwritten`.
+    pub buffer: B,
+
+    /// The data that had been read but not yet written, in order
+    pub unwritten: Vec<u8>,
+
+    /// The total number of bytes read from the reader
+    pub bytes_read: u64,
+
+    /// The total number of bytes written to the writer
+    pub bytes_written: u64,
+}
+
+impl<R, W, B: BufferStorage, C> Forwarder<R, W, B, C> {
+    /// Take the forwarder apart, handing back the reader, writer, buffer,
+    /// and whatever data was still buffered. This works at any point: after
+    /// the forward completes or fails, or part way through, to cancel it
+    /// without losing the buffered data. Nothing is flushed or closed.
+    ///
+    /// The parts are moved out of the forwarder, so to take it apart part
+    /// way through, it has to have been polled without pinning it in place,
+    /// which means it has to be `Unpin`.
+    pub fn into_parts(self) -> ForwarderParts<R, W, B> {
+        let mut buffer = self.inner.pump.buffer;
+        let unwritten = buffer.buffered_data();
+
+        ForwarderParts {
+            reader: self.inner.reader,
+            writer: self.inner.writer,
+            buffer: buffer.into_inner(),
+            unwritten,
+            bytes_read: self.inner.counters.bytes_read,
+            bytes_written: self.inner.counters.bytes_written,
+        }
+    }
+}
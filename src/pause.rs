@@ -0,0 +1,78 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::Context,
+};
+
+use futures::task::AtomicWaker;
+
+#[derive(Debug, Default)]
+struct Shared {
+    paused: AtomicBool,
+    waker: AtomicWaker,
+}
+
+/// Handle for temporarily suspending a forward, for things like bandwidth
+/// arbitration. Created by
+/// [`Forwarder::with_pause_handle`][crate::Forwarder::with_pause_handle].
+#[derive(Debug, Clone)]
+pub struct PauseHandle {
+    shared: Arc<Shared>,
+}
+
+impl PauseHandle {
+    /// Pause the forward. No more data is read or written until it's
+    /// resumed, though a flush that's already in progress is finished.
+    pub fn pause(&self) {
+        self.shared.paused.store(true, Ordering::Release);
+    }
+
+    /// Resume a paused forward, waking it so that it picks up where it left
+    /// off
+    pub fn resume(&self) {
+        self.shared.paused.store(false, Ordering::Release);
+        self.shared.waker.wake();
+    }
+
+    /// Returns true if the forward is paused
+    #[inline]
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.shared.paused.load(Ordering::Acquire)
+    }
+}
+
+/// The forwarder's side of a [`PauseHandle`]
+#[derive(Debug)]
+pub struct PauseState {
+    shared: Arc<Shared>,
+}
+
+impl PauseState {
+    /// Returns true if the forward is paused, in which case we'll be woken
+    /// when it's resumed
+    pub fn check(&self, cx: &mut Context<'_>) -> bool {
+        if !self.shared.paused.load(Ordering::Acquire) {
+            return false;
+        }
+
+        // Register before checking again, so that a resume in between isn't
+        // missed
+        self.shared.waker.register(cx.waker());
+        self.shared.paused.load(Ordering::Acquire)
+    }
+}
+
+/// Create a connected pause handle and state
+pub fn pause_pair() -> (PauseHandle, PauseState) {
+    let shared = Arc::new(Shared::default());
+
+    (
+        PauseHandle {
+            shared: shared.clone(),
+        },
+        PauseState { shared },
+    )
+}
@@ -0,0 +1,119 @@
+use std::sync::{Arc, Mutex};
+
+/// A source of reusable buffers, so that a server with many connections
+/// doesn't have to allocate a fresh buffer for every forward. Use it with
+/// [`Forwarder::from_pool`][crate::Forwarder::from_pool].
+pub trait BufferPool {
+    type Buffer: AsMut<[u8]>;
+
+    /// Lease a buffer from the pool
+    fn acquire(&self) -> Self::Buffer;
+
+    /// Return a buffer that was leased with [`acquire`][Self::acquire]
+    fn release(&self, buffer: Self::Buffer);
+}
+
+impl<P: BufferPool + ?Sized> BufferPool for &P {
+    type Buffer = P::Buffer;
+
+    #[inline]
+    fn acquire(&self) -> Self::Buffer {
+        P::acquire(self)
+    }
+
+    #[inline]
+    fn release(&self, buffer: Self::Buffer) {
+        P::release(self, buffer)
+    }
+}
+
+impl<P: BufferPool + ?Sized> BufferPool for Arc<P> {
+    type Buffer = P::Buffer;
+
+    #[inline]
+    fn acquire(&self) -> Self::Buffer {
+        P::acquire(self)
+    }
+
+    #[inline]
+    fn release(&self, buffer: Self::Buffer) {
+        P::release(self, buffer)
+    }
+}
+
+/// A simple [`BufferPool`] that keeps released buffers in a list, and
+/// allocates a new buffer whenever the list is empty.
+#[derive(Debug)]
+pub struct VecPool {
+    buffers: Mutex<Vec<Vec<u8>>>,
+    buffer_size: usize,
+}
+
+impl VecPool {
+    /// Create an empty pool that allocates buffers of `buffer_size` bytes
+    pub fn new(buffer_size: usize) -> Self {
+        Self {
+            buffers: Mutex::new(Vec::new()),
+            buffer_size,
+        }
+    }
+
+    /// The number of buffers that are in the pool, waiting to be leased
+    #[must_use]
+    pub fn available(&self) -> usize {
+        self.buffers.lock().unwrap().len()
+    }
+}
+
+impl BufferPool for VecPool {
+    type Buffer = Vec<u8>;
+
+    fn acquire(&self) -> Vec<u8> {
+        self.buffers
+            .lock()
+            .unwrap()
+            .pop()
+            .unwrap_or_else(|| vec![0; self.buffer_size])
+    }
+
+    fn release(&self, buffer: Vec<u8>) {
+        self.buffers.lock().unwrap().push(buffer)
+    }
+}
+
+/// A buffer leased from a [`BufferPool`], which is returned to the pool when
+/// it's dropped. This is the buffer type of a forwarder created with
+/// [`Forwarder::from_pool`][crate::Forwarder::from_pool].
+#[derive(Debug)]
+pub struct PooledBuffer<P: BufferPool> {
+    // This is only `None` while it's being returned to the pool
+    buffer: Option<P::Buffer>,
+    pool: P,
+}
+
+impl<P: BufferPool> PooledBuffer<P> {
+    pub fn new(pool: P) -> Self {
+        Self {
+            buffer: Some(pool.acquire()),
+            pool,
+        }
+    }
+}
+
+impl<P: BufferPool> AsMut<[u8]> for PooledBuffer<P> {
+    #[inline]
+    fn as_mut(&mut self) -> &mut [u8] {
+        self.buffer
+            .as_mut()
+            .expect("pooled buffer was already released")
+            .as_mut()
+    }
+}
+
+impl<P: BufferPool> Drop for PooledBuffer<P> {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            self.pool.release(buffer);
+        }
+    }
+}
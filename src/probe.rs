@@ -0,0 +1,78 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A way for a writer to report whether it can still accept data, so that a
+/// zero-length write can be told apart from a closed writer. Enable it on a
+/// forward with [`Forwarder::with_write_probe`][crate::Forwarder::with_write_probe].
+///
+/// Without a probe, a write that returns `Ok(0)` means the writer is closed,
+/// and the forward fails with
+/// [`ForwarderError::WriteClosedEarly`][crate::ForwarderError::WriteClosedEarly].
+/// With a probe, if the writer still reports that it's writable, `Ok(0)` is
+/// instead treated as backpressure: it's counted like a `WouldBlock`, and
+/// the writer is responsible for waking the task once it has room again,
+/// just as it would be after returning `Pending`.
+///
+/// A probe can also report whether the writer is ready for more data right
+/// now, so that we don't keep reading into the buffer while the writer is
+/// falling behind.
+pub trait WriteProbe {
+    /// Returns true if the writer can still accept data in the future, even
+    /// if it can't right now. The default implementation always returns
+    /// false, which keeps the usual meaning of `Ok(0)`.
+    fn is_writable(&self) -> bool {
+        false
+    }
+
+    /// Check whether the writer has room for more data. If it returns
+    /// `Pending`, it must arrange for the task to be woken once it does. The
+    /// default implementation is always ready.
+    fn poll_write_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let _ = cx;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: WriteProbe + Unpin + ?Sized> WriteProbe for &mut T {
+    #[inline]
+    fn is_writable(&self) -> bool {
+        T::is_writable(self)
+    }
+
+    #[inline]
+    fn poll_write_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut **self.get_mut()).poll_write_ready(cx)
+    }
+}
+
+impl<T: WriteProbe + Unpin + ?Sized> WriteProbe for Box<T> {
+    #[inline]
+    fn is_writable(&self) -> bool {
+        T::is_writable(self)
+    }
+
+    #[inline]
+    fn poll_write_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut **self.get_mut()).poll_write_ready(cx)
+    }
+}
+
+/// The [`WriteProbe`] methods of a writer, captured as function pointers, so
+/// that a forwarder can use them without requiring `W: WriteProbe`
+/// everywhere
+pub struct Probe<W> {
+    pub is_writable: fn(&W) -> bool,
+    pub poll_write_ready: fn(Pin<&mut W>, &mut Context<'_>) -> Poll<io::Result<()>>,
+}
+
+impl<W: WriteProbe> Probe<W> {
+    pub fn new() -> Self {
+        Self {
+            is_writable: W::is_writable,
+            poll_write_ready: W::poll_write_ready,
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{future::FusedFuture, stream::FusedStream, Stream};
+use pin_project::pin_project;
+
+use crate::{BufferStorage, ForwardProgress, Forwarder, ForwarderError};
+
+/// An event from a [`ProgressStream`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// Some data was read or written
+    Progress(ForwardProgress),
+
+    /// The forward completed successfully. This is always the last event.
+    Complete { bytes_written: u64 },
+}
+
+/// Stream that runs a [`Forwarder`], yielding an event whenever data moves
+/// and a final event when it completes. Created by
+/// [`Forwarder::into_progress_stream`].
+///
+/// If the forward fails, the error is yielded and the stream ends.
+#[pin_project]
+pub struct ProgressStream<R, W, B, C> {
+    #[pin]
+    forwarder: Forwarder<R, W, B, C>,
+
+    // Set if the forward completed in the same poll as the last progress
+    // event, so the completion event is still to come
+    complete: Option<u64>,
+
+    finished: bool,
+}
+
+impl<R, W, B, C> Forwarder<R, W, B, C> {
+    /// Run the forward as a [`Stream`] of [`ProgressEvent`]s, for wiring up
+    /// progress bars or reporting upload progress. Each event describes the
+    /// data that moved in a single poll.
+    pub fn into_progress_stream(self) -> ProgressStream<R, W, B, C> {
+        ProgressStream {
+            forwarder: self,
+            complete: None,
+            finished: false,
+        }
+    }
+}
+
+impl<R, W, B, C> ProgressStream<R, W, B, C> {
+    /// Get a reference to the underlying forwarder
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Forwarder<R, W, B, C> {
+        &self.forwarder
+    }
+}
+
+impl<R, W, B, C> Stream for ProgressStream<R, W, B, C>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+{
+    type Item = Result<ProgressEvent, ForwarderError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        if *this.finished {
+            return Poll::Ready(None);
+        }
+
+        if let Some(bytes_written) = this.complete.take() {
+            *this.finished = true;
+            return Poll::Ready(Some(Ok(ProgressEvent::Complete { bytes_written })));
+        }
+
+        let progress = match this.forwarder.as_mut().poll_progress(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(Err(err)) => {
+                *this.finished = true;
+                return Poll::Ready(Some(Err(err)));
+            }
+            Poll::Ready(Ok(progress)) => progress,
+        };
+
+        if !this.forwarder.is_terminated() {
+            return Poll::Ready(Some(Ok(ProgressEvent::Progress(progress))));
+        }
+
+        let bytes_written = this.forwarder.inner.counters.bytes_written;
+
+        if progress.read > 0 || progress.wrote > 0 {
+            *this.complete = Some(bytes_written);
+            Poll::Ready(Some(Ok(ProgressEvent::Progress(progress))))
+        } else {
+            *this.finished = true;
+            Poll::Ready(Some(Ok(ProgressEvent::Complete { bytes_written })))
+        }
+    }
+}
+
+impl<R, W, B, C> FusedStream for ProgressStream<R, W, B, C>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+{
+    fn is_terminated(&self) -> bool {
+        self.finished
+    }
+}
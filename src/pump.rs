@@ -0,0 +1,172 @@
+use std::{
+    io::{self, IoSlice},
+    num::NonZeroUsize,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{AsyncRead, AsyncWrite};
+
+use crate::{
+    buffer::{truncate_mut, BufferStorage, DuplexBuffer},
+    vectored::{read_vectored_pair, write_vectored_limited, DEFAULT_MAX_IOV},
+};
+
+/// The ring buffer at the core of a [`Forwarder`][crate::Forwarder], with
+/// the plumbing to read into it and write out of it with vectored I/O. This
+/// is for building custom pumps, like tees or bespoke scheduling, on the
+/// same ring: the caller decides when to read and when to write, and the
+/// pump takes care of turning the wrapped ring into I/O slices and
+/// advancing it by however much was transferred.
+///
+/// Errors, including `WouldBlock` and `Interrupted`, are returned as-is, and
+/// the buffer is left unchanged when an operation fails or is pending.
+pub struct DuplexPump<B> {
+    pub(crate) buffer: DuplexBuffer<B>,
+    max_iov: NonZeroUsize,
+}
+
+impl<B: BufferStorage> DuplexPump<B> {
+    pub fn new(buffer: B) -> Self {
+        Self {
+            buffer: DuplexBuffer::new(buffer),
+            max_iov: DEFAULT_MAX_IOV,
+        }
+    }
+
+    /// Set the maximum number of slices passed to a single vectored write.
+    /// See [`Forwarder::with_max_iov`][crate::Forwarder::with_max_iov].
+    pub fn with_max_iov(self, max_iov: NonZeroUsize) -> Self {
+        Self { max_iov, ..self }
+    }
+
+    /// Attempt a single read from `reader` into the free space in the
+    /// buffer, and return the number of bytes read. `Ok(0)` means the reader
+    /// reached EOF.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there's no room in the buffer; check
+    /// [`read_ready`][Self::read_ready] first.
+    pub fn poll_pump_read<R: AsyncRead + ?Sized>(
+        &mut self,
+        cx: &mut Context<'_>,
+        reader: Pin<&mut R>,
+    ) -> Poll<io::Result<usize>> {
+        self.poll_read_limited(cx, reader, None)
+    }
+
+    /// Like [`poll_pump_read`][Self::poll_pump_read], but reading at most
+    /// `limit` bytes, if there's a limit. The limit must not be 0.
+    pub(crate) fn poll_read_limited<R: AsyncRead + ?Sized>(
+        &mut self,
+        cx: &mut Context<'_>,
+        reader: Pin<&mut R>,
+        limit: Option<usize>,
+    ) -> Poll<io::Result<usize>> {
+        assert!(self.read_ready(), "pump read with no room in the buffer");
+        debug_assert_ne!(limit, Some(0), "a zero-length read looks like EOF");
+
+        // If there are overflow buffers, we scatter the read across the ring
+        // and the overflow; otherwise, we just read into the ring.
+        let result = if self.buffer.has_overflow() {
+            let (ring_len, mut slices) = self.buffer.scatter_read_slices(limit);
+            reader
+                .poll_read_vectored(cx, &mut slices)
+                .map_ok(|n| (n, ring_len))
+        } else {
+            let mut remaining = limit.unwrap_or(usize::MAX);
+            let [b1, b2] = self.buffer.get_buffers().read;
+            let [b1, b2] = [b1, b2].map(|slice| truncate_mut(slice, &mut remaining));
+            let ring_len = b1.len() + b2.len();
+            read_vectored_pair(reader, cx, [b1, b2]).map_ok(|n| (n, ring_len))
+        };
+
+        let (n, ring_len) = futures::ready!(result)?;
+
+        if let Some(n) = NonZeroUsize::new(n) {
+            self.buffer.advance_scatter_read(n, ring_len);
+        }
+
+        Poll::Ready(Ok(n))
+    }
+
+    /// Attempt to write the buffered data to `writer`, and return the number
+    /// of bytes written. `Ok(0)` means the writer didn't accept anything,
+    /// which usually means it's closed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there's nothing buffered; check
+    /// [`write_ready`][Self::write_ready] first.
+    pub fn poll_pump_write<W: AsyncWrite + ?Sized>(
+        &mut self,
+        cx: &mut Context<'_>,
+        writer: Pin<&mut W>,
+    ) -> Poll<io::Result<usize>> {
+        self.poll_write_limited(cx, writer, self.max_iov, None)
+    }
+
+    /// Like [`poll_pump_write`][Self::poll_pump_write], but with an explicit
+    /// `max_iov`, and only offering the first `limit` buffered bytes, if
+    /// there's a limit.
+    pub(crate) fn poll_write_limited<W: AsyncWrite + ?Sized>(
+        &mut self,
+        cx: &mut Context<'_>,
+        writer: Pin<&mut W>,
+        max_iov: NonZeroUsize,
+        limit: Option<usize>,
+    ) -> Poll<io::Result<usize>> {
+        assert!(self.write_ready(), "pump write with nothing buffered");
+
+        // If there are overflow buffers, we also need to write whatever was
+        // scattered into them, after the ring's data
+        let result = if self.buffer.has_overflow() {
+            let slices = self.buffer.gather_write_slices();
+            write_vectored_limited(writer, cx, &slices, max_iov, limit)
+        } else {
+            let [b1, b2] = self.buffer.get_buffers().write;
+            let slices = [IoSlice::new(b1), IoSlice::new(b2)];
+            write_vectored_limited(writer, cx, &slices, max_iov, limit)
+        };
+
+        let n = futures::ready!(result)?;
+
+        if let Some(n) = NonZeroUsize::new(n) {
+            self.buffer.advance_write(n);
+        }
+
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl<B> DuplexPump<B> {
+    /// Returns true if there's room to read more data into the buffer
+    #[inline]
+    #[must_use]
+    pub fn read_ready(&self) -> bool {
+        self.buffer.read_ready()
+    }
+
+    /// Returns true if there's buffered data to write
+    #[inline]
+    #[must_use]
+    pub fn write_ready(&self) -> bool {
+        self.buffer.write_ready()
+    }
+
+    /// The number of bytes that have been read into the buffer but not yet
+    /// written out of it
+    #[inline]
+    #[must_use]
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.buffered_len()
+    }
+
+    /// The total size of the buffer
+    #[inline]
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+}
@@ -0,0 +1,149 @@
+use std::{
+    future::Future,
+    num::NonZeroU64,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use crate::sleep::Sleeper;
+
+/// The fraction of a second's worth of data that we wait for once the
+/// bucket is empty. Waiting for a small quantum, rather than for the bucket
+/// to refill, keeps the pacing smooth.
+const QUANTA_PER_SECOND: u64 = 100;
+
+/// A token bucket. It starts out full.
+#[derive(Debug)]
+struct Bucket {
+    bytes_per_sec: NonZeroU64,
+    burst: NonZeroU64,
+
+    // Fractional, so that slow rates don't lose tokens to rounding
+    tokens: f64,
+    last_refill: Option<Instant>,
+}
+
+impl Bucket {
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let last_refill = self.last_refill.replace(now).unwrap_or(now);
+        let earned = (now - last_refill).as_secs_f64() * self.bytes_per_sec.get() as f64;
+
+        self.tokens = (self.tokens + earned).min(self.burst.get() as f64);
+    }
+
+    /// The number of bytes that can be read right now, or how long to wait
+    /// until a quantum of data is allowed
+    fn allowance(&mut self) -> Result<u64, Duration> {
+        self.refill();
+
+        if self.tokens >= 1.0 {
+            return Ok(self.tokens as u64);
+        }
+
+        let quantum =
+            (self.bytes_per_sec.get() / QUANTA_PER_SECOND).clamp(1, self.burst.get()) as f64;
+
+        Err(Duration::from_secs_f64(
+            (quantum - self.tokens) / self.bytes_per_sec.get() as f64,
+        ))
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    bucket: Mutex<Bucket>,
+
+    // The number of forwarders using this limiter
+    forwarders: AtomicU64,
+}
+
+/// A bandwidth budget, shared by any number of forwarders, with a token
+/// bucket that allows `bytes_per_sec` on average, in bursts of up to `burst`
+/// bytes. Clones share the same budget. See
+/// [`Forwarder::with_shared_rate_limit`][crate::Forwarder::with_shared_rate_limit].
+///
+/// Each forwarder takes at most an equal share of the available tokens at a
+/// time, so that a busy forwarder can't starve the others.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    shared: Arc<Shared>,
+}
+
+impl RateLimiter {
+    pub fn new(bytes_per_sec: NonZeroU64, burst: NonZeroU64) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                bucket: Mutex::new(Bucket {
+                    bytes_per_sec,
+                    burst,
+                    tokens: burst.get() as f64,
+                    last_refill: None,
+                }),
+                forwarders: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    fn bucket(&self) -> std::sync::MutexGuard<'_, Bucket> {
+        self.shared
+            .bucket
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A single forwarder's use of a [`RateLimiter`], with the sleeps it uses to
+/// wait for the bucket to refill
+pub struct RateLimit {
+    limiter: RateLimiter,
+    sleeper: Sleeper,
+}
+
+impl RateLimit {
+    pub fn new<F, S>(limiter: RateLimiter, sleep: F) -> Self
+    where
+        F: FnMut(Duration) -> S + Send + 'static,
+        S: Future<Output = ()> + Send + 'static,
+    {
+        limiter.shared.forwarders.fetch_add(1, Ordering::Relaxed);
+
+        Self {
+            limiter,
+            sleeper: Sleeper::new(sleep),
+        }
+    }
+
+    /// Resolves with the number of bytes that can be read right now, which
+    /// is never 0. If the bucket is empty, this waits until a small quantum
+    /// of data is allowed.
+    pub fn poll_allowance(&mut self, cx: &mut Context<'_>) -> Poll<u64> {
+        loop {
+            let forwarders = self.limiter.shared.forwarders.load(Ordering::Relaxed);
+
+            match self.limiter.bucket().allowance() {
+                Ok(tokens) => return Poll::Ready((tokens / forwarders.max(1)).max(1)),
+                Err(wait) => futures::ready!(self.sleeper.poll_sleep(cx, wait)),
+            }
+        }
+    }
+
+    /// Record that `n` bytes were read
+    #[inline]
+    pub fn consume(&mut self, n: usize) {
+        self.limiter.bucket().tokens -= n as f64;
+    }
+}
+
+impl Drop for RateLimit {
+    fn drop(&mut self) {
+        self.limiter
+            .shared
+            .forwarders
+            .fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,232 @@
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::future::FusedFuture;
+#[cfg(feature = "futures-timer")]
+use futures_timer::Delay;
+use pin_project::pin_project;
+
+use crate::{sleep::Sleeper, BufferStorage, Forwarder, ForwarderError};
+
+/// How long to wait between attempts to reconnect a writer, for
+/// [`Forwarder::with_reconnect`]. The delay starts at `initial` and doubles
+/// with each failed attempt, up to a maximum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    initial: Duration,
+    max_delay: Duration,
+    max_attempts: u32,
+}
+
+impl Backoff {
+    /// Wait `initial` before the first attempt, doubling the delay after
+    /// each failure, and give up after `max_attempts` attempts in a row. By
+    /// default, the delay is capped at 30 seconds.
+    pub fn new(initial: Duration, max_attempts: u32) -> Self {
+        Self {
+            initial,
+            max_delay: Duration::from_secs(30),
+            max_attempts,
+        }
+    }
+
+    /// Cap the delay between attempts at `max_delay`
+    pub fn with_max_delay(self, max_delay: Duration) -> Self {
+        Self { max_delay, ..self }
+    }
+
+    /// The delay before the given attempt, counting from 0
+    #[must_use]
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt).unwrap_or(u32::MAX);
+
+        self.initial.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+/// Future that runs a [`Forwarder`], reconnecting the writer whenever it
+/// fails. Created by [`Forwarder::with_reconnect`].
+///
+/// On success, the output is the total number of bytes written, across all
+/// the connections.
+#[pin_project]
+pub struct Reconnect<R, W, B, C, F, Fut> {
+    #[pin]
+    forwarder: Forwarder<R, W, B, C>,
+
+    connector: F,
+
+    // The connection attempt in progress, if any
+    #[pin]
+    connecting: Option<Fut>,
+
+    sleeper: Sleeper,
+    backoff: Backoff,
+
+    // Failed attempts since the writer last made progress
+    attempts: u32,
+    reconnects: usize,
+
+    // The most recent failure, which is returned if we give up. It's set for
+    // as long as there's no working writer.
+    error: Option<ForwarderError>,
+
+    // The number of bytes written when the current writer was connected
+    written_at_connect: u64,
+}
+
+impl<R, W, B, C> Forwarder<R, W, B, C> {
+    /// Instead of failing when a write fails, or when the writer stops
+    /// accepting data, connect a new writer with `connector` and resume from
+    /// the data that's still buffered, so nothing is lost. Data that the
+    /// failed writer accepted isn't sent again.
+    ///
+    /// Each attempt waits according to `backoff`, with sleeps from `sleep`,
+    /// like with [`with_idle_timeout`][Self::with_idle_timeout]. If the
+    /// connector fails, it's tried again, and once `backoff` runs out of
+    /// attempts, the forward fails with the most recent error; a failed
+    /// connection is reported as [`ForwarderError::Write`]. The attempts
+    /// start over once a new writer has accepted some data.
+    ///
+    /// Failed writers are dropped without being flushed or closed. Errors
+    /// from reading, or from flushing or closing the writer, aren't retried.
+    pub fn with_reconnect<F, Fut, S, SF>(
+        self,
+        connector: F,
+        backoff: Backoff,
+        sleep: S,
+    ) -> Reconnect<R, W, B, C, F, Fut>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = io::Result<W>>,
+        S: FnMut(Duration) -> SF + Send + 'static,
+        SF: Future<Output = ()> + Send + 'static,
+    {
+        Reconnect {
+            forwarder: self,
+            connector,
+            connecting: None,
+            sleeper: Sleeper::new(sleep),
+            backoff,
+            attempts: 0,
+            reconnects: 0,
+            error: None,
+            written_at_connect: 0,
+        }
+    }
+
+    /// Reconnect the writer when it fails, using `futures-timer` for the
+    /// sleeps. See [`with_reconnect`][Self::with_reconnect].
+    #[cfg(feature = "futures-timer")]
+    pub fn reconnect<F, Fut>(self, connector: F, backoff: Backoff) -> Reconnect<R, W, B, C, F, Fut>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = io::Result<W>>,
+    {
+        self.with_reconnect(connector, backoff, Delay::new)
+    }
+}
+
+impl<R, W, B, C, F, Fut> Reconnect<R, W, B, C, F, Fut> {
+    /// Get a reference to the underlying forwarder
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Forwarder<R, W, B, C> {
+        &self.forwarder
+    }
+
+    /// The number of times the writer has been successfully reconnected
+    #[inline]
+    #[must_use]
+    pub fn reconnects(&self) -> usize {
+        self.reconnects
+    }
+}
+
+impl<R, W, B, C, F, Fut> Future for Reconnect<R, W, B, C, F, Fut>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = io::Result<W>>,
+{
+    type Output = Result<u64, ForwarderError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        loop {
+            if this.error.is_none() {
+                let result = futures::ready!(this.forwarder.as_mut().poll(cx));
+
+                let err = match result {
+                    Err(
+                        err @ (ForwarderError::Write { .. }
+                        | ForwarderError::WriteClosedEarly { .. }),
+                    ) => err,
+                    result => return Poll::Ready(result),
+                };
+
+                if this.forwarder.inner.counters.bytes_written > *this.written_at_connect {
+                    *this.attempts = 0;
+                }
+
+                *this.error = Some(err);
+            }
+
+            if let Some(connecting) = this.connecting.as_mut().as_pin_mut() {
+                let result = futures::ready!(connecting.poll(cx));
+                this.connecting.set(None);
+
+                match result {
+                    Ok(writer) => {
+                        this.forwarder.as_mut().replace_writer(writer);
+                        *this.reconnects += 1;
+                        *this.written_at_connect = this.forwarder.inner.counters.bytes_written;
+                        *this.error = None;
+                        continue;
+                    }
+                    Err(err) => {
+                        let forwarder = this.forwarder.as_mut().project().inner.project();
+                        let unforwarded = forwarder.pump.buffer.buffered_data();
+
+                        *this.error = Some(forwarder.counters.write_error(err, unforwarded));
+                    }
+                }
+            }
+
+            // There's no working writer, so back off and try to connect a
+            // new one
+            if *this.attempts >= this.backoff.max_attempts {
+                return Poll::Ready(Err(this.error.take().unwrap()));
+            }
+
+            let delay = this.backoff.delay(*this.attempts);
+            futures::ready!(this.sleeper.poll_sleep(cx, delay));
+
+            *this.attempts += 1;
+            this.connecting.set(Some((this.connector)()));
+        }
+    }
+}
+
+impl<R, W, B, C, F, Fut> FusedFuture for Reconnect<R, W, B, C, F, Fut>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = io::Result<W>>,
+{
+    fn is_terminated(&self) -> bool {
+        self.forwarder.is_terminated() && self.error.is_none()
+    }
+}
@@ -0,0 +1,255 @@
+use std::{
+    future::Future,
+    io,
+    num::NonZeroUsize,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::{Bytes, BytesMut};
+use futures::{AsyncRead, Sink};
+use pin_project::pin_project;
+
+use crate::{
+    buffer::{BufferStorage, DuplexBuffer},
+    vectored::read_vectored_pair,
+    ForwarderError, MAX_CYCLES_PER_POLL,
+};
+
+/// Future that splits everything from a reader into records at a delimiter,
+/// and sends each record to a `Sink` of [`Bytes`] as its own item. Created
+/// by [`forward_records`].
+///
+/// The delimiter is found by scanning the ring buffer in place, and each
+/// record is copied out of it once, so the reader can be read in large
+/// chunks. Records don't include the delimiter. Empty records between two
+/// delimiters are sent as empty items, but if the data ends with a partial
+/// record, it's sent as the last item only if it's not empty.
+///
+/// A record has to fit in the buffer; if the buffer fills up without a
+/// delimiter, the forward fails with [`ForwarderError::RecordTooLong`]. The
+/// sink is flushed once everything has been sent. On success, the output is
+/// the number of records sent.
+#[pin_project]
+pub struct RecordForwarder<R, S, B> {
+    #[pin]
+    reader: Option<R>,
+
+    #[pin]
+    sink: S,
+
+    buffer: DuplexBuffer<B>,
+    delimiter: u8,
+
+    // The length of the buffered data that's known not to contain the
+    // delimiter, so that it isn't scanned again
+    scanned: usize,
+
+    records: u64,
+    bytes_read: u64,
+}
+
+/// Forward everything from `reader` into `sink`, one item per record, where
+/// records are separated by `delimiter`, like `b'\n'` for lines.
+pub fn forward_records<R, S, B>(
+    reader: R,
+    sink: S,
+    buffer: B,
+    delimiter: u8,
+) -> RecordForwarder<R, S, B>
+where
+    R: AsyncRead,
+    S: Sink<Bytes>,
+    S::Error: Into<io::Error>,
+    B: BufferStorage,
+{
+    RecordForwarder {
+        reader: Some(reader),
+        sink,
+        buffer: DuplexBuffer::new(buffer),
+        delimiter,
+        scanned: 0,
+        records: 0,
+        bytes_read: 0,
+    }
+}
+
+impl<R, S, B> RecordForwarder<R, S, B> {
+    /// The number of records that have been sent so far
+    #[inline]
+    #[must_use]
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+}
+
+/// Find the first `delimiter` in the buffered data, starting at `from`
+fn find_delimiter<B: BufferStorage>(
+    buffer: &mut DuplexBuffer<B>,
+    from: usize,
+    delimiter: u8,
+) -> Option<usize> {
+    let [b1, b2] = buffer.get_buffers().write;
+
+    b1.iter()
+        .chain(b2)
+        .skip(from)
+        .position(|&byte| byte == delimiter)
+        .map(|index| index + from)
+}
+
+/// Copy the first `len` bytes of the buffered data into an item, and remove
+/// them, along with `skip` more bytes for the delimiter
+fn take_record<B: BufferStorage>(buffer: &mut DuplexBuffer<B>, len: usize, skip: usize) -> Bytes {
+    let [b1, b2] = buffer.get_buffers().write;
+    let mut record = BytesMut::with_capacity(len);
+
+    record.extend_from_slice(&b1[..len.min(b1.len())]);
+    record.extend_from_slice(&b2[..len - len.min(b1.len())]);
+
+    if let Some(consumed) = NonZeroUsize::new(len + skip) {
+        buffer.advance_write(consumed);
+    }
+
+    record.freeze()
+}
+
+impl<R, S, B> Future for RecordForwarder<R, S, B>
+where
+    R: AsyncRead,
+    S: Sink<Bytes>,
+    S::Error: Into<io::Error>,
+    B: BufferStorage,
+{
+    type Output = Result<u64, ForwarderError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        let mut cycles = 0;
+
+        loop {
+            // Don't hog the executor with a reader and writer that are
+            // always ready; come back for the rest after yielding
+            if cycles == MAX_CYCLES_PER_POLL {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            cycles += 1;
+
+            let mut progress = false;
+
+            // Send every complete record that the sink will take, plus the
+            // final partial record once the reader is done
+            loop {
+                let buffered = this.buffer.buffered_len();
+
+                let (len, skip) = match find_delimiter(this.buffer, *this.scanned, *this.delimiter)
+                {
+                    Some(index) => (index, 1),
+                    None if this.reader.is_none() && buffered > 0 => (buffered, 0),
+                    None => {
+                        *this.scanned = buffered;
+                        break;
+                    }
+                };
+
+                match this.sink.as_mut().poll_ready(cx) {
+                    Poll::Pending => {
+                        *this.scanned = len;
+                        break;
+                    }
+                    Poll::Ready(Err(err)) => {
+                        return Poll::Ready(Err(ForwarderError::Write {
+                            error: err.into(),
+                            bytes_read: *this.bytes_read,
+                            bytes_written: *this.bytes_read - buffered as u64,
+                            unforwarded: this.buffer.buffered_data(),
+                        }))
+                    }
+                    Poll::Ready(Ok(())) => {}
+                }
+
+                let record = take_record(this.buffer, len, skip);
+                *this.scanned = 0;
+
+                // The record is cheap to clone, and it's needed to recover
+                // the unsent data if the sink rejects it
+                if let Err(err) = this.sink.as_mut().start_send(record.clone()) {
+                    let mut unforwarded = record.to_vec();
+                    unforwarded.extend(&[*this.delimiter][..skip]);
+                    unforwarded.extend(this.buffer.buffered_data());
+
+                    return Poll::Ready(Err(ForwarderError::Write {
+                        error: err.into(),
+                        bytes_read: *this.bytes_read,
+                        bytes_written: *this.bytes_read - buffered as u64,
+                        unforwarded,
+                    }));
+                }
+
+                *this.records += 1;
+                progress = true;
+            }
+
+            if let Some(reader) = this.reader.as_mut().as_pin_mut() {
+                if !this.buffer.read_ready() {
+                    // The buffer is full, so if there's no delimiter in it,
+                    // there never will be
+                    if *this.scanned == this.buffer.buffered_len() {
+                        return Poll::Ready(Err(ForwarderError::RecordTooLong {
+                            capacity: this.buffer.capacity(),
+                        }));
+                    }
+                } else {
+                    let [b1, b2] = this.buffer.get_buffers().read;
+
+                    match read_vectored_pair(reader, cx, [b1, b2]) {
+                        Poll::Pending => {}
+                        Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::WouldBlock => {
+                            cx.waker().wake_by_ref();
+                        }
+                        Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::Interrupted => {
+                            progress = true;
+                        }
+                        Poll::Ready(Err(error)) => {
+                            return Poll::Ready(Err(ForwarderError::Read {
+                                error,
+                                bytes_read: *this.bytes_read,
+                                bytes_written: *this.bytes_read - this.buffer.buffered_len() as u64,
+                            }))
+                        }
+                        Poll::Ready(Ok(n)) => {
+                            match NonZeroUsize::new(n) {
+                                None => this.reader.set(None),
+                                Some(n) => {
+                                    this.buffer.advance_read(n);
+                                    *this.bytes_read += n.get() as u64;
+                                }
+                            }
+                            progress = true;
+                        }
+                    }
+                }
+            }
+
+            if !progress {
+                break;
+            }
+        }
+
+        // Everything's been sent; flush the sink, and we're done once that
+        // finishes
+        if this.reader.is_none() && !this.buffer.write_ready() {
+            let records = *this.records;
+
+            return this
+                .sink
+                .poll_flush(cx)
+                .map_ok(|()| records)
+                .map_err(|err| ForwarderError::FlushFailed(err.into()));
+        }
+
+        Poll::Pending
+    }
+}
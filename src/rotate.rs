@@ -0,0 +1,255 @@
+use std::{
+    future::Future,
+    io,
+    num::NonZeroU64,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{future::FusedFuture, AsyncRead, AsyncWrite};
+use pin_project::pin_project;
+
+use crate::{
+    buffer::BufferStorage, pump::DuplexPump, vectored::DEFAULT_MAX_IOV, ForwarderError,
+    MAX_CYCLES_PER_POLL,
+};
+
+/// Future that forwards a reader into a sequence of writers, switching to a
+/// fresh writer every `segment_len` bytes, for things like log rotation and
+/// chunked uploads. Created by [`RotatingForwarder::new`].
+///
+/// Each writer is produced by an async factory, which is passed the index
+/// of the segment. Writes are split so that every writer gets exactly
+/// `segment_len` bytes, except for the last one, which gets whatever is
+/// left. Once a writer is full, it's flushed and closed before the next one
+/// is opened, and a new writer is only opened once there's data for it, so
+/// there's never an empty trailing segment. If the factory fails, the
+/// forward fails with [`ForwarderError::Write`].
+///
+/// On success, the output is the total number of bytes written, across all
+/// the writers.
+#[pin_project]
+pub struct RotatingForwarder<R, W, B, F, Fut> {
+    #[pin]
+    reader: Option<R>,
+
+    // The current segment's writer
+    #[pin]
+    writer: Option<W>,
+
+    // The next segment's writer, while it's being created
+    #[pin]
+    opening: Option<Fut>,
+
+    factory: F,
+    pump: DuplexPump<B>,
+    segment_len: NonZeroU64,
+
+    // Bytes written to the current writer
+    segment_written: u64,
+    segments: u64,
+    bytes_written: u64,
+    done: bool,
+}
+
+impl<R, W, B, F, Fut> RotatingForwarder<R, W, B, F, Fut>
+where
+    R: AsyncRead,
+    W: AsyncWrite,
+    B: BufferStorage,
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = io::Result<W>>,
+{
+    pub fn new(reader: R, buffer: B, segment_len: NonZeroU64, factory: F) -> Self {
+        Self {
+            reader: Some(reader),
+            writer: None,
+            opening: None,
+            factory,
+            pump: DuplexPump::new(buffer),
+            segment_len,
+            segment_written: 0,
+            segments: 0,
+            bytes_written: 0,
+            done: false,
+        }
+    }
+}
+
+impl<R, W, B, F, Fut> RotatingForwarder<R, W, B, F, Fut> {
+    /// The number of writers that have been opened so far
+    #[inline]
+    #[must_use]
+    pub fn segments(&self) -> u64 {
+        self.segments
+    }
+
+    /// The total number of bytes that have been written so far
+    #[inline]
+    #[must_use]
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+}
+
+impl<R, W, B, F, Fut> Future for RotatingForwarder<R, W, B, F, Fut>
+where
+    R: AsyncRead,
+    W: AsyncWrite,
+    B: BufferStorage,
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = io::Result<W>>,
+{
+    type Output = Result<u64, ForwarderError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        assert!(!*this.done, "RotatingForwarder polled after completion");
+
+        let mut cycles = 0;
+
+        let result = loop {
+            // Don't hog the executor with a reader and writer that are
+            // always ready; come back for the rest after yielding
+            if cycles == MAX_CYCLES_PER_POLL {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            cycles += 1;
+
+            let mut progress = false;
+
+            if let Some(reader) = this.reader.as_mut().as_pin_mut() {
+                if this.pump.read_ready() {
+                    match this.pump.poll_pump_read(cx, reader) {
+                        Poll::Pending => {}
+                        Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::WouldBlock => {
+                            cx.waker().wake_by_ref();
+                        }
+                        Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::Interrupted => {
+                            progress = true;
+                        }
+                        Poll::Ready(Err(error)) => {
+                            break Err(ForwarderError::Read {
+                                error,
+                                bytes_read: *this.bytes_written + this.pump.buffered_len() as u64,
+                                bytes_written: *this.bytes_written,
+                            })
+                        }
+                        Poll::Ready(Ok(0)) => {
+                            this.reader.set(None);
+                            progress = true;
+                        }
+                        Poll::Ready(Ok(_)) => progress = true,
+                    }
+                }
+            }
+
+            let segment_full = *this.segment_written >= this.segment_len.get();
+            let finished = this.reader.is_none() && !this.pump.write_ready();
+
+            if let Some(writer) = this.writer.as_mut().as_pin_mut() {
+                if segment_full || finished {
+                    // This segment is done, so close its writer. Closing
+                    // flushes it first.
+                    match writer.poll_close(cx) {
+                        Poll::Pending => {}
+                        Poll::Ready(Err(err)) => break Err(ForwarderError::CloseFailed(err)),
+                        Poll::Ready(Ok(())) => {
+                            this.writer.set(None);
+                            *this.segment_written = 0;
+                            progress = true;
+                        }
+                    }
+                } else if this.pump.write_ready() {
+                    // Only offer what fits in the rest of this segment
+                    let remaining = this.segment_len.get() - *this.segment_written;
+                    let limit = usize::try_from(remaining).unwrap_or(usize::MAX);
+
+                    match this
+                        .pump
+                        .poll_write_limited(cx, writer, DEFAULT_MAX_IOV, Some(limit))
+                    {
+                        Poll::Pending => {}
+                        Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::WouldBlock => {
+                            cx.waker().wake_by_ref();
+                        }
+                        Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::Interrupted => {
+                            progress = true;
+                        }
+                        Poll::Ready(Err(error)) => {
+                            break Err(ForwarderError::Write {
+                                error,
+                                bytes_read: *this.bytes_written + this.pump.buffered_len() as u64,
+                                bytes_written: *this.bytes_written,
+                                unforwarded: this.pump.buffer.buffered_data(),
+                            })
+                        }
+                        Poll::Ready(Ok(0)) => {
+                            break Err(ForwarderError::WriteClosedEarly {
+                                forwarded: *this.bytes_written,
+                                unforwarded: this.pump.buffer.buffered_data(),
+                            })
+                        }
+                        Poll::Ready(Ok(n)) => {
+                            *this.segment_written += n as u64;
+                            *this.bytes_written += n as u64;
+                            progress = true;
+                        }
+                    }
+                }
+            } else if this.pump.write_ready() {
+                // There's data for a new segment, so open its writer
+                if this.opening.is_none() {
+                    let opening = (this.factory)(*this.segments);
+                    this.opening.set(Some(opening));
+                }
+
+                let opening = this.opening.as_mut().as_pin_mut().unwrap();
+
+                if let Poll::Ready(result) = opening.poll(cx) {
+                    this.opening.set(None);
+
+                    match result {
+                        Ok(writer) => {
+                            this.writer.set(Some(writer));
+                            *this.segments += 1;
+                            progress = true;
+                        }
+                        Err(error) => {
+                            break Err(ForwarderError::Write {
+                                error,
+                                bytes_read: *this.bytes_written + this.pump.buffered_len() as u64,
+                                bytes_written: *this.bytes_written,
+                                unforwarded: this.pump.buffer.buffered_data(),
+                            })
+                        }
+                    }
+                }
+            } else if finished {
+                break Ok(*this.bytes_written);
+            }
+
+            if !progress {
+                return Poll::Pending;
+            }
+        };
+
+        *this.done = true;
+        Poll::Ready(result)
+    }
+}
+
+impl<R, W, B, F, Fut> FusedFuture for RotatingForwarder<R, W, B, F, Fut>
+where
+    R: AsyncRead,
+    W: AsyncWrite,
+    B: BufferStorage,
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = io::Result<W>>,
+{
+    fn is_terminated(&self) -> bool {
+        self.done
+    }
+}
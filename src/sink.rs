@@ -0,0 +1,128 @@
+use std::{
+    io::{self, IoSlice},
+    num::NonZeroUsize,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::AsyncWrite;
+use pin_project::pin_project;
+
+use crate::{
+    buffer::{BufferStorage, DuplexBuffer},
+    vectored::{write_vectored_buffers, DEFAULT_MAX_IOV},
+};
+
+/// A buffered writer built on the same ring buffer as the
+/// [`Forwarder`][crate::Forwarder]. Data written to a `BufferSink` is copied
+/// into the ring, and the ring is drained into the wrapped writer when it
+/// fills up, or when the sink is flushed or closed.
+#[pin_project]
+pub struct BufferSink<W, B> {
+    #[pin]
+    writer: W,
+
+    buffer: DuplexBuffer<B>,
+
+    max_iov: NonZeroUsize,
+}
+
+impl<W: AsyncWrite, B: BufferStorage> BufferSink<W, B> {
+    pub fn new(writer: W, buffer: B) -> Self {
+        Self {
+            writer,
+            buffer: DuplexBuffer::new(buffer),
+            max_iov: DEFAULT_MAX_IOV,
+        }
+    }
+
+    /// Set the maximum number of slices passed to a single vectored write.
+    /// See [`Forwarder::with_max_iov`][crate::Forwarder::with_max_iov].
+    pub fn with_max_iov(self, max_iov: NonZeroUsize) -> Self {
+        Self { max_iov, ..self }
+    }
+}
+
+/// Attempt a single write out of the ring and into the writer, advancing the
+/// ring by however much was written.
+fn poll_drain_once<W: AsyncWrite, B: BufferStorage>(
+    writer: Pin<&mut W>,
+    buffer: &mut DuplexBuffer<B>,
+    max_iov: NonZeroUsize,
+    cx: &mut Context<'_>,
+) -> Poll<io::Result<()>> {
+    let buffers = buffer.get_buffers();
+
+    // Nothing to write; make sure we don't mistake an empty write for a
+    // closed writer
+    if buffers.writable_len() == 0 {
+        return Poll::Ready(Ok(()));
+    }
+
+    let [b1, b2] = buffers.write;
+
+    match write_vectored_buffers(writer, cx, &[IoSlice::new(b1), IoSlice::new(b2)], max_iov) {
+        Poll::Pending => Poll::Pending,
+        // `WouldBlock` doesn't register a waker, so make sure we're polled
+        // again to retry
+        Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::WouldBlock => {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+        Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
+        Poll::Ready(Ok(n)) => match NonZeroUsize::new(n) {
+            None => Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+            Some(n) => {
+                buffer.advance_write(n);
+                Poll::Ready(Ok(()))
+            }
+        },
+    }
+}
+
+impl<W: AsyncWrite, B: BufferStorage> AsyncWrite for BufferSink<W, B> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut this = self.project();
+
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        // Like a `BufWriter`, we only write to the underlying writer when
+        // there's no room left in the ring.
+        while !this.buffer.read_ready() {
+            match poll_drain_once(this.writer.as_mut(), this.buffer, *this.max_iov, cx) {
+                Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::Interrupted => {}
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Ready(Ok(())) => {}
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        Poll::Ready(Ok(this.buffer.fill(buf)))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut this = self.project();
+
+        while this.buffer.write_ready() {
+            match poll_drain_once(this.writer.as_mut(), this.buffer, *this.max_iov, cx) {
+                Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::Interrupted => {}
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Ready(Ok(())) => {}
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        this.writer.poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        futures::ready!(self.as_mut().poll_flush(cx))?;
+        self.project().writer.poll_close(cx)
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    io::{self, IoSlice},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::{Bytes, BytesMut};
+use futures::{AsyncRead, AsyncWrite, Sink};
+use pin_project::pin_project;
+
+use crate::{BufferStorage, Forwarder};
+
+/// Adapts a `Sink` of [`Bytes`], like a channel or a websocket, into a
+/// writer, so that it can be the destination of a
+/// [`Forwarder`][crate::Forwarder]. Each write waits for the sink to be
+/// ready, then sends whatever is offered, up to the whole contents of the
+/// forwarder's buffer, as a single item. Flushing and closing flush and
+/// close the sink.
+///
+/// The sink's errors are converted into `io::Error`.
+#[pin_project]
+#[derive(Debug)]
+pub struct SinkWriter<S> {
+    #[pin]
+    sink: S,
+}
+
+impl<S> SinkWriter<S> {
+    pub fn new(sink: S) -> Self {
+        Self { sink }
+    }
+
+    /// Get a reference to the sink
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &S {
+        &self.sink
+    }
+
+    /// Recover the sink
+    pub fn into_inner(self) -> S {
+        self.sink
+    }
+}
+
+impl<S, E> AsyncWrite for SinkWriter<S>
+where
+    S: Sink<Bytes, Error = E>,
+    E: Into<io::Error>,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_write_vectored(cx, &[IoSlice::new(buf)])
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let mut this = self.project();
+
+        // An empty item would be pointless, and a zero-length write has to
+        // succeed without doing anything
+        let len: usize = bufs.iter().map(|buf| buf.len()).sum();
+        if len == 0 {
+            return Poll::Ready(Ok(0));
+        }
+
+        futures::ready!(this.sink.as_mut().poll_ready(cx)).map_err(Into::into)?;
+
+        let mut item = BytesMut::with_capacity(len);
+        bufs.iter().for_each(|buf| item.extend_from_slice(buf));
+
+        this.sink.start_send(item.freeze()).map_err(Into::into)?;
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().sink.poll_flush(cx).map_err(Into::into)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().sink.poll_close(cx).map_err(Into::into)
+    }
+}
+
+/// A [`Forwarder`] that forwards a reader into a `Sink` of [`Bytes`].
+pub type ForwardToSink<R, S, B> = Forwarder<R, SinkWriter<S>, B>;
+
+/// Forward everything from `reader` into `sink`, one item per write. The
+/// sink is flushed once the reader reaches EOF, so that every item has been
+/// delivered when the forward completes.
+pub fn forward_to_sink<R, S, B>(reader: R, sink: S, buffer: B) -> ForwardToSink<R, S, B>
+where
+    R: AsyncRead,
+    S: Sink<Bytes>,
+    S::Error: Into<io::Error>,
+    B: BufferStorage,
+{
+    Forwarder::new(reader, SinkWriter::new(sink), buffer).flush_on_eof(true)
+}
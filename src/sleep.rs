@@ -0,0 +1,53 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::future::BoxFuture;
+
+use crate::sync_cell::SyncCell;
+
+type Sleep = BoxFuture<'static, ()>;
+
+/// Sleeps from a user-supplied factory, so that timers work with any
+/// runtime. At most one sleep is in progress at a time.
+pub struct Sleeper {
+    sleeps: SyncCell<Sleeps>,
+}
+
+struct Sleeps {
+    factory: Box<dyn FnMut(Duration) -> Sleep + Send>,
+    current: Option<Sleep>,
+}
+
+impl Sleeper {
+    pub fn new<F, S>(mut sleep: F) -> Self
+    where
+        F: FnMut(Duration) -> S + Send + 'static,
+        S: Future<Output = ()> + Send + 'static,
+    {
+        Self {
+            sleeps: SyncCell::new(Sleeps {
+                factory: Box::new(move |duration| Box::pin(sleep(duration))),
+                current: None,
+            }),
+        }
+    }
+
+    /// Poll the current sleep, starting one for `duration` if there isn't
+    /// one. `duration` is ignored if a sleep is already in progress. Once
+    /// the sleep finishes, the next call starts a new one.
+    pub fn poll_sleep(&mut self, cx: &mut Context<'_>, duration: Duration) -> Poll<()> {
+        let sleeps = self.sleeps.get_mut();
+
+        let sleep = sleeps
+            .current
+            .get_or_insert_with(|| (sleeps.factory)(duration));
+
+        futures::ready!(Pin::new(sleep).poll(cx));
+        sleeps.current = None;
+        Poll::Ready(())
+    }
+}
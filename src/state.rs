@@ -0,0 +1,117 @@
+/// The broad phase of a forward, as reported by
+/// [`Forwarder::debug_state`][crate::Forwarder::debug_state]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForwarderPhase {
+    /// The reader is still active; data is being read and written
+    Forwarding,
+
+    /// The reader is done, and the remaining buffered data is being written
+    Draining,
+
+    /// All the data has been written, and the writer is being flushed
+    Flushing,
+
+    /// All the data has been written
+    Finished,
+}
+
+/// A read-only snapshot of a [`Forwarder`][crate::Forwarder]'s internal
+/// state, intended for logging and debugging.
+#[derive(Debug, Clone)]
+pub struct ForwarderState {
+    /// The offset in the buffer where the next read will go, or `None` if
+    /// the buffer is full
+    pub read_head: Option<usize>,
+
+    /// The offset in the buffer where the next write will come from, or
+    /// `None` if the buffer is empty
+    pub write_head: Option<usize>,
+
+    /// The number of bytes read but not yet written
+    pub buffered: usize,
+
+    /// The total size of the buffer
+    pub capacity: usize,
+
+    /// The total number of bytes read from the reader
+    pub bytes_read: u64,
+
+    /// The total number of bytes written to the writer
+    pub bytes_written: u64,
+
+    pub phase: ForwarderPhase,
+}
+
+/// What happened during a single call to
+/// [`Forwarder::poll_progress`][crate::Forwarder::poll_progress]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ForwardProgress {
+    /// The number of bytes read during this poll
+    pub read: usize,
+
+    /// The number of bytes written during this poll
+    pub wrote: usize,
+
+    /// True if the reader has reached EOF, during this poll or earlier
+    pub reader_eof: bool,
+}
+
+/// What a [`Forwarder`][crate::Forwarder] was waiting on when its most
+/// recent poll returned `Pending`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WaitingOn {
+    /// Nothing: the forward hasn't been polled yet, is ready to make more
+    /// progress, or has finished
+    #[default]
+    Nothing,
+
+    /// The reader, for more data
+    Reader,
+
+    /// The writer, for room to write more data
+    Writer,
+
+    /// Both the reader and the writer
+    ReaderAndWriter,
+
+    /// A flush of the writer to finish
+    Flush,
+
+    /// The writer to finish closing
+    Close,
+
+    /// A timer, like a rate limit's
+    Timer,
+
+    /// The forward to be resumed with its
+    /// [`PauseHandle`][crate::PauseHandle]
+    Paused,
+}
+
+/// A snapshot of a [`Forwarder`][crate::Forwarder]'s progress, from
+/// [`Forwarder::stats`][crate::Forwarder::stats]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ForwardStats {
+    /// The total number of bytes read from the reader
+    pub bytes_read: u64,
+
+    /// The total number of bytes written to the writer
+    pub bytes_written: u64,
+
+    /// The number of bytes read but not yet written
+    pub buffered: usize,
+
+    /// The number of successful reads, not counting the one that reached
+    /// EOF
+    pub reads: u64,
+
+    /// The number of successful writes
+    pub writes: u64,
+
+    /// True if the reader is done, because it reached EOF or for some other
+    /// reason, like a cancellation
+    pub reader_eof: bool,
+
+    /// What the most recent poll left the forward waiting on
+    pub waiting_on: WaitingOn,
+}
@@ -0,0 +1,75 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use futures::task::AtomicWaker;
+
+#[derive(Debug, Default)]
+struct Shared {
+    stopped: AtomicBool,
+    waker: AtomicWaker,
+}
+
+/// Handle for gracefully stopping a forward from elsewhere, like a server's
+/// shutdown path. Created by [`Forwarder::with_stop_handle`][crate::Forwarder::with_stop_handle].
+/// Dropping the handle doesn't stop the forward.
+#[derive(Debug, Clone)]
+pub struct StopHandle {
+    shared: Arc<Shared>,
+}
+
+impl StopHandle {
+    /// Stop the forward. It stops reading new data, writes out everything
+    /// that's already buffered, flushes the writer, and then resolves with
+    /// the total number of bytes written.
+    pub fn stop(&self) {
+        self.shared.stopped.store(true, Ordering::Release);
+        self.shared.waker.wake();
+    }
+
+    /// Returns true if [`stop`][Self::stop] has been called
+    #[inline]
+    #[must_use]
+    pub fn is_stopped(&self) -> bool {
+        self.shared.stopped.load(Ordering::Acquire)
+    }
+}
+
+/// The cancel signal of a forwarder with a [`StopHandle`], which completes
+/// once the handle is used
+#[derive(Debug)]
+pub struct StopSignal {
+    shared: Arc<Shared>,
+}
+
+impl Future for StopSignal {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        // Register before checking, so that a stop in between isn't missed
+        self.shared.waker.register(cx.waker());
+
+        match self.shared.stopped.load(Ordering::Acquire) {
+            true => Poll::Ready(()),
+            false => Poll::Pending,
+        }
+    }
+}
+
+/// Create a connected stop handle and signal
+pub fn stop_pair() -> (StopHandle, StopSignal) {
+    let shared = Arc::new(Shared::default());
+
+    (
+        StopHandle {
+            shared: shared.clone(),
+        },
+        StopSignal { shared },
+    )
+}
@@ -0,0 +1,156 @@
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::{Buf, Bytes};
+use futures::{AsyncWrite, Stream};
+use pin_project::pin_project;
+
+use crate::ForwarderError;
+
+/// Future that forwards every chunk of a `Stream` of [`Bytes`] into a writer,
+/// then flushes the writer. Created by [`forward_stream`]. On success, the
+/// output is the total number of bytes written.
+///
+/// Because each [`Bytes`] is already contiguous, chunks are written directly
+/// out of the stream's own buffers rather than being copied through a ring.
+#[pin_project]
+#[derive(Debug)]
+pub struct ForwardStream<S, W> {
+    #[pin]
+    stream: Option<S>,
+
+    #[pin]
+    writer: W,
+
+    // The unwritten remainder of the most recent chunk
+    chunk: Bytes,
+
+    // The total number of bytes written so far
+    written: u64,
+}
+
+/// Forward every chunk of `stream` into `writer`, flushing the writer once
+/// the stream is exhausted.
+///
+/// To use a stream as the source of a full [`Forwarder`][crate::Forwarder],
+/// for its limits, callbacks, and the rest, turn it into a reader with
+/// [`TryStreamExt::into_async_read`][futures::TryStreamExt::into_async_read]
+/// instead. That copies each chunk into the forwarder's buffer, carrying
+/// chunks that are larger than the free space over to later reads.
+pub fn forward_stream<S, W>(stream: S, writer: W) -> ForwardStream<S, W>
+where
+    S: Stream<Item = Result<Bytes, io::Error>>,
+    W: AsyncWrite,
+{
+    ForwardStream {
+        stream: Some(stream),
+        writer,
+        chunk: Bytes::new(),
+        written: 0,
+    }
+}
+
+impl<S, W> Future for ForwardStream<S, W>
+where
+    S: Stream<Item = Result<Bytes, io::Error>>,
+    W: AsyncWrite,
+{
+    type Output = Result<u64, ForwarderError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        // Same approach as the `Forwarder`: attempt to pull one chunk, then
+        // attempt one write, and wake ourselves if more work is immediately
+        // possible.
+        let mut ready = false;
+
+        if this.chunk.is_empty() {
+            if let Some(stream) = this.stream.as_mut().as_pin_mut() {
+                match stream.poll_next(cx) {
+                    Poll::Pending => {}
+
+                    // The stream is done; after the last chunk is written,
+                    // we'll flush and finish
+                    Poll::Ready(None) => this.stream.set(None),
+
+                    // We got a new chunk. It might be empty, in which case
+                    // we need to make sure we get polled again, since the
+                    // stream didn't register a waker.
+                    Poll::Ready(Some(Ok(chunk))) => {
+                        *this.chunk = chunk;
+                        ready = true;
+                    }
+
+                    Poll::Ready(Some(Err(error))) => {
+                        return Poll::Ready(Err(ForwarderError::Read {
+                            error,
+                            bytes_read: *this.written,
+                            bytes_written: *this.written,
+                        }))
+                    }
+                }
+            }
+        }
+
+        if !this.chunk.is_empty() {
+            match this.writer.as_mut().poll_write(cx, this.chunk) {
+                Poll::Pending => {}
+
+                // `WouldBlock` doesn't register a waker, so make sure we're
+                // polled again to retry
+                Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::WouldBlock => {
+                    ready = true;
+                }
+
+                Poll::Ready(Ok(0)) => {
+                    return Poll::Ready(Err(ForwarderError::WriteClosedEarly {
+                        forwarded: *this.written,
+                        unforwarded: this.chunk.to_vec(),
+                    }))
+                }
+
+                Poll::Ready(Ok(n)) => {
+                    this.chunk.advance(n);
+                    *this.written += n as u64;
+                    ready = true;
+                }
+
+                Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::Interrupted => {
+                    ready = true;
+                }
+
+                Poll::Ready(Err(error)) => {
+                    return Poll::Ready(Err(ForwarderError::Write {
+                        error,
+                        bytes_read: *this.written + this.chunk.len() as u64,
+                        bytes_written: *this.written,
+                        unforwarded: this.chunk.to_vec(),
+                    }))
+                }
+            }
+        }
+
+        // Everything's been written; flush the writer, and we're done once
+        // that finishes.
+        if this.stream.is_none() && this.chunk.is_empty() {
+            let written = *this.written;
+
+            return this
+                .writer
+                .poll_flush(cx)
+                .map_ok(|()| written)
+                .map_err(ForwarderError::FlushFailed);
+        }
+
+        if ready {
+            cx.waker().wake_by_ref();
+        }
+
+        Poll::Pending
+    }
+}
@@ -0,0 +1,126 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures::future::FusedFuture;
+use pin_project::pin_project;
+
+use crate::{BufferStorage, Forwarder, ForwarderError};
+
+/// Future that runs a [`Forwarder`] and, on success, outputs a
+/// [`ForwardSummary`]. Created by [`Forwarder::summarize`].
+#[pin_project]
+pub struct Summarize<R, W, B, C> {
+    #[pin]
+    forwarder: Forwarder<R, W, B, C>,
+
+    // When we were first polled
+    started: Option<Instant>,
+
+    time_to_first_byte: Option<Duration>,
+}
+
+/// Timing and throughput for a completed forward, from
+/// [`Forwarder::summarize`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForwardSummary {
+    /// The total number of bytes read from the reader
+    pub bytes_read: u64,
+
+    /// The total number of bytes written to the writer
+    pub bytes_written: u64,
+
+    /// The number of successful reads, not counting the one that reached
+    /// EOF
+    pub reads: u64,
+
+    /// The number of successful writes
+    pub writes: u64,
+
+    /// The wall-clock time from when the forward was first polled until it
+    /// completed
+    pub duration: Duration,
+
+    /// The wall-clock time from when the forward was first polled until the
+    /// poll that first wrote some data, or `None` if nothing was written
+    pub time_to_first_byte: Option<Duration>,
+}
+
+impl ForwardSummary {
+    /// The average number of bytes written per second
+    #[must_use]
+    pub fn throughput(&self) -> f64 {
+        self.bytes_written as f64 / self.duration.as_secs_f64()
+    }
+}
+
+impl<R, W, B, C> Forwarder<R, W, B, C> {
+    /// Collect timing and throughput for the forward, so that the `.await`
+    /// produces a [`ForwardSummary`] instead of just the number of bytes
+    /// written. Times are measured from when the forward is first polled,
+    /// at the granularity of polls.
+    pub fn summarize(self) -> Summarize<R, W, B, C> {
+        Summarize {
+            forwarder: self,
+            started: None,
+            time_to_first_byte: None,
+        }
+    }
+}
+
+impl<R, W, B, C> Summarize<R, W, B, C> {
+    /// Get a reference to the underlying forwarder
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Forwarder<R, W, B, C> {
+        &self.forwarder
+    }
+}
+
+impl<R, W, B, C> Future for Summarize<R, W, B, C>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+{
+    type Output = Result<ForwardSummary, ForwarderError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+        let started = *this.started.get_or_insert_with(Instant::now);
+
+        let result = this.forwarder.as_mut().poll(cx);
+
+        let counters = this.forwarder.inner.counters;
+        if this.time_to_first_byte.is_none() && counters.bytes_written > 0 {
+            *this.time_to_first_byte = Some(started.elapsed());
+        }
+
+        let bytes_written = futures::ready!(result)?;
+
+        Poll::Ready(Ok(ForwardSummary {
+            bytes_read: counters.bytes_read,
+            bytes_written,
+            reads: counters.reads,
+            writes: counters.writes,
+            duration: started.elapsed(),
+            time_to_first_byte: *this.time_to_first_byte,
+        }))
+    }
+}
+
+impl<R, W, B, C> FusedFuture for Summarize<R, W, B, C>
+where
+    R: futures::AsyncRead,
+    W: futures::AsyncWrite,
+    B: BufferStorage,
+    C: Future<Output = ()>,
+{
+    fn is_terminated(&self) -> bool {
+        self.forwarder.is_terminated()
+    }
+}
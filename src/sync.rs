@@ -0,0 +1,57 @@
+use std::{
+    io::{self, IoSlice, IoSliceMut, Read, Write},
+    num::NonZeroUsize,
+};
+
+use crate::buffer::{BufferStorage, DuplexBuffer};
+
+/// Forward everything from `reader` into `writer` with blocking I/O, through
+/// the same ring buffer that [`Forwarder`][crate::Forwarder] uses. This is
+/// for thread-per-connection code that doesn't have an executor.
+///
+/// Each read is followed by writing out everything that's buffered, so data
+/// is never held back while we block waiting for the reader. `Interrupted`
+/// errors are retried. The writer isn't flushed at the end. Returns the
+/// total number of bytes written, or an `InvalidInput` error if the buffer
+/// is empty.
+pub fn forward_sync<R, W, B>(mut reader: R, mut writer: W, buffer: B) -> io::Result<u64>
+where
+    R: Read,
+    W: Write,
+    B: BufferStorage,
+{
+    let mut buffer = DuplexBuffer::new(buffer);
+    let mut written = 0;
+
+    if buffer.capacity() == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "can't forward through an empty buffer",
+        ));
+    }
+
+    loop {
+        let [b1, b2] = buffer.get_buffers().read;
+
+        match reader.read_vectored(&mut [IoSliceMut::new(b1), IoSliceMut::new(b2)]) {
+            Ok(0) => return Ok(written),
+            Ok(n) => buffer.advance_read(NonZeroUsize::new(n).unwrap()),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+
+        while buffer.write_ready() {
+            let [b1, b2] = buffer.get_buffers().write;
+
+            match writer.write_vectored(&[IoSlice::new(b1), IoSlice::new(b2)]) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => {
+                    buffer.advance_write(NonZeroUsize::new(n).unwrap());
+                    written += n as u64;
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
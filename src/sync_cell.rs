@@ -0,0 +1,24 @@
+use std::sync::{Mutex, PoisonError};
+
+/// A value that's only ever accessed through `&mut self`, in a wrapper
+/// that's `Sync` even if the value isn't. The mutex is never locked; it's
+/// only there so that things like user callbacks don't need to be `Sync`
+/// for the forwarder to be.
+pub struct SyncCell<T: ?Sized> {
+    inner: Mutex<T>,
+}
+
+impl<T> SyncCell<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: Mutex::new(value),
+        }
+    }
+}
+
+impl<T: ?Sized> SyncCell<T> {
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+}
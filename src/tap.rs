@@ -0,0 +1,197 @@
+use std::{
+    cmp::min,
+    io::{self, IoSlice},
+    num::NonZeroUsize,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{AsyncBufRead, AsyncRead, AsyncWrite};
+use pin_project::pin_project;
+
+use crate::{
+    buffer::{BufferStorage, DuplexBuffer},
+    vectored::{read_vectored_pair, write_vectored_buffers, DEFAULT_MAX_IOV},
+};
+
+/// A forward that lets the data be inspected before it's written. The tap is
+/// an [`AsyncBufRead`]: [`poll_fill_buf`][AsyncBufRead::poll_fill_buf]
+/// returns data that has been read from the reader but not yet inspected,
+/// and [`consume`][AsyncBufRead::consume] marks it as inspected, which
+/// releases it to be written to the writer. Data is never written until it's
+/// been consumed, so a proxy can sniff a protocol header before any of it
+/// reaches the other side.
+///
+/// Released data is written opportunistically whenever the tap is read
+/// from. Once you're done reading, call
+/// [`poll_finish`][ForwardTap::poll_finish] to write out whatever has been
+/// released and flush the writer.
+#[pin_project]
+pub struct ForwardTap<R, W, B> {
+    #[pin]
+    reader: Option<R>,
+
+    #[pin]
+    writer: W,
+
+    buffer: DuplexBuffer<B>,
+
+    // The number of bytes at the front of the buffer that have been
+    // consumed, and so can be written
+    released: usize,
+
+    max_iov: NonZeroUsize,
+}
+
+impl<R: AsyncRead, W: AsyncWrite, B: BufferStorage> ForwardTap<R, W, B> {
+    pub fn new(reader: R, writer: W, buffer: B) -> Self {
+        Self {
+            reader: Some(reader),
+            writer,
+            buffer: DuplexBuffer::new(buffer),
+            released: 0,
+            max_iov: DEFAULT_MAX_IOV,
+        }
+    }
+
+    /// Set the maximum number of slices passed to a single vectored write.
+    /// See [`Forwarder::with_max_iov`][crate::Forwarder::with_max_iov].
+    pub fn with_max_iov(self, max_iov: NonZeroUsize) -> Self {
+        Self { max_iov, ..self }
+    }
+
+    /// Write out everything that has been released with
+    /// [`consume`][AsyncBufRead::consume], then flush the writer. Data that
+    /// hasn't been consumed is left in the buffer.
+    pub fn poll_finish(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut this = self.project();
+
+        while *this.released > 0 {
+            futures::ready!(poll_write_released(
+                this.writer.as_mut(),
+                this.buffer,
+                this.released,
+                *this.max_iov,
+                cx
+            ))?;
+        }
+
+        this.writer.poll_flush(cx)
+    }
+}
+
+impl<R, W, B> ForwardTap<R, W, B> {
+    /// The number of bytes that have been consumed but not yet written
+    #[inline]
+    #[must_use]
+    pub fn released(&self) -> usize {
+        self.released
+    }
+}
+
+/// Attempt a single write of the released data. Returns `Pending` if the
+/// writer isn't ready, or if it returned `WouldBlock`, in which case the
+/// task is woken to try again.
+fn poll_write_released<W: AsyncWrite, B: BufferStorage>(
+    writer: Pin<&mut W>,
+    buffer: &mut DuplexBuffer<B>,
+    released: &mut usize,
+    max_iov: NonZeroUsize,
+    cx: &mut Context<'_>,
+) -> Poll<io::Result<()>> {
+    let [b1, b2] = buffer.get_buffers().write;
+    let b1 = &b1[..min(b1.len(), *released)];
+    let b2 = &b2[..min(b2.len(), *released - b1.len())];
+
+    match write_vectored_buffers(writer, cx, &[IoSlice::new(b1), IoSlice::new(b2)], max_iov) {
+        Poll::Pending => Poll::Pending,
+        // `WouldBlock` doesn't register a waker, so make sure we're polled
+        // again to retry
+        Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::WouldBlock => {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+        Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::Interrupted => Poll::Ready(Ok(())),
+        Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
+        Poll::Ready(Ok(n)) => match NonZeroUsize::new(n) {
+            None => Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+            Some(n) => {
+                buffer.advance_write(n);
+                *released -= n.get();
+                Poll::Ready(Ok(()))
+            }
+        },
+    }
+}
+
+impl<R: AsyncRead, W: AsyncWrite, B: BufferStorage> AsyncBufRead for ForwardTap<R, W, B> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let mut this = self.project();
+
+        // Make room in the buffer by writing out whatever's been released.
+        // If the writer isn't ready, that's fine, as long as there's
+        // something else for the caller to inspect.
+        let mut write_blocked = false;
+
+        while *this.released > 0 && !write_blocked {
+            match poll_write_released(
+                this.writer.as_mut(),
+                this.buffer,
+                this.released,
+                *this.max_iov,
+                cx,
+            ) {
+                Poll::Pending => write_blocked = true,
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+            }
+        }
+
+        // If everything in the buffer has already been inspected, read more
+        if this.buffer.buffered_len() == *this.released {
+            if let Some(reader) = this.reader.as_mut().as_pin_mut() {
+                let buffers = this.buffer.get_buffers();
+
+                // The buffer is full of released data that the writer isn't
+                // ready for yet; it'll wake us when it is
+                if buffers.readable_len() == 0 {
+                    return Poll::Pending;
+                }
+
+                match futures::ready!(read_vectored_pair(reader, cx, buffers.read))? {
+                    0 => this.reader.set(None),
+                    n => this.buffer.advance_read(NonZeroUsize::new(n).unwrap()),
+                }
+            }
+        }
+
+        // Skip past the data that's already been inspected
+        let released = *this.released;
+        let [b1, b2] = this.buffer.get_buffers().write;
+
+        Poll::Ready(Ok(match released.checked_sub(b1.len()) {
+            None => &b1[released..],
+            Some(offset) => &b2[offset..],
+        }))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.project();
+        *this.released += amt;
+        debug_assert!(*this.released <= this.buffer.buffered_len());
+    }
+}
+
+impl<R: AsyncRead, W: AsyncWrite, B: BufferStorage> AsyncRead for ForwardTap<R, W, B> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let data = futures::ready!(self.as_mut().poll_fill_buf(cx))?;
+        let n = min(data.len(), buf.len());
+        buf[..n].copy_from_slice(&data[..n]);
+        self.consume(n);
+        Poll::Ready(Ok(n))
+    }
+}
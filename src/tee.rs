@@ -0,0 +1,305 @@
+use std::{
+    future::Future,
+    io::{self, IoSlice},
+    num::NonZeroUsize,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{future::FusedFuture, AsyncRead, AsyncWrite};
+use pin_project::pin_project;
+
+use crate::{
+    buffer::{BufferStorage, DuplexBuffer},
+    vectored::{read_vectored_pair, write_vectored_buffers, DEFAULT_MAX_IOV},
+    ForwarderError, MAX_CYCLES_PER_POLL,
+};
+
+/// An error from a [`TeeForwarder`]
+#[derive(Debug)]
+pub struct TeeError {
+    /// The index of the writer that failed, or `None` if it was the reader
+    pub writer: Option<usize>,
+    pub source: ForwarderError,
+}
+
+struct Branch<W> {
+    writer: W,
+
+    // How far into the buffered data this writer has written. Data is only
+    // released from the ring once every writer is past it.
+    offset: usize,
+
+    written: u64,
+    flushed: bool,
+}
+
+/// Future that forwards everything from one reader into several writers,
+/// through a single shared ring buffer. Each chunk is written to every
+/// writer, and is only released from the buffer once the slowest writer has
+/// written it, so the reader is held back to the pace of the slowest writer
+/// rather than buffering without bound. Created by [`TeeForwarder::new`].
+///
+/// Once the reader reaches EOF and every writer has written everything,
+/// each writer is flushed. If any writer fails, the whole forward fails. On
+/// success, the output is the total number of bytes forwarded, which every
+/// writer received in full.
+#[pin_project]
+pub struct TeeForwarder<R, W, B> {
+    #[pin]
+    reader: Option<R>,
+
+    branches: Vec<Branch<W>>,
+    buffer: DuplexBuffer<B>,
+    bytes_read: u64,
+    max_iov: NonZeroUsize,
+    done: bool,
+}
+
+impl<R: AsyncRead, W: AsyncWrite + Unpin, B: BufferStorage> TeeForwarder<R, W, B> {
+    pub fn new(reader: R, writers: impl IntoIterator<Item = W>, buffer: B) -> Self {
+        Self {
+            reader: Some(reader),
+            branches: writers
+                .into_iter()
+                .map(|writer| Branch {
+                    writer,
+                    offset: 0,
+                    written: 0,
+                    flushed: false,
+                })
+                .collect(),
+            buffer: DuplexBuffer::new(buffer),
+            bytes_read: 0,
+            max_iov: DEFAULT_MAX_IOV,
+            done: false,
+        }
+    }
+
+    /// Set the maximum number of slices passed to a single vectored write.
+    /// See [`Forwarder::with_max_iov`][crate::Forwarder::with_max_iov].
+    pub fn with_max_iov(self, max_iov: NonZeroUsize) -> Self {
+        Self { max_iov, ..self }
+    }
+}
+
+impl<R, W, B> TeeForwarder<R, W, B> {
+    /// The number of bytes that have been read so far
+    #[inline]
+    #[must_use]
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// Iterate over the writers, in the order they were given
+    pub fn writers(&self) -> impl Iterator<Item = &W> {
+        self.branches.iter().map(|branch| &branch.writer)
+    }
+
+    /// Recover the writers, in the order they were given
+    pub fn into_writers(self) -> Vec<W> {
+        self.branches
+            .into_iter()
+            .map(|branch| branch.writer)
+            .collect()
+    }
+}
+
+/// Attempt a single write to `branch` of whatever it hasn't written yet.
+/// Returns true if it made progress.
+fn poll_write_branch<W: AsyncWrite + Unpin, B: BufferStorage>(
+    branch: &mut Branch<W>,
+    buffer: &mut DuplexBuffer<B>,
+    max_iov: NonZeroUsize,
+    cx: &mut Context<'_>,
+) -> Result<bool, ForwarderError> {
+    let [b1, b2] = buffer.get_buffers().write;
+    let (b1, b2) = match b1.get(branch.offset..) {
+        Some(b1) => (b1, b2),
+        None => (&b2[branch.offset - b1.len()..], &[][..]),
+    };
+
+    let slices = [IoSlice::new(b1), IoSlice::new(b2)];
+
+    match write_vectored_buffers(Pin::new(&mut branch.writer), cx, &slices, max_iov) {
+        Poll::Pending => Ok(false),
+        Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::WouldBlock => {
+            cx.waker().wake_by_ref();
+            Ok(false)
+        }
+        Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::Interrupted => Ok(true),
+        Poll::Ready(Err(error)) => Err(ForwarderError::Write {
+            error,
+            bytes_read: branch.written + (b1.len() + b2.len()) as u64,
+            bytes_written: branch.written,
+            unforwarded: [b1, b2].concat(),
+        }),
+        Poll::Ready(Ok(0)) => Err(ForwarderError::WriteClosedEarly {
+            forwarded: branch.written,
+            unforwarded: slices
+                .iter()
+                .flat_map(|slice| slice.iter().copied())
+                .collect(),
+        }),
+        Poll::Ready(Ok(n)) => {
+            branch.offset += n;
+            branch.written += n as u64;
+            Ok(true)
+        }
+    }
+}
+
+impl<R, W, B> Future for TeeForwarder<R, W, B>
+where
+    R: AsyncRead,
+    W: AsyncWrite + Unpin,
+    B: BufferStorage,
+{
+    type Output = Result<u64, TeeError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        assert!(!*this.done, "TeeForwarder polled after completion");
+
+        let mut cycles = 0;
+
+        loop {
+            // Don't hog the executor with a reader and writer that are
+            // always ready; come back for the rest after yielding
+            if cycles == MAX_CYCLES_PER_POLL {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            cycles += 1;
+
+            let mut progress = false;
+
+            if let Some(reader) = this.reader.as_mut().as_pin_mut() {
+                if this.buffer.read_ready() {
+                    let [b1, b2] = this.buffer.get_buffers().read;
+
+                    match read_vectored_pair(reader, cx, [b1, b2]) {
+                        Poll::Pending => {}
+                        Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::WouldBlock => {
+                            cx.waker().wake_by_ref();
+                        }
+                        Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::Interrupted => {
+                            progress = true;
+                        }
+                        Poll::Ready(Err(error)) => {
+                            *this.done = true;
+
+                            // Only count what every writer has written
+                            let bytes_written = this
+                                .branches
+                                .iter()
+                                .map(|branch| branch.written)
+                                .min()
+                                .unwrap_or(*this.bytes_read);
+
+                            return Poll::Ready(Err(TeeError {
+                                writer: None,
+                                source: ForwarderError::Read {
+                                    error,
+                                    bytes_read: *this.bytes_read,
+                                    bytes_written,
+                                },
+                            }));
+                        }
+                        Poll::Ready(Ok(n)) => {
+                            match NonZeroUsize::new(n) {
+                                None => this.reader.set(None),
+                                Some(n) => {
+                                    this.buffer.advance_read(n);
+                                    *this.bytes_read += n.get() as u64;
+                                }
+                            }
+                            progress = true;
+                        }
+                    }
+                }
+            }
+
+            let buffered = this.buffer.buffered_len();
+
+            for (index, branch) in this.branches.iter_mut().enumerate() {
+                if branch.offset < buffered {
+                    match poll_write_branch(branch, this.buffer, *this.max_iov, cx) {
+                        Ok(wrote) => progress |= wrote,
+                        Err(source) => {
+                            *this.done = true;
+                            return Poll::Ready(Err(TeeError {
+                                writer: Some(index),
+                                source,
+                            }));
+                        }
+                    }
+                }
+            }
+
+            // Release whatever every writer has written
+            let released = this
+                .branches
+                .iter()
+                .map(|branch| branch.offset)
+                .min()
+                .unwrap_or(buffered);
+
+            if let Some(released) = NonZeroUsize::new(released) {
+                this.buffer.advance_write(released);
+                this.branches
+                    .iter_mut()
+                    .for_each(|branch| branch.offset -= released.get());
+                progress = true;
+            }
+
+            if !progress {
+                break;
+            }
+        }
+
+        if this.reader.is_some() || this.buffer.write_ready() {
+            return Poll::Pending;
+        }
+
+        // Everything has been written everywhere; flush all the writers
+        let mut flushed = true;
+
+        for (index, branch) in this.branches.iter_mut().enumerate() {
+            if branch.flushed {
+                continue;
+            }
+
+            match Pin::new(&mut branch.writer).poll_flush(cx) {
+                Poll::Pending => flushed = false,
+                Poll::Ready(Ok(())) => branch.flushed = true,
+                Poll::Ready(Err(err)) => {
+                    *this.done = true;
+                    return Poll::Ready(Err(TeeError {
+                        writer: Some(index),
+                        source: ForwarderError::FlushFailed(err),
+                    }));
+                }
+            }
+        }
+
+        if !flushed {
+            return Poll::Pending;
+        }
+
+        *this.done = true;
+        Poll::Ready(Ok(*this.bytes_read))
+    }
+}
+
+impl<R, W, B> FusedFuture for TeeForwarder<R, W, B>
+where
+    R: AsyncRead,
+    W: AsyncWrite + Unpin,
+    B: BufferStorage,
+{
+    fn is_terminated(&self) -> bool {
+        self.done
+    }
+}
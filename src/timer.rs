@@ -0,0 +1,108 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures_timer::Delay;
+
+use crate::callback::Callback;
+
+/// A timer that expires once a certain amount of time has passed without any
+/// activity. Rather than resetting the underlying timer on every bit of
+/// activity, we just record when it happened, and re-arm the timer lazily
+/// when it fires early. The clock doesn't start until the timer is first
+/// polled, so time spent before the forward starts doesn't count.
+#[derive(Debug)]
+pub struct ActivityTimer {
+    duration: Duration,
+    last_activity: Option<Instant>,
+    delay: Option<Delay>,
+}
+
+impl ActivityTimer {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            last_activity: None,
+            delay: None,
+        }
+    }
+
+    /// Record that some activity happened just now
+    #[inline]
+    pub fn touch(&mut self) {
+        self.last_activity = Some(Instant::now());
+    }
+
+    /// Resolves once `duration` has passed since the most recent activity,
+    /// returning how long it's actually been.
+    pub fn poll_expired(&mut self, cx: &mut Context<'_>) -> Poll<Duration> {
+        let last_activity = *self.last_activity.get_or_insert_with(Instant::now);
+        let duration = self.duration;
+
+        let delay = self
+            .delay
+            .get_or_insert_with(|| Delay::new(duration.saturating_sub(last_activity.elapsed())));
+
+        loop {
+            futures::ready!(Pin::new(&mut *delay).poll(cx));
+
+            let elapsed = last_activity.elapsed();
+            if elapsed >= duration {
+                return Poll::Ready(elapsed);
+            }
+
+            delay.reset(duration - elapsed);
+        }
+    }
+}
+
+/// Calls a user callback when the buffer has been full for a while without
+/// any data being written out of it.
+pub struct StallWarning {
+    // Fires after each interval of the stall, to trigger a warning
+    timer: ActivityTimer,
+
+    // When the current stall started, once it's been warned about. The
+    // timer is re-armed after each warning, so it can't tell us this.
+    stall_start: Option<Instant>,
+
+    callback: Callback<dyn FnMut(usize, Duration) + Send>,
+}
+
+impl StallWarning {
+    pub fn new(duration: Duration, callback: impl FnMut(usize, Duration) + Send + 'static) -> Self {
+        Self {
+            timer: ActivityTimer::new(duration),
+            stall_start: None,
+            callback: Callback::new(Box::new(callback)),
+        }
+    }
+
+    /// Record that a write succeeded, which ends any stall
+    #[inline]
+    pub fn touch(&mut self) {
+        self.timer.touch();
+        self.stall_start = None;
+    }
+
+    /// If we've been stalled for long enough, call the callback with the
+    /// number of buffered bytes and the stall time so far. The timer is then
+    /// re-armed, so a continuing stall is reported again after another
+    /// interval, with the total stall time.
+    pub fn poll_warn(&mut self, cx: &mut Context<'_>, buffered: usize) {
+        if let Poll::Ready(elapsed) = self.timer.poll_expired(cx) {
+            let stall_start = *self
+                .stall_start
+                .get_or_insert_with(|| Instant::now() - elapsed);
+
+            (self.callback.get_mut())(buffered, stall_start.elapsed());
+            self.timer.touch();
+
+            // Re-arm the timer, so that we get woken for the next warning
+            let _ = self.timer.poll_expired(cx);
+        }
+    }
+}
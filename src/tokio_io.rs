@@ -0,0 +1,97 @@
+use std::{
+    io::{self, IoSlice},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use pin_project::pin_project;
+use tokio::io::ReadBuf;
+
+use crate::{BufferStorage, Forwarder};
+
+/// Adapts a stream that implements tokio's `AsyncRead` and `AsyncWrite` to
+/// the `futures` traits that the [`Forwarder`] uses, so that tokio sockets
+/// can be forwarded directly. Closing the adapter shuts down the stream.
+#[pin_project]
+#[derive(Debug)]
+pub struct TokioIo<T> {
+    #[pin]
+    inner: T,
+}
+
+impl<T> TokioIo<T> {
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: tokio::io::AsyncRead> futures::AsyncRead for TokioIo<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        // The ring's memory is always initialized, so the read buffer can be
+        // handed over as-is
+        let mut buf = ReadBuf::new(buf);
+        futures::ready!(self.project().inner.poll_read(cx, &mut buf))?;
+        Poll::Ready(Ok(buf.filled().len()))
+    }
+}
+
+impl<T: tokio::io::AsyncWrite> futures::AsyncWrite for TokioIo<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_write_vectored(cx, bufs)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_shutdown(cx)
+    }
+}
+
+impl<R, W, B> Forwarder<TokioIo<R>, TokioIo<W>, B>
+where
+    R: tokio::io::AsyncRead,
+    W: tokio::io::AsyncWrite,
+    B: BufferStorage,
+{
+    /// Create a new `Forwarder` between streams that implement tokio's I/O
+    /// traits, rather than the `futures` ones.
+    pub fn from_tokio(reader: R, writer: W, buffer: B) -> Self {
+        Self::new(TokioIo::new(reader), TokioIo::new(writer), buffer)
+    }
+}
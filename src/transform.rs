@@ -0,0 +1,211 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::AsyncWrite;
+use pin_project::pin_project;
+
+use crate::{BufferStorage, Forwarder};
+
+/// Modifies data in flight, between the reader and the writer of a
+/// [`Forwarder`], for things like compression, encryption, escaping, or
+/// re-framing. The output can be a different length than the input. See
+/// [`Forwarder::with_transform`].
+pub trait Transform {
+    /// Transform some of `input` into `output`, and return how many bytes
+    /// of input were consumed and how many bytes of output were produced.
+    /// Input that isn't consumed is offered again on the next call, along
+    /// with any data that follows it. Consuming nothing while producing
+    /// nothing, with room in `output`, is an error.
+    fn poll_transform(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Poll<io::Result<(usize, usize)>>;
+
+    /// There's no more input; produce any remaining output, like a trailer,
+    /// into `output`, and return how many bytes were produced. This is
+    /// called until it returns `Ok(0)`. By default, there's nothing to
+    /// produce.
+    fn poll_finish(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        output: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let _ = (cx, output);
+        Poll::Ready(Ok(0))
+    }
+}
+
+/// A writer that passes everything written to it through a [`Transform`]
+/// before writing it to the inner writer. The transform's output is staged
+/// in a buffer of its own. Closing the writer finishes the transform,
+/// writes out the rest of its output, then closes the inner writer.
+#[pin_project]
+#[derive(Debug)]
+pub struct TransformWriter<W, T> {
+    #[pin]
+    writer: W,
+
+    #[pin]
+    transform: T,
+
+    // Output from the transform that's waiting to be written, in
+    // `staged[start..end]`
+    staged: Box<[u8]>,
+    start: usize,
+    end: usize,
+
+    // True once the transform has produced all of its final output
+    finished: bool,
+}
+
+impl<W, T> TransformWriter<W, T> {
+    /// Create a new `TransformWriter`, staging up to `staging` bytes of the
+    /// transform's output at a time
+    ///
+    /// # Panics
+    ///
+    /// Panics if `staging` is 0.
+    pub fn new(writer: W, transform: T, staging: usize) -> Self {
+        assert!(staging > 0, "transform staging buffer can't be empty");
+
+        Self {
+            writer,
+            transform,
+            staged: vec![0; staging].into_boxed_slice(),
+            start: 0,
+            end: 0,
+            finished: false,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn transform(&self) -> &T {
+        &self.transform
+    }
+
+    /// Get the inner writer and the transform back. Any staged output is
+    /// lost.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> (W, T) {
+        (self.writer, self.transform)
+    }
+}
+
+impl<W: AsyncWrite, T: Transform> TransformWriter<W, T> {
+    /// Write staged output until there's none left
+    fn poll_drain(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut this = self.project();
+
+        while *this.start < *this.end {
+            let staged = &this.staged[*this.start..*this.end];
+
+            match futures::ready!(this.writer.as_mut().poll_write(cx, staged))? {
+                0 => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                n => *this.start += n,
+            }
+        }
+
+        *this.start = 0;
+        *this.end = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite, T: Transform> AsyncWrite for TransformWriter<W, T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            // Make room for more output. If the writer isn't ready, we can
+            // still transform into whatever room there is.
+            let drained = self.as_mut().poll_drain(cx)?;
+
+            let this = self.as_mut().project();
+            let output = &mut this.staged[*this.end..];
+
+            if output.is_empty() {
+                debug_assert!(drained.is_pending());
+                return Poll::Pending;
+            }
+
+            let (consumed, produced) =
+                futures::ready!(this.transform.poll_transform(cx, buf, output))?;
+            *this.end += produced;
+
+            if consumed > 0 || buf.is_empty() {
+                return Poll::Ready(Ok(consumed));
+            }
+
+            if produced == 0 {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "transform made no progress",
+                )));
+            }
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        futures::ready!(self.as_mut().poll_drain(cx))?;
+        self.project().writer.poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.finished {
+            futures::ready!(self.as_mut().poll_drain(cx))?;
+
+            let this = self.as_mut().project();
+            let produced = futures::ready!(this.transform.poll_finish(cx, this.staged))?;
+
+            *this.end = produced;
+            *this.finished = produced == 0;
+        }
+
+        futures::ready!(self.as_mut().poll_drain(cx))?;
+        self.project().writer.poll_close(cx)
+    }
+}
+
+impl<R, W, T, B> Forwarder<R, TransformWriter<W, T>, B>
+where
+    R: futures::AsyncRead,
+    W: AsyncWrite,
+    T: Transform,
+    B: BufferStorage,
+{
+    /// Create a new `Forwarder` that passes the data through `transform` on
+    /// its way from `reader` to `writer`. The forwarder's buffer still does
+    /// the reading; the transform's output is staged in a separate buffer of
+    /// `staging` bytes. Staged output is written out when the writer is
+    /// flushed, so this enables [`flush_on_eof`][Self::flush_on_eof]. The
+    /// transform is finished when the writer is closed, so this also enables
+    /// [`half_close_on_eof`][Self::half_close_on_eof]; turning that off
+    /// means any final output, like a trailer, is never produced.
+    ///
+    /// Byte counts, like the forwarder's output, count bytes going into the
+    /// transform, not coming out of it.
+    pub fn with_transform(reader: R, writer: W, transform: T, buffer: B, staging: usize) -> Self {
+        Self::new(
+            reader,
+            TransformWriter::new(writer, transform, staging),
+            buffer,
+        )
+        .flush_on_eof(true)
+        .half_close_on_eof(true)
+    }
+}
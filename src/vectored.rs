@@ -0,0 +1,120 @@
+use std::{
+    io::{self, IoSlice, IoSliceMut},
+    num::NonZeroUsize,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{AsyncRead, AsyncWrite};
+
+use crate::buffer::IoSlices;
+
+/// The default maximum number of slices we'll pass to a single vectored
+/// write. This matches the common `IOV_MAX` of 1024.
+pub const DEFAULT_MAX_IOV: NonZeroUsize = match NonZeroUsize::new(1024) {
+    Some(n) => n,
+    None => unreachable!(),
+};
+
+/// Write a list of slices to a writer with vectored writes, passing at most
+/// `max_iov` slices to each individual write. Chunks are written in order
+/// and we keep going until a write is partial, pending, or fails, returning
+/// the total number of bytes that were written.
+///
+/// If some data was written before a `Pending` or an error, that's reported
+/// as a successful write of that much data; the caller will retry the rest
+/// (and encounter the error again, if it's persistent) on a later write.
+pub fn write_vectored_buffers<W: AsyncWrite + ?Sized>(
+    mut writer: Pin<&mut W>,
+    cx: &mut Context<'_>,
+    slices: &[IoSlice<'_>],
+    max_iov: NonZeroUsize,
+) -> Poll<io::Result<usize>> {
+    let mut written = 0;
+
+    for chunk in slices.chunks(max_iov.get()) {
+        let chunk_len: usize = chunk.iter().map(|slice| slice.len()).sum();
+
+        if chunk_len == 0 {
+            continue;
+        }
+
+        match writer.as_mut().poll_write_vectored(cx, chunk) {
+            Poll::Ready(Ok(n)) => {
+                written += n;
+
+                // A partial (or zero) write means the writer can't accept
+                // any more right now; don't try the next chunk.
+                if n < chunk_len {
+                    break;
+                }
+            }
+
+            // We made some progress before hitting the error or the pending
+            // state; report the progress. The waker registered by the pending
+            // write is harmless; at worst it causes a spurious wakeup.
+            Poll::Ready(Err(_)) | Poll::Pending if written > 0 => break,
+
+            Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+            Poll::Pending => return Poll::Pending,
+        }
+    }
+
+    Poll::Ready(Ok(written))
+}
+
+/// Like [`write_vectored_buffers`], but if there's a `limit`, only offer the
+/// writer the first `limit` bytes of `slices`. There can be at most as many
+/// slices as an [`IoSlices`] can hold.
+pub fn write_vectored_limited<W: AsyncWrite + ?Sized>(
+    writer: Pin<&mut W>,
+    cx: &mut Context<'_>,
+    slices: &[IoSlice<'_>],
+    max_iov: NonZeroUsize,
+    limit: Option<usize>,
+) -> Poll<io::Result<usize>> {
+    let Some(mut remaining) = limit else {
+        return write_vectored_buffers(writer, cx, slices, max_iov);
+    };
+
+    let limited = IoSlices::collect(
+        slices
+            .iter()
+            .map(|slice| {
+                let n = slice.len().min(remaining);
+                remaining -= n;
+                IoSlice::new(&slice[..n])
+            })
+            .filter(|slice| !slice.is_empty()),
+        || IoSlice::new(&[]),
+    );
+
+    write_vectored_buffers(writer, cx, &limited, max_iov)
+}
+
+/// Read into a pair of slices with a vectored read. Readers that don't
+/// implement vectored reads only fill the first slice, so if the first slice
+/// was filled exactly, we follow up with a plain read into the second.
+///
+/// If the follow-up read doesn't succeed, the first read is still reported
+/// as a success; the reader will be tried again (and report its error again,
+/// if it's persistent) on a later read.
+pub fn read_vectored_pair<R: AsyncRead + ?Sized>(
+    mut reader: Pin<&mut R>,
+    cx: &mut Context<'_>,
+    [first, second]: [&mut [u8]; 2],
+) -> Poll<io::Result<usize>> {
+    let first_len = first.len();
+    let mut slices = [IoSliceMut::new(first), IoSliceMut::new(second)];
+
+    let n = futures::ready!(reader.as_mut().poll_read_vectored(cx, &mut slices))?;
+
+    if n == 0 || n != first_len || slices[1].is_empty() {
+        return Poll::Ready(Ok(n));
+    }
+
+    match reader.poll_read(cx, &mut slices[1]) {
+        Poll::Ready(Ok(m)) => Poll::Ready(Ok(n + m)),
+        Poll::Ready(Err(_)) | Poll::Pending => Poll::Ready(Ok(n)),
+    }
+}
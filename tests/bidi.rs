@@ -0,0 +1,192 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_forward::{
+    copy_bidirectional, BidiForwarder, Direction, ForwarderError, OnDirectionError,
+};
+use futures::{executor::block_on, AsyncRead, AsyncWrite};
+
+/// One end of a connection: reads come from `input`, and writes go to
+/// `output`. Reads either fail with `error`, or stall forever if `stall` is
+/// set, once the input is exhausted.
+#[derive(Default)]
+struct Endpoint {
+    input: Vec<u8>,
+    output: Vec<u8>,
+    closed: bool,
+    error: bool,
+    stall: bool,
+}
+
+impl Endpoint {
+    fn new(input: &[u8]) -> Self {
+        Self {
+            input: input.to_vec(),
+            ..Self::default()
+        }
+    }
+}
+
+impl AsyncRead for Endpoint {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        if this.input.is_empty() {
+            if this.error {
+                return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+            }
+            if this.stall {
+                return Poll::Pending;
+            }
+        }
+
+        let n = buf.len().min(this.input.len());
+        buf[..n].copy_from_slice(&this.input[..n]);
+        this.input.drain(..n);
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl AsyncWrite for Endpoint {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut().output.extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().closed = true;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[test]
+fn forward_both_directions() {
+    let request = b"GET /".to_vec();
+    let response: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+
+    let mut client = Endpoint::new(&request);
+    let mut server = Endpoint::new(&response);
+
+    // A small buffer for the requests, and a bigger one for the responses
+    let (sent, received) = block_on(copy_bidirectional(
+        &mut client,
+        &mut server,
+        [0; 4],
+        vec![0; 256],
+    ))
+    .unwrap();
+
+    assert_eq!(sent, 5);
+    assert_eq!(received, 1000);
+    assert_eq!(server.output, request);
+    assert_eq!(client.output, response);
+    assert!(client.closed);
+    assert!(server.closed);
+}
+
+#[test]
+fn abort_on_error() {
+    let mut client = Endpoint {
+        error: true,
+        ..Endpoint::default()
+    };
+
+    // The server never finishes, so this only completes because the other
+    // direction is aborted
+    let mut server = Endpoint {
+        stall: true,
+        ..Endpoint::default()
+    };
+
+    let copy: BidiForwarder<_, _, [u8; 16]> =
+        copy_bidirectional(&mut client, &mut server, [0; 16], [0; 16]);
+    let error = block_on(copy).unwrap_err();
+
+    assert_eq!(error.direction, Direction::AToB);
+    assert!(matches!(error.source, ForwarderError::Read { .. }));
+    assert!(client.closed);
+    assert!(server.closed);
+}
+
+#[test]
+fn drain_on_error() {
+    let mut client = Endpoint {
+        error: true,
+        ..Endpoint::default()
+    };
+    let mut server = Endpoint::new(b"response");
+
+    let error = block_on(
+        copy_bidirectional(&mut client, &mut server, [0; 16], [0; 4])
+            .on_error(OnDirectionError::Drain),
+    )
+    .unwrap_err();
+
+    // The failure was reported, but only after the other direction finished
+    assert_eq!(error.direction, Direction::AToB);
+    assert_eq!(error.b_to_a, 8);
+    assert_eq!(client.output, b"response");
+    assert!(client.closed);
+    assert!(server.closed);
+}
+
+#[test]
+fn full_close_on_first_eof() {
+    let mut client = Endpoint::new(b"request");
+
+    // The server never finishes, so this only completes because the first
+    // EOF stops the other direction
+    let mut server = Endpoint {
+        stall: true,
+        ..Endpoint::default()
+    };
+
+    let (sent, received) =
+        block_on(copy_bidirectional(&mut client, &mut server, [0; 16], [0; 16]).half_close(false))
+            .unwrap();
+
+    assert_eq!((sent, received), (7, 0));
+    assert_eq!(server.output, b"request");
+    assert!(client.closed);
+    assert!(server.closed);
+}
+
+#[test]
+#[cfg(feature = "futures-timer")]
+fn linger_after_first_eof() {
+    let mut client = Endpoint::new(b"request");
+    let mut server = Endpoint {
+        input: b"partial".to_vec(),
+        stall: true,
+        ..Endpoint::default()
+    };
+
+    let start = std::time::Instant::now();
+    let (sent, received) = block_on(
+        copy_bidirectional(&mut client, &mut server, [0; 16], [0; 16])
+            .linger(std::time::Duration::from_millis(20)),
+    )
+    .unwrap();
+
+    // The server's direction kept going until the linger time ran out
+    assert!(start.elapsed() >= std::time::Duration::from_millis(20));
+    assert_eq!((sent, received), (7, 7));
+    assert_eq!(client.output, b"partial");
+    assert!(client.closed);
+    assert!(server.closed);
+}
@@ -0,0 +1,92 @@
+use std::io::{self, Cursor, Read, Write};
+
+use async_forward::forward_sync;
+
+fn test_data(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+/// A reader that fails with `Interrupted` before every successful read, and
+/// never reads more than `chunk` bytes at once
+struct InterruptingReader {
+    inner: Cursor<Vec<u8>>,
+    chunk: usize,
+    interrupt: bool,
+}
+
+impl Read for InterruptingReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.interrupt = !self.interrupt;
+
+        match self.interrupt {
+            true => Err(io::ErrorKind::Interrupted.into()),
+            false => {
+                let len = buf.len().min(self.chunk);
+                self.inner.read(&mut buf[..len])
+            }
+        }
+    }
+}
+
+/// A writer that accepts at most `chunk` bytes per write
+struct ChunkedWriter {
+    data: Vec<u8>,
+    chunk: usize,
+}
+
+impl Write for ChunkedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.chunk);
+        self.data.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn forward_everything() {
+    let data = test_data(10_000);
+    let mut writer = Vec::new();
+
+    let written = forward_sync(Cursor::new(data.clone()), &mut writer, [0; 64]).unwrap();
+
+    assert_eq!(written, 10_000);
+    assert_eq!(writer, data);
+}
+
+#[test]
+fn forward_with_interrupts_and_partial_writes() {
+    let data = test_data(10_000);
+    let reader = InterruptingReader {
+        inner: Cursor::new(data.clone()),
+        chunk: 100,
+        interrupt: false,
+    };
+    let mut writer = ChunkedWriter {
+        data: Vec::new(),
+        chunk: 7,
+    };
+
+    let written = forward_sync(reader, &mut writer, vec![0; 64]).unwrap();
+
+    assert_eq!(written, 10_000);
+    assert_eq!(writer.data, data);
+}
+
+#[test]
+fn write_zero() {
+    let mut buffer = [0; 4];
+    let err = forward_sync(Cursor::new(test_data(10)), &mut buffer[..], [0; 8]).unwrap_err();
+
+    assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+}
+
+#[test]
+fn empty_buffer() {
+    let err = forward_sync(Cursor::new(test_data(10)), Vec::new(), [0; 0]).unwrap_err();
+
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "http-body")]
+
+use std::{
+    collections::VecDeque,
+    convert::Infallible,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use async_forward::{BodyReader, Forwarder};
+use bytes::Bytes;
+use futures::executor::block_on;
+use http::{HeaderMap, HeaderValue};
+use http_body::{Body, Frame};
+
+/// A body made of a fixed list of frames
+struct Frames(VecDeque<Frame<Bytes>>);
+
+impl Body for Frames {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+        Poll::Ready(self.0.pop_front().map(Ok))
+    }
+}
+
+#[test]
+fn forward_body_with_trailers() {
+    let mut trailers = HeaderMap::new();
+    trailers.insert("checksum", HeaderValue::from_static("abc"));
+
+    // Frames larger than the buffer have to be split across reads
+    let body = Frames(VecDeque::from([
+        Frame::data(Bytes::from(vec![1; 100])),
+        Frame::data(Bytes::new()),
+        Frame::data(Bytes::from(vec![2; 50])),
+        Frame::trailers(trailers.clone()),
+    ]));
+
+    let seen = Arc::new(Mutex::new(None));
+    let reader = BodyReader::new(body).on_trailers({
+        let seen = seen.clone();
+        move |trailers| *seen.lock().unwrap() = Some(trailers)
+    });
+
+    let mut writer = Vec::new();
+    let written = block_on(Forwarder::new(reader, &mut writer, [0; 32])).unwrap();
+
+    assert_eq!(written, 150);
+    assert_eq!(writer, [vec![1; 100], vec![2; 50]].concat());
+    assert_eq!(seen.lock().unwrap().take(), Some(trailers));
+}
+
+#[test]
+fn forward_body() {
+    let body = Frames(VecDeque::from([Frame::data(Bytes::from_static(b"hello"))]));
+
+    let mut writer = Vec::new();
+    block_on(Forwarder::from_body(body, &mut writer, [0; 32])).unwrap();
+    assert_eq!(writer, b"hello");
+}
@@ -0,0 +1,127 @@
+use std::{
+    io,
+    num::NonZeroUsize,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_forward::{BuildError, ForwardExt, ForwarderBuilder};
+use futures::{executor::block_on, AsyncWrite};
+
+/// A writer that records the size of each write, and how many times it was
+/// flushed
+#[derive(Default)]
+struct RecordingWriter {
+    data: Vec<u8>,
+    writes: Vec<usize>,
+    flushes: usize,
+}
+
+impl AsyncWrite for RecordingWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.data.extend_from_slice(buf);
+        self.writes.push(buf.len());
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.flushes += 1;
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[test]
+fn build_and_forward() {
+    let builder = ForwarderBuilder::new()
+        .read_aggregation(4)
+        .eager_flush(true);
+    let mut writer = Vec::new();
+
+    let forwarder = builder
+        .build(&b"hello, world"[..], &mut writer, [0; 8])
+        .unwrap();
+    block_on(forwarder).unwrap();
+
+    assert_eq!(writer, b"hello, world");
+}
+
+#[test]
+fn reject_empty_buffer() {
+    let result = ForwarderBuilder::new().build(&b""[..], Vec::new(), [0; 0]);
+
+    assert!(matches!(result, Err(BuildError::EmptyBuffer)));
+}
+
+#[test]
+fn reject_unreachable_read_aggregation() {
+    let result = ForwarderBuilder::new()
+        .read_aggregation(100)
+        .build(&b""[..], Vec::new(), [0; 64]);
+
+    assert!(matches!(
+        result,
+        Err(BuildError::ReadAggregationTooLarge {
+            min_read: 100,
+            capacity: 64
+        })
+    ));
+}
+
+#[test]
+fn forward_to_with_builder() {
+    let builder = ForwarderBuilder::new().buffer_size(4).eager_flush(true);
+    let mut writer = RecordingWriter::default();
+
+    let forwarder = (&b"hello, world"[..])
+        .forward_to_with(&mut writer, &builder)
+        .unwrap();
+    block_on(forwarder).unwrap();
+
+    assert_eq!(writer.data, b"hello, world");
+    assert!(writer.writes.iter().all(|&len| len <= 4));
+}
+
+#[test]
+fn start_many_from_template() {
+    let template = ForwarderBuilder::new().buffer_size(4).read_aggregation(2);
+
+    for input in [&b"hello"[..], b"goodbye, world", b""] {
+        let mut writer = Vec::new();
+
+        block_on(template.start(input, &mut writer).unwrap()).unwrap();
+        assert_eq!(writer, input);
+    }
+
+    assert!(matches!(
+        template.buffer_size(0).start(&b""[..], Vec::new()),
+        Err(BuildError::EmptyBuffer)
+    ));
+}
+
+#[test]
+fn limit_chunk_and_flush() {
+    let builder = ForwarderBuilder::new()
+        .read_limit(10)
+        .max_write_size(NonZeroUsize::new(3).unwrap())
+        .flush_on_eof(true);
+
+    let mut reader = &b"hello, world!"[..];
+    let mut writer = RecordingWriter::default();
+
+    let written = block_on(builder.build(&mut reader, &mut writer, [0; 8]).unwrap()).unwrap();
+
+    // Nothing past the limit was read, and every write was capped
+    assert_eq!(written, 10);
+    assert_eq!(reader, b"ld!");
+    assert_eq!(writer.data, b"hello, wor");
+    assert!(writer.writes.iter().all(|&len| len <= 3));
+    assert_eq!(writer.flushes, 1);
+}
@@ -0,0 +1,23 @@
+use async_forward::forward_chain;
+use futures::executor::block_on;
+
+#[test]
+fn chain_readers() {
+    let parts: [&[u8]; 4] = [b"hello", b"", b", ", b"world, this is a longer part"];
+    let mut writer = Vec::new();
+
+    // The buffer is smaller than some of the parts, so data is buffered
+    // across the reader transitions
+    block_on(forward_chain(parts, &mut writer, [0; 8])).unwrap();
+
+    assert_eq!(writer, b"hello, world, this is a longer part");
+}
+
+#[test]
+fn chain_no_readers() {
+    let mut writer = Vec::new();
+
+    block_on(forward_chain(Vec::<&[u8]>::new(), &mut writer, [0; 8])).unwrap();
+
+    assert!(writer.is_empty());
+}
@@ -0,0 +1,38 @@
+#![cfg(feature = "gzip")]
+
+use async_forward::{
+    async_compression::futures::write::{GzipDecoder, GzipEncoder},
+    Codec, Forwarder,
+};
+use futures::{executor::block_on, io::Cursor};
+
+#[test]
+fn gzip_round_trip() {
+    let data: Vec<u8> = b"the quick brown fox jumps over the lazy dog\n".repeat(200);
+
+    let mut compressed = Vec::new();
+    let read = block_on(Forwarder::with_transform(
+        Cursor::new(data.clone()),
+        &mut compressed,
+        Codec::new(GzipEncoder::new(Vec::new())),
+        [0; 64],
+        32,
+    ))
+    .unwrap();
+
+    assert_eq!(read, data.len() as u64);
+    assert!(compressed.len() < data.len());
+    assert_eq!(&compressed[..2], b"\x1f\x8b");
+
+    let mut decompressed = Vec::new();
+    block_on(Forwarder::with_transform(
+        Cursor::new(compressed),
+        &mut decompressed,
+        Codec::new(GzipDecoder::new(Vec::new())),
+        [0; 64],
+        32,
+    ))
+    .unwrap();
+
+    assert_eq!(decompressed, data);
+}
@@ -0,0 +1,110 @@
+use std::{
+    collections::VecDeque,
+    io,
+    num::NonZeroU64,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_forward::{AsyncRecv, AsyncSend, DatagramForwarder, ForwarderError};
+use futures::{executor::block_on, Future};
+
+/// A queue of messages, that can be received from and sent to
+#[derive(Default)]
+struct Messages {
+    queue: VecDeque<Vec<u8>>,
+
+    // If set, sends only send this many bytes
+    max_send: Option<usize>,
+}
+
+impl AsyncRecv for Messages {
+    fn poll_recv(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let message = self.queue.pop_front().expect("no more messages");
+        buf[..message.len()].copy_from_slice(&message);
+        Poll::Ready(Ok(message.len()))
+    }
+}
+
+impl AsyncSend for Messages {
+    fn poll_send(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let len = buf.len().min(self.max_send.unwrap_or(usize::MAX));
+        self.queue.push_back(buf[..len].to_vec());
+        Poll::Ready(Ok(len))
+    }
+}
+
+#[test]
+fn forward_whole_messages() {
+    let messages: Vec<Vec<u8>> = vec![b"hello".to_vec(), Vec::new(), vec![7; 100], b"bye".to_vec()];
+
+    let mut source = Messages {
+        queue: messages.iter().cloned().collect(),
+        max_send: None,
+    };
+    let mut destination = Messages::default();
+
+    let forward = DatagramForwarder::new(&mut source, &mut destination, [0; 128])
+        .with_max_messages(NonZeroU64::new(4).unwrap());
+
+    assert_eq!(block_on(forward).unwrap(), 4);
+
+    // Even the empty message arrives, as its own message
+    assert_eq!(destination.queue, messages);
+}
+
+#[test]
+fn partial_send_fails() {
+    let mut source = Messages {
+        queue: [vec![1; 10]].into(),
+        max_send: None,
+    };
+    let mut destination = Messages {
+        queue: VecDeque::new(),
+        max_send: Some(5),
+    };
+
+    let err = block_on(DatagramForwarder::new(
+        &mut source,
+        &mut destination,
+        [0; 128],
+    ))
+    .unwrap_err();
+    assert!(matches!(err, ForwarderError::Write { .. }));
+}
+
+/// A source that always has another message
+struct Endless;
+
+impl AsyncRecv for Endless {
+    fn poll_recv(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        buf[0] = 7;
+        Poll::Ready(Ok(1))
+    }
+}
+
+#[test]
+fn datagram_flood_yields_to_executor() {
+    let mut destination = Messages::default();
+    let mut forward = DatagramForwarder::new(Endless, &mut destination, [0; 16]);
+
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    for _ in 0..3 {
+        assert!(Pin::new(&mut forward).poll(&mut cx).is_pending());
+    }
+    assert!(forward.messages() > 0);
+}
@@ -0,0 +1,94 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_forward::{Destination, Endpoint, Forwarder, Source};
+use futures::{executor::block_on, io::Cursor};
+
+/// A source that counts up from 0, like a generator in shared memory
+struct Counter {
+    next: u8,
+    remaining: usize,
+}
+
+impl Source for Counter {
+    fn poll_fill(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        bufs: [&mut [u8]; 2],
+    ) -> Poll<io::Result<usize>> {
+        let mut filled = 0;
+
+        for byte in bufs.into_iter().flatten() {
+            if self.remaining == 0 {
+                break;
+            }
+
+            *byte = self.next;
+            self.next = self.next.wrapping_add(1);
+            self.remaining -= 1;
+            filled += 1;
+        }
+
+        Poll::Ready(Ok(filled))
+    }
+}
+
+/// A destination that records each pair of slices it's given
+#[derive(Default)]
+struct Recorder {
+    data: Vec<u8>,
+    drains: usize,
+}
+
+impl Destination for &mut Recorder {
+    fn poll_drain(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        [b1, b2]: [&[u8]; 2],
+    ) -> Poll<io::Result<usize>> {
+        self.data.extend_from_slice(b1);
+        self.data.extend_from_slice(b2);
+        self.drains += 1;
+        Poll::Ready(Ok(b1.len() + b2.len()))
+    }
+}
+
+fn counted(len: usize) -> Vec<u8> {
+    (0..len).map(|i| i as u8).collect()
+}
+
+#[test]
+fn forward_between_custom_endpoints() {
+    let source = Counter {
+        next: 0,
+        remaining: 1000,
+    };
+
+    let mut recorder = Recorder::default();
+    let written = block_on(Forwarder::from_endpoints(source, &mut recorder, [0; 64])).unwrap();
+
+    assert_eq!(written, 1000);
+    assert_eq!(recorder.data, counted(1000));
+}
+
+#[test]
+fn forward_into_custom_destination() {
+    let data = counted(1000);
+    let mut recorder = Recorder::default();
+
+    // Mixing a custom endpoint with an ordinary reader
+    block_on(Forwarder::new(
+        Cursor::new(data.clone()),
+        Endpoint::new(&mut recorder),
+        [0; 64],
+    ))
+    .unwrap();
+
+    assert_eq!(recorder.data, data);
+
+    // Both halves of the ring were drained together whenever it wrapped
+    assert!(recorder.drains <= 1000 / 64 + 1);
+}
@@ -0,0 +1,111 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_forward::{ForwarderError, MergeForwarder};
+use futures::{executor::block_on, AsyncRead, Future};
+
+/// A reader that produces one line per read
+struct LineReader {
+    lines: std::vec::IntoIter<Vec<u8>>,
+}
+
+impl LineReader {
+    fn new(name: &str, count: usize) -> Self {
+        let lines: Vec<Vec<u8>> = (0..count)
+            .map(|i| format!("{name} {i}\n").into_bytes())
+            .collect();
+
+        Self {
+            lines: lines.into_iter(),
+        }
+    }
+}
+
+impl AsyncRead for LineReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let Some(line) = self.lines.next() else {
+            return Poll::Ready(Ok(0));
+        };
+
+        assert!(line.len() <= buf.len(), "line doesn't fit in the read");
+        buf[..line.len()].copy_from_slice(&line);
+        Poll::Ready(Ok(line.len()))
+    }
+}
+
+/// A reader that fails immediately
+struct FailingReader;
+
+impl AsyncRead for FailingReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()))
+    }
+}
+
+#[test]
+fn merge_interleaves_whole_lines() {
+    let readers = ["a", "b", "c"].map(|name| LineReader::new(name, 50));
+    let mut out = Vec::new();
+
+    let written = block_on(MergeForwarder::new(readers, &mut out, [0; 64])).unwrap();
+    assert_eq!(written, out.len() as u64);
+
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 150);
+
+    // Every line arrives whole, and each reader's lines stay in order
+    for name in ["a", "b", "c"] {
+        let expected: Vec<String> = (0..50).map(|i| format!("{name} {i}")).collect();
+        let actual: Vec<&str> = lines
+            .iter()
+            .copied()
+            .filter(|line| line.starts_with(name))
+            .collect();
+
+        assert_eq!(actual, expected);
+    }
+
+    // The readers take turns, rather than one being drained first
+    assert_ne!(
+        lines[..50],
+        *(0..50).map(|i| format!("a {i}")).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn merge_fails_if_any_reader_fails() {
+    let readers: Vec<Box<dyn AsyncRead + Unpin>> =
+        vec![Box::new(LineReader::new("a", 10)), Box::new(FailingReader)];
+
+    let err = block_on(MergeForwarder::new(readers, Vec::new(), [0; 64])).unwrap_err();
+    assert_eq!(err.reader, Some(1));
+    assert!(matches!(err.source, ForwarderError::Read { .. }));
+}
+
+#[test]
+fn merge_yields_to_executor() {
+    // The readers and writer are always ready, so the forward never ends,
+    // but each poll has to
+    let readers = vec![futures::io::repeat(1), futures::io::repeat(2)];
+    let mut merge = MergeForwarder::new(readers, futures::io::sink(), vec![0; 64]);
+
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    for _ in 0..3 {
+        assert!(Pin::new(&mut merge).poll(&mut cx).is_pending());
+    }
+    assert!(merge.bytes_written() > 0);
+}
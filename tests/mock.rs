@@ -0,0 +1,351 @@
+#![cfg(feature = "testing")]
+
+use std::{
+    io,
+    num::NonZeroU32,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use async_forward::{
+    mock::{drive, Action, MockStream},
+    BufferSink, ForwardTap, Forwarder, ForwarderError, WriterClosePolicy,
+};
+use futures::{AsyncBufRead, AsyncWrite, Future};
+
+#[test]
+fn scripted_reads_and_writes() {
+    let reader = MockStream::new([
+        Action::Read(b"hello, ".to_vec()),
+        Action::Pending,
+        Action::WouldBlock,
+        Action::Read(b"world".to_vec()),
+        Action::Interrupted,
+        Action::Read(b"!".to_vec()),
+        Action::Eof,
+    ]);
+
+    let mut writer = MockStream::new([
+        Action::WriteAccept(3),
+        Action::Pending,
+        Action::Interrupted,
+        Action::WriteAccept(1),
+    ]);
+
+    let mut forwarder = Forwarder::new(reader, &mut writer, [0; 8]);
+    let (result, _) = drive(&mut forwarder, 100).unwrap();
+    result.unwrap();
+
+    assert_eq!(forwarder.would_block_reads(), 1);
+    assert_eq!(forwarder.interrupted_reads(), 1);
+    assert_eq!(forwarder.interrupted_writes(), 1);
+
+    drop(forwarder);
+    assert_eq!(writer.written(), b"hello, world!");
+    assert_eq!(writer.remaining(), 0);
+}
+
+#[test]
+fn scripted_write_zero() {
+    let reader = MockStream::new([Action::Read(b"hello".to_vec())]);
+    let writer = MockStream::new([Action::WriteAccept(2), Action::WriteZero]);
+
+    let (result, _) = drive(Forwarder::new(reader, writer, [0; 8]), 100).unwrap();
+
+    match result {
+        Err(ForwarderError::WriteClosedEarly {
+            forwarded,
+            unforwarded,
+        }) => {
+            assert_eq!(forwarded, 2);
+            assert_eq!(unforwarded, b"llo");
+        }
+        result => panic!("unexpected result: {result:?}"),
+    }
+}
+
+#[test]
+fn graceful_writer_close_between_reads() {
+    let reader = MockStream::new([
+        Action::Read(b"hello".to_vec()),
+        Action::Pending,
+        Action::Read(b"world".to_vec()),
+    ]);
+    let mut writer = MockStream::new([Action::WriteAccept(5), Action::WriteZero]);
+
+    let mut forwarder = Forwarder::new(reader, &mut writer, [0; 8])
+        .with_writer_close_policy(WriterClosePolicy::GracefulWhenDrained);
+    let (result, _) = drive(&mut forwarder, 100).unwrap();
+    assert_eq!(result.unwrap(), 5);
+
+    // The data that wasn't written can still be recovered
+    assert_eq!(forwarder.into_parts().unwritten, b"world");
+    assert_eq!(writer.written(), b"hello");
+}
+
+#[test]
+fn graceful_writer_close_after_reader_eof() {
+    // The reader is done, so the writer closing can't be a request to stop
+    // early, and the last read would be lost
+    let reader = MockStream::new([
+        Action::Read(b"hello".to_vec()),
+        Action::Pending,
+        Action::Read(b"world".to_vec()),
+        Action::Eof,
+    ]);
+    let writer = MockStream::new([Action::WriteAccept(5), Action::Pending, Action::WriteZero]);
+
+    let forwarder = Forwarder::new(reader, writer, [0; 8])
+        .with_writer_close_policy(WriterClosePolicy::GracefulWhenDrained);
+    let (result, _) = drive(forwarder, 100).unwrap();
+
+    match result {
+        Err(ForwarderError::WriteClosedEarly {
+            forwarded,
+            unforwarded,
+        }) => {
+            assert_eq!(forwarded, 5);
+            assert_eq!(unforwarded, b"world");
+        }
+        other => panic!("expected the writer to close early, got {other:?}"),
+    }
+}
+
+#[test]
+fn graceful_writer_close_mid_buffer() {
+    // The writer closes after taking only part of the first read, so this is
+    // still an error
+    let reader = MockStream::new([Action::Read(b"hello".to_vec())]);
+    let writer = MockStream::new([Action::WriteAccept(2), Action::WriteZero]);
+
+    let forwarder = Forwarder::new(reader, writer, [0; 8])
+        .with_writer_close_policy(WriterClosePolicy::GracefulWhenDrained);
+    let (result, _) = drive(forwarder, 100).unwrap();
+
+    assert!(matches!(
+        result,
+        Err(ForwarderError::WriteClosedEarly { forwarded: 2, .. })
+    ));
+}
+
+#[test]
+fn scripted_non_vectored() {
+    // With a wrapped ring, a non-vectored writer only takes the first slice
+    // of each write
+    let reader = MockStream::new([
+        Action::Read(b"abcdef".to_vec()),
+        Action::Read(b"ghij".to_vec()),
+    ])
+    .without_vectored();
+
+    let mut writer = MockStream::new([Action::WriteAccept(4), Action::Pending, Action::Pending])
+        .without_vectored();
+
+    let mut forwarder =
+        Forwarder::new(reader, &mut writer, [0; 8]).with_yield_budget(NonZeroU32::new(2).unwrap());
+    let (result, _) = drive(&mut forwarder, 100).unwrap();
+    result.unwrap();
+
+    drop(forwarder);
+    assert_eq!(writer.written(), b"abcdefghij");
+}
+
+#[test]
+#[should_panic = "unexpected action for a read"]
+fn wrong_action_kind() {
+    let reader = MockStream::new([Action::WriteZero]);
+    let _ = drive(Forwarder::new(reader, Vec::new(), [0; 8]), 100);
+}
+
+#[test]
+#[should_panic = "empty Action::Read"]
+fn empty_read_action() {
+    let reader = MockStream::new([Action::Read(Vec::new()), Action::Read(b"abc".to_vec())]);
+    let _ = drive(Forwarder::new(reader, Vec::new(), [0; 8]), 100);
+}
+
+/// A writer that behaves like an OpenSSL-based TLS stream: the first write
+/// takes the data internally and returns `Pending`, and the retry must offer
+/// exactly the same bytes, at which point they're reported as written.
+#[derive(Default)]
+struct TlsLikeWriter {
+    // The data taken by a write that returned `Pending`
+    in_flight: Option<Vec<u8>>,
+    written: Vec<u8>,
+}
+
+impl AsyncWrite for TlsLikeWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        match this.in_flight.take() {
+            None => {
+                this.in_flight = Some(buf.to_vec());
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            Some(in_flight) if in_flight == buf => {
+                this.written.extend_from_slice(&in_flight);
+                Poll::Ready(Ok(buf.len()))
+            }
+            Some(_) => Poll::Ready(Err(io::Error::other("bad write retry"))),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[test]
+fn strict_write_accounting() {
+    // More data is read while the first write is in flight
+    let reader = MockStream::new([Action::Read(b"abc".to_vec()), Action::Read(b"def".to_vec())]);
+    let mut writer = TlsLikeWriter::default();
+
+    let forwarder = Forwarder::new(reader, &mut writer, [0; 8]).strict_write_accounting(true);
+    let (result, _) = drive(forwarder, 100).unwrap();
+    result.unwrap();
+
+    // Nothing was duplicated or lost across the retries
+    assert_eq!(writer.written, b"abcdef");
+}
+
+#[test]
+fn loose_write_accounting() {
+    // Without strict accounting, the retry includes the newly read data
+    let reader = MockStream::new([Action::Read(b"abc".to_vec()), Action::Read(b"def".to_vec())]);
+    let writer = TlsLikeWriter::default();
+
+    let (result, _) = drive(Forwarder::new(reader, writer, [0; 8]), 100).unwrap();
+    assert!(matches!(result, Err(ForwarderError::Write { .. })));
+}
+
+#[test]
+fn flush_on_read_stall() {
+    for flush in [false, true] {
+        let reader = MockStream::new([
+            Action::Read(b"hello".to_vec()),
+            Action::Pending,
+            Action::Read(b"world".to_vec()),
+            Action::Pending,
+            Action::Eof,
+        ]);
+        let mut writer = MockStream::default();
+
+        let forwarder = Forwarder::new(reader, &mut writer, [0; 16]).flush_on_read_stall(flush);
+        let (result, _) = drive(forwarder, 100).unwrap();
+        result.unwrap();
+
+        // Each stall flushes what was written before it
+        assert_eq!(writer.written(), b"helloworld");
+        assert_eq!(writer.flushes(), if flush { 2 } else { 0 });
+    }
+}
+
+/// A waker that counts how many times it's been woken, to catch futures that
+/// return `Pending` without arranging to be polled again
+#[derive(Default)]
+struct WakeCounter(AtomicUsize);
+
+impl futures::task::ArcWake for WakeCounter {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl WakeCounter {
+    fn wakes(&self) -> usize {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn forward_stream_wakes_after_would_block() {
+    use bytes::Bytes;
+    use futures::{stream, StreamExt};
+
+    let chunks = stream::iter([Ok(Bytes::from_static(b"hello"))]).chain(stream::pending());
+    let mut writer = MockStream::new([Action::WouldBlock, Action::WouldBlock]);
+    let mut forward = async_forward::forward_stream(chunks, &mut writer);
+
+    let counter = Arc::new(WakeCounter::default());
+    let waker = futures::task::waker(counter.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    // Every poll that hits `WouldBlock` has to wake the task, even once the
+    // stream itself is pending
+    for polls in 1..=2 {
+        assert!(Pin::new(&mut forward).poll(&mut cx).is_pending());
+        assert!(counter.wakes() >= polls);
+    }
+
+    assert!(Pin::new(&mut forward).poll(&mut cx).is_pending());
+    drop(forward);
+    assert_eq!(writer.written(), b"hello");
+}
+
+#[test]
+fn buffer_sink_wakes_after_would_block() {
+    let mut writer = MockStream::new([Action::WouldBlock]);
+    let mut sink = BufferSink::new(&mut writer, [0; 16]);
+
+    let counter = Arc::new(WakeCounter::default());
+    let waker = futures::task::waker(counter.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    assert!(matches!(
+        Pin::new(&mut sink).poll_write(&mut cx, b"hello"),
+        Poll::Ready(Ok(5))
+    ));
+
+    assert!(Pin::new(&mut sink).poll_flush(&mut cx).is_pending());
+    assert_eq!(counter.wakes(), 1);
+
+    assert!(matches!(
+        Pin::new(&mut sink).poll_flush(&mut cx),
+        Poll::Ready(Ok(()))
+    ));
+    drop(sink);
+    assert_eq!(writer.written(), b"hello");
+}
+
+#[test]
+fn forward_tap_wakes_after_would_block() {
+    let reader = MockStream::new([Action::Read(b"hello".to_vec())]);
+    let mut writer = MockStream::new([Action::WouldBlock]);
+    let mut tap = ForwardTap::new(reader, &mut writer, [0; 16]);
+
+    let counter = Arc::new(WakeCounter::default());
+    let waker = futures::task::waker(counter.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    match Pin::new(&mut tap).poll_fill_buf(&mut cx) {
+        Poll::Ready(Ok(data)) => assert_eq!(data, b"hello"),
+        _ => panic!("expected the tap to read"),
+    }
+    Pin::new(&mut tap).consume(5);
+
+    assert!(Pin::new(&mut tap).poll_finish(&mut cx).is_pending());
+    assert_eq!(counter.wakes(), 1);
+
+    assert!(matches!(
+        Pin::new(&mut tap).poll_finish(&mut cx),
+        Poll::Ready(Ok(()))
+    ));
+    drop(tap);
+    assert_eq!(writer.written(), b"hello");
+}
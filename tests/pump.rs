@@ -0,0 +1,93 @@
+use std::{
+    future::poll_fn,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_forward::DuplexPump;
+use futures::{executor::block_on, io::Cursor, AsyncWrite};
+
+fn test_data(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+/// A writer that accepts at most `chunk` bytes per write
+struct ChunkedWriter {
+    data: Vec<u8>,
+    chunk: usize,
+}
+
+impl AsyncWrite for ChunkedWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let len = buf.len().min(self.chunk);
+        self.data.extend_from_slice(&buf[..len]);
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[test]
+fn copy_through_a_pump() {
+    let data = test_data(1000);
+    let mut reader = Cursor::new(data.clone());
+    let mut writer = ChunkedWriter {
+        data: Vec::new(),
+        chunk: 7,
+    };
+
+    let mut pump = DuplexPump::new([0; 16]);
+    let mut eof = false;
+    let mut reads = 0;
+
+    // Custom scheduling: read whenever there's room, but only write once
+    // the buffer is at least half full, or the reader is done
+    block_on(poll_fn(|cx| {
+        while !eof || pump.write_ready() {
+            if !eof && pump.read_ready() {
+                match futures::ready!(pump.poll_pump_read(cx, Pin::new(&mut reader)))? {
+                    0 => eof = true,
+                    _ => reads += 1,
+                }
+            }
+
+            if pump.write_ready() && (eof || pump.buffered_len() >= 8) {
+                futures::ready!(pump.poll_pump_write(cx, Pin::new(&mut writer)))?;
+            }
+        }
+
+        Poll::Ready(Ok::<_, std::io::Error>(()))
+    }))
+    .unwrap();
+
+    assert_eq!(writer.data, data);
+    assert!(reads >= 1000 / 16);
+}
+
+#[test]
+#[should_panic(expected = "pump write with nothing buffered")]
+fn write_with_nothing_buffered() {
+    let mut pump = DuplexPump::new([0; 16]);
+    let _ = block_on(poll_fn(|cx| {
+        pump.poll_pump_write(cx, Pin::new(&mut Vec::new()))
+    }));
+}
+
+#[test]
+fn empty_pump_never_reads() {
+    // A zero-length read would look like EOF, and lose the reader's data
+    let pump = DuplexPump::new(Vec::new());
+
+    assert!(!pump.read_ready());
+    assert!(!pump.write_ready());
+}
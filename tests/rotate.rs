@@ -0,0 +1,121 @@
+use std::{
+    io,
+    num::NonZeroU64,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use async_forward::RotatingForwarder;
+use futures::{executor::block_on, future, io::Cursor, AsyncWrite, Future};
+
+fn test_data(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+/// A writer for one segment, which hands its data over once it's closed
+struct Segment {
+    data: Vec<u8>,
+    closed: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+impl AsyncWrite for Segment {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        // Only accept a few bytes at a time, to exercise partial writes
+        let n = buf.len().min(7);
+        self.data.extend_from_slice(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let data = std::mem::take(&mut self.data);
+        self.closed.lock().unwrap().push(data);
+        Poll::Ready(Ok(()))
+    }
+}
+
+fn rotate(data: &[u8], segment_len: u64) -> Vec<Vec<u8>> {
+    let closed = Arc::new(Mutex::new(Vec::new()));
+    let mut indexes = Vec::new();
+
+    let written = block_on(RotatingForwarder::new(
+        Cursor::new(data.to_vec()),
+        [0; 64],
+        NonZeroU64::new(segment_len).unwrap(),
+        |index| {
+            indexes.push(index);
+            future::ready(Ok(Segment {
+                data: Vec::new(),
+                closed: closed.clone(),
+            }))
+        },
+    ))
+    .unwrap();
+
+    assert_eq!(written, data.len() as u64);
+    assert_eq!(indexes, (0..indexes.len() as u64).collect::<Vec<_>>());
+
+    let closed = closed.lock().unwrap().clone();
+    closed
+}
+
+#[test]
+fn rotate_every_segment_len_bytes() {
+    let data = test_data(1050);
+    let segments = rotate(&data, 100);
+
+    assert_eq!(segments.len(), 11);
+    assert!(segments[..10].iter().all(|segment| segment.len() == 100));
+    assert_eq!(segments[10].len(), 50);
+    assert_eq!(segments.concat(), data);
+}
+
+#[test]
+fn no_empty_trailing_segment() {
+    let data = test_data(1000);
+    let segments = rotate(&data, 100);
+
+    assert_eq!(segments.len(), 10);
+    assert_eq!(segments.concat(), data);
+}
+
+#[test]
+fn factory_failure() {
+    let err = block_on(RotatingForwarder::new(
+        Cursor::new(test_data(100)),
+        [0; 64],
+        NonZeroU64::new(10).unwrap(),
+        |_| future::ready(Err::<Vec<u8>, _>(io::ErrorKind::PermissionDenied.into())),
+    ))
+    .unwrap_err();
+
+    assert_eq!(err.into_io_error().kind(), io::ErrorKind::PermissionDenied);
+}
+
+#[test]
+fn rotate_yields_to_executor() {
+    // The reader and writers are always ready, so the forward never ends,
+    // but each poll has to
+    let mut forward = RotatingForwarder::new(
+        futures::io::repeat(7),
+        [0; 64],
+        NonZeroU64::new(1000).unwrap(),
+        |_| future::ready(Ok(futures::io::sink())),
+    );
+
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    for _ in 0..3 {
+        assert!(Pin::new(&mut forward).poll(&mut cx).is_pending());
+    }
+    assert!(forward.bytes_written() > 0);
+}
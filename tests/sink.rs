@@ -0,0 +1,72 @@
+use std::{
+    cell::{Cell, RefCell},
+    io,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use async_forward::BufferSink;
+use futures::{executor::block_on, AsyncWrite, AsyncWriteExt};
+
+/// A writer that shares what's been written to it, so that it can be
+/// inspected while the sink still owns the writer
+#[derive(Default, Clone)]
+struct SharedWriter {
+    data: Rc<RefCell<Vec<u8>>>,
+    writes: Rc<Cell<usize>>,
+}
+
+impl AsyncWrite for SharedWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.data.borrow_mut().extend_from_slice(buf);
+        self.writes.set(self.writes.get() + 1);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[test]
+fn sink_buffers_until_full() {
+    block_on(async {
+        let out = SharedWriter::default();
+        let mut sink = BufferSink::new(out.clone(), [0; 8]);
+
+        // This fits in the ring, so nothing is written yet
+        sink.write_all(b"hello").await.unwrap();
+        assert_eq!(out.writes.get(), 0);
+        assert!(out.data.borrow().is_empty());
+
+        sink.write_all(b", world").await.unwrap();
+        sink.flush().await.unwrap();
+
+        assert_eq!(*out.data.borrow(), b"hello, world");
+    })
+}
+
+#[test]
+fn sink_wraps_ring() {
+    block_on(async {
+        let data: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let mut out = Vec::new();
+        let mut sink = BufferSink::new(&mut out, vec![0; 7]);
+
+        for chunk in data.chunks(5) {
+            sink.write_all(chunk).await.unwrap();
+        }
+        sink.close().await.unwrap();
+
+        assert_eq!(out, data);
+    })
+}
@@ -0,0 +1,139 @@
+#![cfg(feature = "bytes")]
+
+use std::{io, pin::Pin, task::Context};
+
+use async_forward::{forward_records, forward_stream, forward_to_sink, Forwarder, ForwarderError};
+use bytes::Bytes;
+use futures::{
+    channel::mpsc, executor::block_on, future, io::Cursor, stream, Future, SinkExt, StreamExt,
+    TryStreamExt,
+};
+
+#[test]
+fn forward_stream_chunks() {
+    let chunks = vec![
+        Ok(Bytes::from_static(b"hello")),
+        Ok(Bytes::new()),
+        Ok(Bytes::from_static(b", ")),
+        Ok(Bytes::from_static(b"world")),
+    ];
+
+    let mut writer = Vec::new();
+    let written = block_on(forward_stream(stream::iter(chunks), &mut writer)).unwrap();
+
+    assert_eq!(written, 12);
+    assert_eq!(writer, b"hello, world");
+}
+
+#[test]
+fn forward_stream_error() {
+    let chunks = vec![
+        Ok(Bytes::from_static(b"hello")),
+        Err(io::Error::other("broken")),
+    ];
+
+    let mut writer = Vec::new();
+    let err = block_on(forward_stream(stream::iter(chunks), &mut writer)).unwrap_err();
+
+    assert!(matches!(err, ForwarderError::Read { .. }));
+    assert_eq!(writer, b"hello");
+}
+
+#[test]
+fn stream_as_forwarder_source() {
+    let chunks: Vec<io::Result<Bytes>> = (0..10u8).map(|i| Ok(Bytes::from(vec![i; 100]))).collect();
+
+    // Each chunk is larger than the buffer, so it has to be split across
+    // reads
+    let mut writer = Vec::new();
+    let written = block_on(Forwarder::new(
+        stream::iter(chunks).into_async_read(),
+        &mut writer,
+        [0; 64],
+    ))
+    .unwrap();
+
+    assert_eq!(written, 1000);
+    assert!(writer
+        .chunks(100)
+        .enumerate()
+        .all(|(i, chunk)| chunk.iter().all(|&byte| byte == i as u8)));
+}
+
+#[test]
+fn forward_into_sink() {
+    let data: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+
+    // The channel only holds one item, so the forward has to wait for the
+    // receiver
+    let (tx, rx) = mpsc::channel::<Bytes>(1);
+    let tx = tx.sink_map_err(io::Error::other);
+
+    let (written, items) = block_on(future::join(
+        forward_to_sink(Cursor::new(data.clone()), tx, [0; 64]),
+        rx.collect::<Vec<Bytes>>(),
+    ));
+
+    assert_eq!(written.unwrap(), 1000);
+    assert!(items
+        .iter()
+        .all(|item| !item.is_empty() && item.len() <= 64));
+    assert_eq!(items.concat(), data);
+}
+
+#[test]
+fn split_records_into_sink() {
+    let lines: Vec<String> = (0..100).map(|i| format!("line {i}")).collect();
+    let mut data = lines.join("\n").into_bytes();
+    data.extend_from_slice(b"\n\nlast");
+
+    let (tx, rx) = mpsc::channel::<Bytes>(1);
+    let tx = tx.sink_map_err(io::Error::other);
+
+    // The buffer is small, so records wrap around the ring
+    let (records, items) = block_on(future::join(
+        forward_records(Cursor::new(data), tx, [0; 16], b'\n'),
+        rx.collect::<Vec<Bytes>>(),
+    ));
+
+    let mut expected: Vec<&[u8]> = lines.iter().map(|line| line.as_bytes()).collect();
+    expected.extend([&b""[..], b"last"]);
+
+    assert_eq!(records.unwrap(), 102);
+    assert_eq!(items, expected);
+}
+
+#[test]
+fn record_too_long() {
+    let (tx, _rx) = mpsc::unbounded::<Bytes>();
+    let tx = tx.sink_map_err(io::Error::other);
+
+    let err = block_on(forward_records(
+        Cursor::new(b"short\nthis record is much too long\n".to_vec()),
+        tx,
+        [0; 16],
+        b'\n',
+    ))
+    .unwrap_err();
+
+    assert!(matches!(
+        err,
+        ForwarderError::RecordTooLong { capacity: 16 }
+    ));
+}
+
+#[test]
+fn records_yield_to_executor() {
+    // The reader and sink are always ready, so the forward never ends, but
+    // each poll has to
+    let sink = futures::sink::drain().sink_map_err(|never| -> io::Error { match never {} });
+    let mut forward = forward_records(futures::io::repeat(b'\n'), sink, [0; 16], b'\n');
+
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    for _ in 0..3 {
+        assert!(Pin::new(&mut forward).poll(&mut cx).is_pending());
+    }
+    assert!(forward.records() > 0);
+}
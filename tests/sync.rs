@@ -1,6 +1,2251 @@
-use async_forward::Forwarder;
-use futures::{AsyncRead, AsyncWrite};
+use std::{
+    future::Future,
+    io::{self, IoSlice},
+    num::{NonZeroU32, NonZeroU64, NonZeroUsize},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
 
+use async_forward::{
+    forward, forward_ref, AbortError, Backoff, Checksum, DeadlineError, FlushPolicy, ForwardExt,
+    ForwardProgress, ForwardStats, Forwarder, ForwarderError, ForwarderPhase, PrefillTooLarge,
+    ProgressEvent, RateLimiter, ReceiveExt, UninitBuffer, VecPool, WaitingOn, WriteProbe,
+};
+use futures::{
+    executor::block_on, future, future::FusedFuture, io::Cursor, AsyncRead, AsyncReadExt,
+    AsyncWrite, FutureExt, StreamExt,
+};
+
+/// A writer that records everything written to it, but only accepts up to
+/// `chunk` bytes per write, to exercise partial writes.
 struct TestBuffer {
     data: Vec<u8>,
+    chunk: usize,
+
+    // The size of each individual write
+    writes: Vec<usize>,
+
+    flushes: usize,
+    closed: bool,
+}
+
+impl TestBuffer {
+    fn new(chunk: usize) -> Self {
+        Self {
+            data: Vec::new(),
+            chunk,
+            writes: Vec::new(),
+            flushes: 0,
+            closed: false,
+        }
+    }
+}
+
+impl AsyncWrite for TestBuffer {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let n = buf.len().min(this.chunk);
+        this.data.extend_from_slice(&buf[..n]);
+        this.writes.push(n);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let mut remaining = this.chunk;
+
+        for buf in bufs {
+            let n = buf.len().min(remaining);
+            this.data.extend_from_slice(&buf[..n]);
+            remaining -= n;
+        }
+
+        this.writes.push(this.chunk - remaining);
+        Poll::Ready(Ok(this.chunk - remaining))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().flushes += 1;
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().closed = true;
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A reader that produces its data at most `chunk` bytes at a time
+struct ChunkedReader {
+    data: Cursor<Vec<u8>>,
+    chunk: usize,
+}
+
+impl ChunkedReader {
+    fn new(data: Vec<u8>, chunk: usize) -> Self {
+        Self {
+            data: Cursor::new(data),
+            chunk,
+        }
+    }
+}
+
+impl AsyncRead for ChunkedReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let len = buf.len().min(this.chunk);
+        Pin::new(&mut this.data).poll_read(cx, &mut buf[..len])
+    }
+}
+
+fn test_data(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+#[test]
+fn forward_everything() {
+    let data = test_data(10_000);
+    let mut writer = TestBuffer::new(usize::MAX);
+
+    let written = block_on(Forwarder::new(
+        Cursor::new(data.clone()),
+        &mut writer,
+        vec![0; 64],
+    ))
+    .unwrap();
+
+    assert_eq!(written, 10_000);
+
+    assert_eq!(writer.data, data);
+}
+
+#[test]
+fn forward_one_liner() {
+    let data = test_data(10_000);
+    let mut writer = TestBuffer::new(usize::MAX);
+
+    let written = block_on(forward(Cursor::new(data.clone()), &mut writer, [0; 64])).unwrap();
+
+    assert_eq!(written, 10_000);
+    assert_eq!(writer.data, data);
+}
+
+#[test]
+fn forward_to_extension() {
+    let data = test_data(10_000);
+    let mut writer = TestBuffer::new(usize::MAX);
+
+    let written = block_on(Cursor::new(data.clone()).forward_to(&mut writer, [0; 64])).unwrap();
+
+    assert_eq!(written, 10_000);
+    assert_eq!(writer.data, data);
+}
+
+#[test]
+fn receive_from_extension() {
+    let data = test_data(10_000);
+    let mut writer = TestBuffer::new(usize::MAX);
+
+    let written = block_on(
+        (&mut writer)
+            .receive_from(Cursor::new(data.clone()), [0; 64])
+            .with_flush_policy(FlushPolicy::EveryWrite),
+    )
+    .unwrap();
+
+    assert_eq!(written, 10_000);
+    assert_eq!(writer.data, data);
+    assert!(writer.flushes > 0);
+}
+
+#[test]
+#[should_panic(expected = "forwarder buffer is empty")]
+fn reject_empty_buffer() {
+    drop(Forwarder::new(
+        &b"hello"[..],
+        futures::io::sink(),
+        Vec::new(),
+    ));
+}
+
+#[test]
+fn forward_with_partial_reads_and_writes() {
+    let data = test_data(10_000);
+    let mut writer = TestBuffer::new(7);
+
+    block_on(Forwarder::new(
+        ChunkedReader::new(data.clone(), 13),
+        &mut writer,
+        vec![0; 32],
+    ))
+    .unwrap();
+
+    assert_eq!(writer.data, data);
+}
+
+#[test]
+fn forward_with_single_iov() {
+    let data = test_data(10_000);
+    let mut writer = TestBuffer::new(usize::MAX);
+
+    block_on(
+        Forwarder::new(
+            ChunkedReader::new(data.clone(), 11),
+            &mut writer,
+            vec![0; 32],
+        )
+        .with_max_iov(NonZeroUsize::new(1).unwrap()),
+    )
+    .unwrap();
+
+    assert_eq!(writer.data, data);
+}
+
+#[test]
+fn forward_with_read_aggregation() {
+    let data = test_data(10_000);
+    let mut writer = TestBuffer::new(usize::MAX);
+
+    block_on(
+        Forwarder::new(
+            ChunkedReader::new(data.clone(), 3),
+            &mut writer,
+            vec![0; 64],
+        )
+        .with_read_aggregation(20),
+    )
+    .unwrap();
+
+    assert_eq!(writer.data, data);
+}
+
+/// A reader that fails with the given error kind before every successful read
+struct FlakyReader {
+    inner: ChunkedReader,
+    kind: io::ErrorKind,
+    fail_next: bool,
+}
+
+impl AsyncRead for FlakyReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        this.fail_next = !this.fail_next;
+
+        match this.fail_next {
+            true => Poll::Ready(Err(this.kind.into())),
+            false => Pin::new(&mut this.inner).poll_read(cx, buf),
+        }
+    }
+}
+
+/// Repeatedly poll a future with a no-op waker until it completes. This lets
+/// us test with I/O objects that don't register wakers.
+fn poll_until_ready<F: Future + Unpin>(fut: &mut F) -> F::Output {
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    for _ in 0..100_000 {
+        if let Poll::Ready(out) = Pin::new(&mut *fut).poll(&mut cx) {
+            return out;
+        }
+    }
+
+    panic!("future didn't complete");
+}
+
+#[test]
+fn terminated_after_completion() {
+    let data = test_data(100);
+    let mut writer = TestBuffer::new(10);
+
+    let mut forwarder = Forwarder::new(Cursor::new(data.clone()), &mut writer, [0; 16]);
+    assert!(!forwarder.is_terminated());
+
+    poll_until_ready(&mut forwarder).unwrap();
+    assert!(forwarder.is_terminated());
+
+    drop(forwarder);
+    assert_eq!(writer.data, data);
+}
+
+#[test]
+fn terminated_after_error() {
+    let reader = FlakyReader {
+        inner: ChunkedReader::new(test_data(100), 10),
+        kind: io::ErrorKind::ConnectionReset,
+        fail_next: false,
+    };
+
+    let mut forwarder = Forwarder::new(reader, TestBuffer::new(usize::MAX), [0; 16]);
+    assert!(poll_until_ready(&mut forwarder).is_err());
+    assert!(forwarder.is_terminated());
+}
+
+/// A stream identified by a number, like a file descriptor
+struct Fd(u32, Cursor<Vec<u8>>);
+
+impl AsyncRead for Fd {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.1).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Fd {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.1).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.1).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.1).poll_close(cx)
+    }
+}
+
+#[test]
+fn distinct_streams() {
+    let data = test_data(100);
+    let reader = Fd(3, Cursor::new(data.clone()));
+    let mut writer = Fd(4, Cursor::new(Vec::new()));
+
+    block_on(
+        Forwarder::new(reader, &mut writer, [0; 16])
+            .assert_distinct(|reader, writer| reader.0 == writer.0),
+    )
+    .unwrap();
+
+    assert_eq!(writer.1.into_inner(), data);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "forwarder reader and writer are the same stream")]
+fn aliased_streams() {
+    let reader = Fd(3, Cursor::new(test_data(100)));
+    let writer = Fd(3, Cursor::new(Vec::new()));
+
+    drop(
+        Forwarder::new(reader, writer, [0; 16])
+            .assert_distinct(|reader, writer| reader.0 == writer.0),
+    );
+}
+
+#[derive(Debug, PartialEq)]
+enum AppError {
+    Upstream(io::ErrorKind),
+    Downstream(io::ErrorKind),
+    Other,
+}
+
+fn to_app_error(err: ForwarderError) -> AppError {
+    match err {
+        ForwarderError::Read { error, .. } => AppError::Upstream(error.kind()),
+        ForwarderError::Write { error, .. } => AppError::Downstream(error.kind()),
+        _ => AppError::Other,
+    }
+}
+
+#[test]
+fn map_forwarder_error() {
+    let data = test_data(100);
+    let mut writer = TestBuffer::new(usize::MAX);
+
+    let written = block_on(
+        Forwarder::new(Cursor::new(data.clone()), &mut writer, [0; 16]).map_err(to_app_error),
+    )
+    .unwrap();
+
+    assert_eq!(written, 100);
+    assert_eq!(writer.data, data);
+
+    let reader = FlakyReader {
+        inner: ChunkedReader::new(data, 10),
+        kind: io::ErrorKind::ConnectionReset,
+        fail_next: false,
+    };
+
+    let result = block_on(
+        Forwarder::new(reader, TestBuffer::new(usize::MAX), [0; 16]).map_err(to_app_error),
+    );
+    assert_eq!(
+        result,
+        Err(AppError::Upstream(io::ErrorKind::ConnectionReset))
+    );
+}
+
+#[test]
+fn errors_report_progress() {
+    let data = test_data(100);
+    let mut writer = TestBuffer::new(usize::MAX);
+
+    let reader = Cursor::new(data.clone()).chain(FlakyReader {
+        inner: ChunkedReader::new(Vec::new(), 10),
+        kind: io::ErrorKind::ConnectionReset,
+        fail_next: false,
+    });
+
+    match block_on(Forwarder::new(reader, &mut writer, [0; 16])) {
+        Err(ForwarderError::Read {
+            error,
+            bytes_read,
+            bytes_written,
+        }) => {
+            assert_eq!(error.kind(), io::ErrorKind::ConnectionReset);
+            assert_eq!(bytes_read, 100);
+            assert_eq!(bytes_written, writer.data.len() as u64);
+        }
+        other => panic!("expected a read error, got {other:?}"),
+    }
+
+    let mut writer = FailingWriter::new(50);
+
+    match block_on(Forwarder::new(
+        Cursor::new(data.clone()),
+        &mut writer,
+        [0; 16],
+    )) {
+        Err(ForwarderError::Write {
+            error,
+            bytes_read,
+            bytes_written,
+            unforwarded,
+        }) => {
+            assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+            assert_eq!(bytes_written, 50);
+            assert!((50..=66).contains(&bytes_read));
+
+            // Nothing that was read is lost
+            assert_eq!(unforwarded, data[50..bytes_read as usize]);
+        }
+        other => panic!("expected a write error, got {other:?}"),
+    }
+}
+
+#[test]
+fn count_interrupted_and_would_block() {
+    for kind in [io::ErrorKind::Interrupted, io::ErrorKind::WouldBlock] {
+        let data = test_data(100);
+        let mut writer = TestBuffer::new(usize::MAX);
+
+        let reader = FlakyReader {
+            inner: ChunkedReader::new(data.clone(), 10),
+            kind,
+            fail_next: false,
+        };
+
+        let mut forwarder = Forwarder::new(reader, &mut writer, vec![0; 16]);
+        poll_until_ready(&mut forwarder).unwrap();
+
+        // 10 successful reads and an EOF, each preceded by a failure
+        let (interrupted, would_block) = match kind {
+            io::ErrorKind::Interrupted => (11, 0),
+            _ => (0, 11),
+        };
+
+        assert_eq!(forwarder.interrupted_reads(), interrupted);
+        assert_eq!(forwarder.would_block_reads(), would_block);
+        assert_eq!(forwarder.interrupted_writes(), 0);
+        assert_eq!(forwarder.would_block_writes(), 0);
+
+        drop(forwarder);
+        assert_eq!(writer.data, data);
+    }
+}
+
+/// A reader that's always interrupted
+struct InterruptedReader;
+
+impl AsyncRead for InterruptedReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(Err(io::ErrorKind::Interrupted.into()))
+    }
+}
+
+#[test]
+fn no_progress_watchdog() {
+    let mut writer = TestBuffer::new(usize::MAX);
+
+    let result = block_on(
+        Forwarder::new(InterruptedReader, &mut writer, [0; 16])
+            .max_polls_without_progress(NonZeroU32::new(5).unwrap()),
+    );
+
+    match result {
+        Err(ForwarderError::NoProgress {
+            polls,
+            state,
+            interrupted_reads,
+            ..
+        }) => {
+            assert_eq!(polls, 5);
+            assert_eq!(state.phase, ForwarderPhase::Forwarding);
+            assert!(interrupted_reads >= 5);
+        }
+        other => panic!("expected NoProgress, got {other:?}"),
+    }
+
+    // Occasional polls without progress are fine, as long as some data
+    // moves in between
+    let data = test_data(100);
+    let reader = FlakyReader {
+        inner: ChunkedReader::new(data.clone(), 10),
+        kind: io::ErrorKind::Interrupted,
+        fail_next: false,
+    };
+
+    block_on(
+        Forwarder::new(reader, &mut writer, [0; 16])
+            .max_polls_without_progress(NonZeroU32::new(2).unwrap()),
+    )
+    .unwrap();
+
+    assert_eq!(writer.data, data);
+}
+
+#[test]
+fn retry_would_block_read() {
+    let data = test_data(100);
+    let mut writer = TestBuffer::new(usize::MAX);
+
+    let reader = FlakyReader {
+        inner: ChunkedReader::new(data.clone(), 10),
+        kind: io::ErrorKind::WouldBlock,
+        fail_next: false,
+    };
+
+    // A `WouldBlock` doesn't register the waker, so with a real executor
+    // this would hang if we didn't retry the read ourselves
+    block_on(Forwarder::new(reader, &mut writer, vec![0; 16])).unwrap();
+    assert_eq!(writer.data, data);
+}
+
+#[test]
+fn wakeup_registered_when_idle() {
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    // The buffer is empty, and the reader has nothing for us
+    let mut forwarder = Forwarder::new(StalledReader, Vec::new(), vec![0; 16]);
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+    assert_eq!(forwarder.debug_state().write_head, None);
+
+    // Everything that was read has been written, so the buffer is fully
+    // drained, and the reader has nothing more for us
+    let reader = Cursor::new(test_data(20)).chain(StalledReader);
+    let mut forwarder = Forwarder::new(reader, Vec::new(), vec![0; 16]);
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+    assert_eq!(forwarder.debug_state().bytes_written, 20);
+    assert_eq!(forwarder.debug_state().buffered, 0);
+}
+
+#[test]
+fn eager_flush_after_every_write() {
+    let data = test_data(1000);
+    let mut writer = TestBuffer::new(10);
+
+    block_on(Forwarder::new(Cursor::new(data.clone()), &mut writer, vec![0; 32]).eager_flush(true))
+        .unwrap();
+
+    assert_eq!(writer.data, data);
+    assert_eq!(writer.flushes, writer.writes.len());
+}
+
+#[test]
+fn flush_after_bytes() {
+    let data = test_data(1000);
+    let mut writer = TestBuffer::new(10);
+
+    block_on(
+        Forwarder::new(Cursor::new(data.clone()), &mut writer, vec![0; 32])
+            .with_flush_policy(FlushPolicy::AfterBytes(100)),
+    )
+    .unwrap();
+
+    // Every write is 10 bytes, so a flush is due after every 10 writes
+    assert_eq!(writer.data, data);
+    assert_eq!(writer.flushes, 10);
+}
+
+#[test]
+fn forward_with_read_limit() {
+    let data = test_data(1000);
+
+    for overflow in [false, true] {
+        let mut reader = Cursor::new(data.clone());
+        let mut writer = TestBuffer::new(usize::MAX);
+
+        let forwarder = Forwarder::new(&mut reader, &mut writer, [0; 16]).with_read_limit(100);
+        let forwarder = match overflow {
+            true => forwarder.with_overflow(vec![vec![0; 8].into_boxed_slice()]),
+            false => forwarder,
+        };
+
+        assert_eq!(block_on(forwarder).unwrap(), 100);
+        assert_eq!(writer.data, &data[..100]);
+
+        // The reader picks up right where the limit left off
+        let mut rest = Vec::new();
+        block_on(reader.read_to_end(&mut rest)).unwrap();
+        assert_eq!(rest, &data[100..]);
+    }
+}
+
+#[test]
+fn copy_exact() {
+    let data = test_data(100);
+
+    let mut writer = TestBuffer::new(usize::MAX);
+    let written =
+        block_on(Forwarder::new(Cursor::new(data.clone()), &mut writer, [0; 16]).copy_exact(60))
+            .unwrap();
+
+    assert_eq!(written, 60);
+    assert_eq!(writer.data, &data[..60]);
+
+    // A short stream is an error, but what was read is still written, and
+    // the writer isn't closed
+    let mut writer = TestBuffer::new(usize::MAX);
+    let result = block_on(
+        Forwarder::new(Cursor::new(data.clone()), &mut writer, [0; 16])
+            .copy_exact(200)
+            .half_close_on_eof(true),
+    );
+
+    assert!(matches!(
+        result,
+        Err(ForwarderError::UnexpectedEof { copied: 100 })
+    ));
+    assert_eq!(writer.data, data);
+    assert!(!writer.closed);
+}
+
+#[test]
+fn half_close_on_eof() {
+    let data = test_data(1000);
+
+    for half_close in [false, true] {
+        let mut writer = TestBuffer::new(50);
+
+        block_on(
+            Forwarder::new(Cursor::new(data.clone()), &mut writer, vec![0; 64])
+                .half_close_on_eof(half_close),
+        )
+        .unwrap();
+
+        assert_eq!(writer.data, data);
+        assert_eq!(writer.closed, half_close);
+    }
+
+    // A cancel isn't an EOF, so the writer stays open
+    let mut writer = TestBuffer::new(50);
+    let reader = Cursor::new(data.clone()).chain(StalledReader);
+
+    block_on(
+        Forwarder::new(reader, &mut writer, vec![0; 64])
+            .half_close_on_eof(true)
+            .with_cancel(async {}),
+    )
+    .unwrap();
+
+    assert!(!writer.closed);
+}
+
+#[test]
+fn flush_on_eof() {
+    let data = test_data(1000);
+
+    for half_close in [false, true] {
+        let mut writer = TestBuffer::new(50);
+
+        block_on(
+            Forwarder::new(Cursor::new(data.clone()), &mut writer, vec![0; 64])
+                .flush_on_eof(true)
+                .half_close_on_eof(half_close),
+        )
+        .unwrap();
+
+        // Closing flushes too, so there's no separate flush when closing
+        assert_eq!(writer.data, data);
+        assert_eq!(writer.flushes, if half_close { 0 } else { 1 });
+        assert_eq!(writer.closed, half_close);
+    }
+}
+
+/// A writer whose flushes always fail
+struct BrokenFlushWriter;
+
+impl AsyncWrite for BrokenFlushWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()))
+    }
+}
+
+#[test]
+fn flush_and_close_failures() {
+    let result = block_on(
+        Forwarder::new(Cursor::new(test_data(100)), BrokenFlushWriter, [0; 16]).flush_on_eof(true),
+    );
+    assert!(matches!(result, Err(ForwarderError::FlushFailed(_))));
+
+    let result = block_on(
+        Forwarder::new(Cursor::new(test_data(100)), BrokenFlushWriter, [0; 16])
+            .half_close_on_eof(true),
+    );
+    assert!(matches!(result, Err(ForwarderError::CloseFailed(_))));
+}
+
+#[test]
+fn forward_with_uninit_buffer() {
+    for capacity in [16, 5000, 100_000] {
+        let data = test_data(50_000);
+        let mut writer = TestBuffer::new(1000);
+
+        block_on(Forwarder::with_capacity(
+            ChunkedReader::new(data.clone(), 3000),
+            &mut writer,
+            capacity,
+        ))
+        .unwrap();
+
+        assert_eq!(writer.data, data);
+    }
+}
+
+/// A writer that accepts `remaining` bytes, then reports that it's closed
+struct ClosingWriter {
+    remaining: usize,
+}
+
+impl AsyncWrite for ClosingWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let n = buf.len().min(this.remaining);
+        this.remaining -= n;
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[test]
+fn write_closed_early_reports_progress() {
+    let data = test_data(1000);
+
+    let result = block_on(Forwarder::new(
+        Cursor::new(data.clone()),
+        ClosingWriter { remaining: 100 },
+        vec![0; 32],
+    ));
+
+    match result {
+        Err(ForwarderError::WriteClosedEarly {
+            forwarded,
+            unforwarded,
+        }) => {
+            assert_eq!(forwarded, 100);
+
+            // Whatever was still buffered picks up right where the writer
+            // left off
+            assert!(!unforwarded.is_empty());
+            assert_eq!(unforwarded, data[100..100 + unforwarded.len()]);
+        }
+        result => panic!("unexpected result: {result:?}"),
+    }
+}
+
+/// A writer that accepts nothing for the first few writes, but reports
+/// through its probe that it's still open
+struct StallingWriter {
+    data: Vec<u8>,
+    stalls: usize,
+}
+
+impl AsyncWrite for StallingWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        if this.stalls > 0 {
+            this.stalls -= 1;
+            cx.waker().wake_by_ref();
+            return Poll::Ready(Ok(0));
+        }
+
+        this.data.extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl WriteProbe for StallingWriter {
+    fn is_writable(&self) -> bool {
+        true
+    }
+}
+
+// The default probe keeps the usual meaning of a zero-length write
+impl WriteProbe for ClosingWriter {}
+
+#[test]
+fn write_probe_distinguishes_backpressure() {
+    let data = test_data(1000);
+    let mut writer = StallingWriter {
+        data: Vec::new(),
+        stalls: 3,
+    };
+
+    let mut forwarder =
+        Forwarder::new(Cursor::new(data.clone()), &mut writer, vec![0; 32]).with_write_probe();
+    block_on(&mut forwarder).unwrap();
+    assert_eq!(forwarder.would_block_writes(), 3);
+
+    assert_eq!(writer.data, data);
+
+    let result = block_on(
+        Forwarder::new(
+            Cursor::new(test_data(1000)),
+            ClosingWriter { remaining: 100 },
+            vec![0; 32],
+        )
+        .with_write_probe(),
+    );
+
+    assert!(matches!(
+        result,
+        Err(ForwarderError::WriteClosedEarly { forwarded: 100, .. })
+    ));
+}
+
+#[test]
+fn forward_borrowed() {
+    let data = test_data(1000);
+    let mut reader = Cursor::new(data.clone());
+    let mut writer = TestBuffer::new(50);
+
+    block_on(Forwarder::new(&mut reader, &mut writer, vec![0; 64])).unwrap();
+    assert_eq!(writer.data, data);
+
+    // Both ends are still usable afterwards
+    reader.set_position(500);
+    block_on(forward_ref(&mut reader, &mut writer, vec![0; 64])).unwrap();
+    assert_eq!(writer.data.len(), 1500);
+    assert_eq!(writer.data[1000..], data[500..]);
+}
+
+#[test]
+fn boxed_forwarders() {
+    let data = test_data(1000);
+
+    let forwards = vec![
+        Forwarder::new(Cursor::new(data.clone()), Vec::new(), vec![0; 64]).boxed(),
+        Forwarder::new(
+            ChunkedReader::new(data.clone(), 10),
+            TestBuffer::new(50),
+            [0; 32],
+        )
+        .boxed(),
+    ];
+
+    for result in block_on(futures::future::join_all(forwards)) {
+        result.unwrap();
+    }
+
+    // Not Send
+    let reader = Cursor::new(std::rc::Rc::<[u8]>::from(data));
+    block_on(Forwarder::new(reader, Vec::new(), vec![0; 64]).boxed_local()).unwrap();
+}
+
+#[test]
+fn yield_budget_yields() {
+    let data = test_data(1000);
+
+    // Count the polls needed to finish with and without a budget
+    let count_polls = |budget: Option<NonZeroU32>| {
+        let mut writer = TestBuffer::new(usize::MAX);
+        let mut forwarder = Forwarder::new(
+            ChunkedReader::new(data.clone(), 10),
+            &mut writer,
+            vec![0; 64],
+        );
+
+        if let Some(budget) = budget {
+            forwarder = forwarder.with_yield_budget(budget);
+        }
+
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut polls = 1;
+
+        while Pin::new(&mut forwarder).poll(&mut cx).is_pending() {
+            polls += 1;
+        }
+
+        drop(forwarder);
+        assert_eq!(writer.data, data);
+        polls
+    };
+
+    let unbudgeted = count_polls(None);
+    let budgeted = count_polls(NonZeroU32::new(2));
+
+    // Each poll does a read and a write, so a budget of 2 means we yield
+    // after nearly every poll
+    assert!(
+        budgeted > unbudgeted + unbudgeted / 2,
+        "{budgeted} vs {unbudgeted}"
+    );
+}
+
+/// A reader that never produces any data
+struct StalledReader;
+
+impl AsyncRead for StalledReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Pending
+    }
+}
+
+/// A runtime-agnostic sleep, for testing pluggable timers: a thread that
+/// completes a channel after `duration`
+fn thread_sleep(duration: Duration) -> impl Future<Output = ()> {
+    let (sender, receiver) = futures::channel::oneshot::channel();
+
+    std::thread::spawn(move || {
+        std::thread::sleep(duration);
+        let _ = sender.send(());
+    });
+
+    receiver.map(|_| ())
+}
+
+#[test]
+fn idle_timeout() {
+    let data = test_data(100);
+    let mut writer = TestBuffer::new(usize::MAX);
+    let reader = Cursor::new(data.clone()).chain(StalledReader);
+
+    let start = Instant::now();
+    let result = block_on(
+        Forwarder::new(reader, &mut writer, [0; 16])
+            .with_idle_timeout(Duration::from_millis(20), thread_sleep),
+    );
+
+    assert!(matches!(result, Err(ForwarderError::IdleTimeout)));
+    assert!(start.elapsed() >= Duration::from_millis(20));
+    assert_eq!(writer.data, data);
+}
+
+#[test]
+fn rate_limit() {
+    let data = test_data(1100);
+    let mut writer = TestBuffer::new(usize::MAX);
+    let start = Instant::now();
+
+    // The first 100 bytes are a burst, and the rest take 100ms
+    block_on(
+        Forwarder::new(Cursor::new(data.clone()), &mut writer, [0; 64]).with_rate_limit(
+            NonZeroU64::new(10_000).unwrap(),
+            NonZeroU64::new(100).unwrap(),
+            thread_sleep,
+        ),
+    )
+    .unwrap();
+
+    assert!(start.elapsed() >= Duration::from_millis(90));
+    assert_eq!(writer.data, data);
+
+    // Smooth pacing means many small reads, rather than a few big ones
+    assert!(writer.writes.len() >= 10);
+}
+
+#[test]
+fn shared_rate_limit() {
+    let data = test_data(1100);
+    let limiter = RateLimiter::new(
+        NonZeroU64::new(20_000).unwrap(),
+        NonZeroU64::new(200).unwrap(),
+    );
+
+    let mut writer1 = TestBuffer::new(usize::MAX);
+    let mut writer2 = TestBuffer::new(usize::MAX);
+    let start = Instant::now();
+
+    // Together, the forwards exceed the burst by 2000 bytes, which takes
+    // 100ms
+    let (result1, result2) = block_on(futures::future::join(
+        Forwarder::new(Cursor::new(data.clone()), &mut writer1, [0; 64])
+            .with_shared_rate_limit(limiter.clone(), thread_sleep),
+        Forwarder::new(Cursor::new(data.clone()), &mut writer2, [0; 64])
+            .with_shared_rate_limit(limiter, thread_sleep),
+    ));
+
+    assert_eq!(result1.unwrap(), 1100);
+    assert_eq!(result2.unwrap(), 1100);
+    assert!(start.elapsed() >= Duration::from_millis(90));
+    assert_eq!(writer1.data, data);
+    assert_eq!(writer2.data, data);
+}
+
+#[test]
+fn cancel_drains_buffer() {
+    let data = test_data(100);
+    let (send, recv) = futures::channel::oneshot::channel::<()>();
+
+    // The writer takes a few polls to drain the buffer
+    let mut writer = TestBuffer::new(10);
+    let reader = Cursor::new(data.clone()).chain(StalledReader);
+
+    // A budget of 2 limits each poll to a single read and write
+    let mut forwarder = Forwarder::new(reader, &mut writer, vec![0; 64])
+        .with_yield_budget(NonZeroU32::new(2).unwrap())
+        .with_cancel(recv.map(|_| ()));
+
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+
+    send.send(()).unwrap();
+    poll_until_ready(&mut forwarder).unwrap();
+
+    drop(forwarder);
+
+    // Everything that was read before the cancel was written out
+    assert_eq!(writer.data, data[..64]);
+    assert_eq!(writer.flushes, 1);
+}
+
+#[test]
+fn race_deadline_recovers_streams() {
+    let data = test_data(100);
+    let (send, recv) = futures::channel::oneshot::channel::<()>();
+
+    let mut writer = TestBuffer::new(10);
+    let reader = Cursor::new(data.clone()).chain(StalledReader);
+
+    // A budget of 2 limits each poll to a single read and write
+    let mut forwarder = Forwarder::new(reader, &mut writer, vec![0; 64])
+        .with_yield_budget(NonZeroU32::new(2).unwrap())
+        .race_deadline(recv);
+
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+
+    send.send(()).unwrap();
+
+    match Pin::new(&mut forwarder).poll(&mut cx) {
+        Poll::Ready(Err(DeadlineError::Expired {
+            copied,
+            reader,
+            writer,
+            unwritten,
+        })) => {
+            assert_eq!(copied, 10);
+            assert!(reader.is_some());
+            assert_eq!(writer.data, data[..10]);
+            assert_eq!(unwritten, data[10..64]);
+        }
+        _ => panic!("expected the deadline to expire"),
+    }
+
+    assert!(forwarder.is_terminated());
+}
+
+#[test]
+fn race_deadline_forward_wins() {
+    let data = test_data(100);
+    let mut writer = Vec::new();
+
+    let written = block_on(
+        Forwarder::new(Cursor::new(data.clone()), &mut writer, [0; 16])
+            .race_deadline(futures::future::pending::<()>()),
+    )
+    .unwrap();
+
+    assert_eq!(written, 100);
+    assert_eq!(writer, data);
+}
+
+#[test]
+fn stop_handle_drains_buffer() {
+    let data = test_data(100);
+    let mut writer = TestBuffer::new(10);
+    let reader = Cursor::new(data.clone()).chain(StalledReader);
+
+    // A budget of 2 limits each poll to a single read and write
+    let (mut forwarder, handle) = Forwarder::new(reader, &mut writer, vec![0; 64])
+        .with_yield_budget(NonZeroU32::new(2).unwrap())
+        .with_stop_handle();
+
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+
+    let stopper = handle.clone();
+    drop(handle);
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+
+    stopper.stop();
+    assert!(stopper.is_stopped());
+    let written = poll_until_ready(&mut forwarder).unwrap();
+    assert_eq!(written, 64);
+
+    drop(forwarder);
+
+    // Everything that was read before the stop was written out
+    assert_eq!(writer.data, data[..64]);
+    assert_eq!(writer.flushes, 1);
+}
+
+#[test]
+fn abort_returns_parts() {
+    let data = test_data(100);
+    let mut writer = TestBuffer::new(10);
+    let reader = Cursor::new(data.clone()).chain(StalledReader);
+
+    // A budget of 2 limits each poll to a single read and write
+    let (mut forwarder, handle) = Forwarder::new(reader, &mut writer, vec![0; 64])
+        .with_yield_budget(NonZeroU32::new(2).unwrap())
+        .abortable();
+
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+
+    handle.abort();
+    assert!(handle.is_aborted());
+
+    match Pin::new(&mut forwarder).poll(&mut cx) {
+        Poll::Ready(Err(AbortError::Aborted(parts))) => {
+            assert!(parts.reader.is_some());
+            assert_eq!(parts.writer.data, data[..10]);
+            assert_eq!(parts.buffer.len(), 64);
+            assert_eq!(parts.unwritten, data[10..64]);
+            assert_eq!(parts.bytes_read, 64);
+            assert_eq!(parts.bytes_written, 10);
+        }
+        _ => panic!("expected the forward to be aborted"),
+    }
+
+    assert!(forwarder.is_terminated());
+    assert_eq!(writer.flushes, 0);
+}
+
+#[test]
+fn into_parts_and_resume() {
+    let data = test_data(100);
+    let mut writer = TestBuffer::new(10);
+
+    // A budget of 2 limits each poll to a single read and write
+    let mut forwarder = Forwarder::new(Cursor::new(data.clone()), &mut writer, vec![0; 64])
+        .with_yield_budget(NonZeroU32::new(2).unwrap());
+
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+
+    let parts = forwarder.into_parts();
+    assert_eq!(parts.unwritten, data[10..64]);
+    assert_eq!(parts.bytes_read, 64);
+    assert_eq!(parts.bytes_written, 10);
+
+    // Pick up where we left off, with the same writer and buffer
+    let resumed = Forwarder::new_with_prefilled(
+        parts.reader.unwrap(),
+        parts.writer,
+        parts.buffer,
+        &parts.unwritten,
+    )
+    .unwrap();
+    assert_eq!(block_on(resumed).unwrap(), 90);
+
+    assert_eq!(writer.data, data);
+}
+
+#[test]
+fn reader_and_writer_accessors() {
+    let data = test_data(100);
+
+    // A budget of 2 limits each poll to a single read and write
+    let mut forwarder = Forwarder::new(Cursor::new(data.clone()), TestBuffer::new(10), [0; 64])
+        .with_yield_budget(NonZeroU32::new(2).unwrap());
+
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+
+    assert_eq!(forwarder.get_reader().unwrap().position(), 64);
+    assert_eq!(forwarder.get_writer().data, data[..10]);
+
+    forwarder.get_writer_mut().chunk = usize::MAX;
+    assert!(Pin::new(&mut forwarder).get_reader_pin_mut().is_some());
+
+    assert_eq!(poll_until_ready(&mut forwarder).unwrap(), 100);
+    assert!(forwarder.get_reader().is_none());
+    assert_eq!(Pin::new(&mut forwarder).get_writer_pin_mut().data, data);
+}
+
+#[test]
+fn pause_and_resume() {
+    let data = test_data(100);
+    let mut writer = TestBuffer::new(10);
+
+    // A budget of 2 limits each poll to a single read and write
+    let (mut forwarder, handle) = Forwarder::new(Cursor::new(data.clone()), &mut writer, [0; 64])
+        .with_yield_budget(NonZeroU32::new(2).unwrap())
+        .with_pause_handle();
+
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+
+    handle.pause();
+    assert!(handle.is_paused());
+
+    // Nothing moves while we're paused
+    for _ in 0..10 {
+        assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+    }
+    assert_eq!(forwarder.debug_state().bytes_written, 10);
+
+    handle.resume();
+    assert_eq!(poll_until_ready(&mut forwarder).unwrap(), 100);
+
+    drop(forwarder);
+    assert_eq!(writer.data, data);
+}
+
+#[test]
+fn detach_reader_drains_buffer() {
+    let data = test_data(100);
+    let mut writer = TestBuffer::new(10);
+    let reader = Cursor::new(data.clone()).chain(StalledReader);
+
+    // A budget of 2 limits each poll to a single read and write
+    let mut forwarder = Forwarder::new(reader, &mut writer, vec![0; 64])
+        .with_yield_budget(NonZeroU32::new(2).unwrap());
+
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+
+    let reader = Pin::new(&mut forwarder).detach_reader().unwrap();
+    assert!(Pin::new(&mut forwarder).detach_reader().is_none());
+    assert_eq!(poll_until_ready(&mut forwarder).unwrap(), 64);
+
+    drop(forwarder);
+
+    // The reader picks up where the forward left off
+    let (cursor, _) = reader.into_inner();
+    assert_eq!(cursor.position(), 64);
+    assert_eq!(writer.data, data[..64]);
+    assert_eq!(writer.flushes, 1);
+}
+
+#[test]
+fn live_stats() {
+    let data = test_data(20);
+    let reader = Cursor::new(data.clone()).chain(StalledReader);
+    let mut forwarder = Forwarder::new(reader, TestBuffer::new(10), [0; 64]);
+
+    let stats = forwarder.stats();
+    assert_eq!(stats.bytes_read, 0);
+    assert_eq!(stats.waiting_on, WaitingOn::Nothing);
+
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+
+    assert_eq!(
+        forwarder.stats(),
+        ForwardStats {
+            bytes_read: 20,
+            bytes_written: 20,
+            buffered: 0,
+            reads: 1,
+            writes: 2,
+            reader_eof: false,
+            waiting_on: WaitingOn::Reader,
+        }
+    );
+
+    let mut forwarder = Forwarder::new(Cursor::new(data), TestBuffer::new(10), [0; 64]);
+    poll_until_ready(&mut forwarder).unwrap();
+
+    let stats = forwarder.stats();
+    assert!(stats.reader_eof);
+    assert_eq!(stats.waiting_on, WaitingOn::Nothing);
+}
+
+#[test]
+fn completion_summary() {
+    let data = test_data(100);
+    let mut writer = TestBuffer::new(10);
+
+    let summary =
+        block_on(Forwarder::new(Cursor::new(data.clone()), &mut writer, [0; 64]).summarize())
+            .unwrap();
+
+    assert_eq!(summary.bytes_read, 100);
+    assert_eq!(summary.bytes_written, 100);
+    assert!(summary.reads >= 2);
+    assert_eq!(summary.writes, 10);
+    assert!(summary.time_to_first_byte.unwrap() <= summary.duration);
+    assert!(summary.throughput() > 0.0);
+    assert_eq!(writer.data, data);
+}
+
+#[test]
+fn progress_stream() {
+    let data = test_data(100);
+    let mut writer = TestBuffer::new(10);
+
+    let events: Vec<_> = block_on(
+        Forwarder::new(Cursor::new(data.clone()), &mut writer, [0; 64])
+            .into_progress_stream()
+            .collect(),
+    );
+
+    let (last, progress) = events.split_last().unwrap();
+    assert!(matches!(
+        last,
+        Ok(ProgressEvent::Complete { bytes_written: 100 })
+    ));
+
+    let (read, written) = progress
+        .iter()
+        .fold((0, 0), |(read, written), event| match event {
+            Ok(ProgressEvent::Progress(progress)) => {
+                (read + progress.read, written + progress.wrote)
+            }
+            _ => panic!("unexpected event: {event:?}"),
+        });
+
+    assert_eq!((read, written), (100, 100));
+    assert_eq!(writer.data, data);
+}
+
+#[test]
+fn read_and_write_callbacks() {
+    let data = test_data(100);
+    let mut writer = TestBuffer::new(10);
+
+    let reads = Arc::new(Mutex::new(Vec::new()));
+    let writes = Arc::new(Mutex::new(Vec::new()));
+
+    block_on(
+        Forwarder::new(Cursor::new(data.clone()), &mut writer, [0; 64])
+            .on_read({
+                let reads = reads.clone();
+                move |n| reads.lock().unwrap().push(n)
+            })
+            .on_write({
+                let writes = writes.clone();
+                move |n| writes.lock().unwrap().push(n)
+            }),
+    )
+    .unwrap();
+
+    assert_eq!(reads.lock().unwrap().iter().sum::<usize>(), 100);
+    assert_eq!(*writes.lock().unwrap(), writer.writes);
+    assert_eq!(writer.data, data);
+}
+
+#[test]
+fn inspect_every_chunk() {
+    let data = test_data(1000);
+    let mut writer = TestBuffer::new(7);
+    let seen = Arc::new(Mutex::new(Vec::new()));
+
+    block_on(
+        Forwarder::new(ChunkedReader::new(data.clone(), 13), &mut writer, [0; 64]).inspect({
+            let seen = seen.clone();
+            move |chunk| seen.lock().unwrap().extend_from_slice(chunk)
+        }),
+    )
+    .unwrap();
+
+    assert_eq!(*seen.lock().unwrap(), data);
+    assert_eq!(writer.data, data);
+}
+
+#[test]
+fn xor_mask_in_place() {
+    let data = test_data(1000);
+    let mask = [0x12, 0x34, 0x56, 0x78];
+    let mut writer = TestBuffer::new(7);
+
+    let mut offset = 0;
+    block_on(
+        Forwarder::new(ChunkedReader::new(data.clone(), 13), &mut writer, [0; 64]).map_in_place(
+            move |chunk| {
+                for byte in chunk {
+                    *byte ^= mask[offset % 4];
+                    offset += 1;
+                }
+            },
+        ),
+    )
+    .unwrap();
+
+    let masked: Vec<u8> = data
+        .iter()
+        .enumerate()
+        .map(|(i, byte)| byte ^ mask[i % 4])
+        .collect();
+
+    assert_eq!(writer.data, masked);
+}
+
+/// A toy stream cipher, with a keystream from a linear congruential
+/// generator
+struct ToyCipher {
+    state: u32,
+}
+
+impl ToyCipher {
+    fn apply_keystream(&mut self, data: &mut [u8]) {
+        for byte in data {
+            self.state = self.state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            *byte ^= (self.state >> 16) as u8;
+        }
+    }
+}
+
+#[test]
+fn stream_cipher_round_trip() {
+    let data = test_data(1000);
+
+    let mut encrypted = TestBuffer::new(7);
+    let mut cipher = ToyCipher { state: 42 };
+    block_on(
+        Forwarder::new(
+            ChunkedReader::new(data.clone(), 13),
+            &mut encrypted,
+            [0; 64],
+        )
+        .map_in_place(move |chunk| cipher.apply_keystream(chunk)),
+    )
+    .unwrap();
+
+    assert_ne!(encrypted.data, data);
+
+    let mut decrypted = TestBuffer::new(11);
+    let mut cipher = ToyCipher { state: 42 };
+    block_on(
+        Forwarder::new(
+            ChunkedReader::new(encrypted.data, 17),
+            &mut decrypted,
+            [0; 48],
+        )
+        .map_in_place(move |chunk| cipher.apply_keystream(chunk)),
+    )
+    .unwrap();
+
+    assert_eq!(decrypted.data, data);
+}
+
+/// A trivial checksum that records everything it's fed
+#[derive(Default)]
+struct Recorder(Vec<u8>);
+
+impl Checksum for Recorder {
+    fn update(&mut self, data: &[u8]) {
+        self.0.extend_from_slice(data);
+    }
+}
+
+#[test]
+fn checksum_every_byte() {
+    let data = test_data(1000);
+    let mut writer = TestBuffer::new(7);
+
+    // The checksum sees the data after it's been mapped
+    let (written, recorder) = block_on(
+        Forwarder::new(ChunkedReader::new(data.clone(), 13), &mut writer, [0; 64])
+            .map_in_place(|chunk| chunk.iter_mut().for_each(|byte| *byte ^= 0xFF))
+            .checksum(Recorder::default()),
+    )
+    .unwrap();
+
+    assert_eq!(written, 1000);
+    assert_eq!(recorder.0, writer.data);
+    assert_eq!(
+        recorder.0,
+        data.iter().map(|byte| byte ^ 0xFF).collect::<Vec<u8>>()
+    );
+}
+
+#[test]
+fn verify_checksum() {
+    let data = test_data(1000);
+
+    let mut writer = TestBuffer::new(7);
+    let written =
+        block_on(
+            Forwarder::new(ChunkedReader::new(data.clone(), 13), &mut writer, [0; 64])
+                .verify_checksum(Recorder::default(), |recorder| recorder.0, data.clone()),
+        )
+        .unwrap();
+
+    assert_eq!(written, 1000);
+
+    let mut writer = TestBuffer::new(7);
+    let err =
+        block_on(
+            Forwarder::new(ChunkedReader::new(data.clone(), 13), &mut writer, [0; 64])
+                .verify_checksum(Recorder::default(), |recorder| recorder.0, &data[..999]),
+        )
+        .unwrap_err();
+
+    match err {
+        ForwarderError::DigestMismatch { expected, actual } => {
+            assert_eq!(expected, data[..999]);
+            assert_eq!(actual, data);
+        }
+        err => panic!("unexpected error: {err:?}"),
+    }
+
+    // The mismatch is only found after everything was written
+    assert_eq!(writer.data, data);
+}
+
+#[cfg(feature = "digest")]
+#[test]
+fn sha256_checksum() {
+    use async_forward::Digest;
+    use sha2::{Digest as _, Sha256};
+
+    let data = test_data(1000);
+    let mut writer = TestBuffer::new(7);
+
+    let (written, Digest(hasher)) = block_on(
+        Forwarder::new(ChunkedReader::new(data.clone(), 13), &mut writer, [0; 64])
+            .checksum(Digest(Sha256::new())),
+    )
+    .unwrap();
+
+    assert_eq!(written, 1000);
+    assert_eq!(hasher.finalize(), Sha256::digest(&data));
+
+    let mut writer = TestBuffer::new(7);
+    let result = block_on(
+        Forwarder::new(ChunkedReader::new(data.clone(), 13), &mut writer, [0; 64])
+            .verify_digest::<Sha256>(Sha256::digest(&data).to_vec()),
+    );
+
+    assert_eq!(result.unwrap(), 1000);
+}
+
+/// A writer that accepts `capacity` bytes, a few at a time, and then fails
+struct FailingWriter {
+    data: Vec<u8>,
+    capacity: usize,
+}
+
+impl FailingWriter {
+    fn new(capacity: usize) -> Self {
+        Self {
+            data: Vec::new(),
+            capacity,
+        }
+    }
+}
+
+impl AsyncWrite for FailingWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        if this.data.len() >= this.capacity {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+
+        let n = buf.len().min(7).min(this.capacity - this.data.len());
+        this.data.extend_from_slice(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[test]
+fn failover_to_fallback_writers() {
+    let data = test_data(1000);
+    let mut primary = FailingWriter::new(100);
+    let mut backup = FailingWriter::new(300);
+    let mut last = FailingWriter::new(usize::MAX);
+
+    let mut forward = Forwarder::new(Cursor::new(data.clone()), &mut primary, [0; 64])
+        .with_failover([&mut backup, &mut last]);
+
+    assert_eq!(block_on(&mut forward).unwrap(), 1000);
+    assert_eq!(forward.failovers(), 2);
+    drop(forward);
+
+    // Nothing was lost or repeated at either switch
+    assert_eq!(primary.data, data[..100]);
+    assert_eq!(backup.data, data[100..400]);
+    assert_eq!(last.data, data[400..]);
+}
+
+#[test]
+fn failover_runs_out_of_fallbacks() {
+    let mut primary = FailingWriter::new(100);
+    let mut backup = FailingWriter::new(100);
+
+    let err = block_on(
+        Forwarder::new(Cursor::new(test_data(1000)), &mut primary, [0; 64])
+            .with_failover([&mut backup]),
+    )
+    .unwrap_err();
+
+    assert!(matches!(err, ForwarderError::Write { .. }));
+}
+
+/// A connection that sends everything written to it to a shared sink, and
+/// fails once it's accepted `capacity` bytes
+struct FlakyConnection {
+    sink: Arc<Mutex<Vec<u8>>>,
+    capacity: usize,
+}
+
+impl AsyncWrite for FlakyConnection {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        if this.capacity == 0 {
+            return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+        }
+
+        let n = buf.len().min(7).min(this.capacity);
+        this.sink.lock().unwrap().extend_from_slice(&buf[..n]);
+        this.capacity -= n;
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[test]
+fn reconnect_with_backoff() {
+    let data = test_data(1000);
+    let sink = Arc::new(Mutex::new(Vec::new()));
+    let delays = Arc::new(Mutex::new(Vec::new()));
+
+    // Every other connection attempt fails
+    let mut attempts = 0;
+    let connector = || {
+        attempts += 1;
+        let connection = FlakyConnection {
+            sink: sink.clone(),
+            capacity: 300,
+        };
+
+        future::ready(match attempts % 2 {
+            0 => Ok(connection),
+            _ => Err(io::ErrorKind::ConnectionRefused.into()),
+        })
+    };
+
+    let first = FlakyConnection {
+        sink: sink.clone(),
+        capacity: 100,
+    };
+
+    let mut forward = Forwarder::new(Cursor::new(data.clone()), first, [0; 64]).with_reconnect(
+        connector,
+        Backoff::new(Duration::from_millis(10), 3),
+        {
+            let delays = delays.clone();
+            move |delay| {
+                delays.lock().unwrap().push(delay);
+                future::ready(())
+            }
+        },
+    );
+
+    assert_eq!(block_on(&mut forward).unwrap(), 1000);
+    assert_eq!(forward.reconnects(), 3);
+    drop(forward);
+
+    // Nothing was lost or repeated across the reconnects
+    assert_eq!(*sink.lock().unwrap(), data);
+
+    // The delay doubles after a failed attempt, and starts over once a new
+    // connection makes progress
+    let ms = Duration::from_millis;
+    assert_eq!(
+        *delays.lock().unwrap(),
+        [ms(10), ms(20), ms(10), ms(20), ms(10), ms(20)]
+    );
+}
+
+#[test]
+fn reconnect_gives_up() {
+    let first = FlakyConnection {
+        sink: Arc::new(Mutex::new(Vec::new())),
+        capacity: 100,
+    };
+
+    let err = block_on(
+        Forwarder::new(Cursor::new(test_data(1000)), first, [0; 64]).with_reconnect(
+            || future::ready(Err(io::ErrorKind::ConnectionRefused.into())),
+            Backoff::new(Duration::from_millis(10), 3),
+            |_| future::ready(()),
+        ),
+    )
+    .unwrap_err();
+
+    assert_eq!(err.into_io_error().kind(), io::ErrorKind::ConnectionRefused);
+}
+
+#[test]
+fn debug_state_snapshot() {
+    let data = test_data(100);
+    let mut writer = TestBuffer::new(10);
+    let reader = Cursor::new(data).chain(StalledReader);
+
+    // A budget of 2 limits each poll to a single read and write
+    let mut forwarder = Forwarder::new(reader, &mut writer, vec![0; 64])
+        .with_yield_budget(NonZeroU32::new(2).unwrap());
+
+    let state = forwarder.debug_state();
+    assert_eq!(state.phase, ForwarderPhase::Forwarding);
+    assert_eq!(state.buffered, 0);
+    assert_eq!(state.capacity, 64);
+    assert_eq!(state.write_head, None);
+
+    // One poll fills the buffer, then writes 10 bytes out of it
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+
+    let state = forwarder.debug_state();
+    assert_eq!(state.phase, ForwarderPhase::Forwarding);
+    assert_eq!(state.read_head, Some(0));
+    assert_eq!(state.write_head, Some(10));
+    assert_eq!(state.buffered, 54);
+    assert_eq!(state.bytes_read, 64);
+    assert_eq!(state.bytes_written, 10);
+}
+
+#[test]
+fn compact_buffered_data() {
+    let data = test_data(100);
+    let mut writer = TestBuffer::new(10);
+    let reader = Cursor::new(data.clone()).chain(StalledReader);
+
+    let mut forwarder = Forwarder::new(reader, &mut writer, vec![0; 16])
+        .with_yield_budget(NonZeroU32::new(2).unwrap());
+
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    // Each poll does a single read and write, which moves the buffered data
+    // around the ring
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+
+    let state = forwarder.debug_state();
+    let start = state.bytes_written as usize;
+    let end = state.bytes_read as usize;
+
+    assert_eq!(Pin::new(&mut forwarder).compact_buffer(), &data[start..end]);
+    assert_eq!(forwarder.debug_state().write_head, Some(0));
+}
+
+#[test]
+fn report_progress_per_poll() {
+    let data = test_data(30);
+    let mut writer = TestBuffer::new(10);
+
+    // A budget of 2 limits each poll to a single read and write
+    let mut forwarder = Forwarder::new(Cursor::new(data.clone()), &mut writer, vec![0; 16])
+        .with_yield_budget(NonZeroU32::new(2).unwrap());
+
+    let mut progress = Vec::new();
+
+    loop {
+        match poll_until_ready(&mut futures::future::poll_fn(|cx| {
+            Pin::new(&mut forwarder).poll_progress(cx)
+        }))
+        .unwrap()
+        {
+            ForwardProgress {
+                wrote: 0,
+                reader_eof: true,
+                ..
+            } => break,
+            step => progress.push(step),
+        }
+    }
+
+    assert_eq!(
+        progress.first(),
+        Some(&ForwardProgress {
+            read: 16,
+            wrote: 10,
+            reader_eof: false,
+        })
+    );
+    assert_eq!(progress.iter().map(|step| step.read).sum::<usize>(), 30);
+    assert_eq!(progress.iter().map(|step| step.wrote).sum::<usize>(), 30);
+    assert_eq!(forwarder.debug_state().phase, ForwarderPhase::Finished);
+
+    drop(forwarder);
+    assert_eq!(writer.data, data);
+}
+
+#[test]
+fn forward_with_borrowed_buffer() {
+    let data = test_data(1000);
+    let mut arena = vec![0; 64 * 1024];
+
+    // Lend a slice of a bigger arena to each forward in turn
+    for region in arena.chunks_mut(8192).take(2) {
+        let mut writer = Vec::new();
+        block_on(Forwarder::new(
+            Cursor::new(data.clone()),
+            &mut writer,
+            region,
+        ))
+        .unwrap();
+        assert_eq!(writer, data);
+    }
+
+    // The arena is still usable once the forwards are done
+    assert_eq!(arena[..1000], data);
+}
+
+#[test]
+fn refill_freed_space_in_same_poll() {
+    let data = test_data(100);
+    let mut writer = Vec::new();
+    let mut forwarder = Forwarder::new(Cursor::new(data.clone()), &mut writer, vec![0; 16]);
+
+    // Each write frees up the whole buffer, so the reader refills it
+    // without waiting for another poll
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    let _ = Pin::new(&mut forwarder).poll(&mut cx);
+
+    assert_eq!(forwarder.debug_state().bytes_written, 100);
+}
+
+/// A waker that counts how many times it's been woken
+struct CountingWaker(std::sync::atomic::AtomicUsize);
+
+impl futures::task::ArcWake for CountingWaker {
+    fn wake_by_ref(arc_self: &std::sync::Arc<Self>) {
+        arc_self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn no_self_wake_while_reader_is_pending() {
+    let mut writer = TestBuffer::new(3);
+    let reader = Cursor::new(b"hello".to_vec()).chain(StalledReader);
+    let mut forwarder = Forwarder::new(reader, &mut writer, vec![0; 16]);
+
+    let counter = std::sync::Arc::new(CountingWaker(Default::default()));
+    let waker = futures::task::waker(counter.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    // The second write drains the buffer after the reader has already
+    // registered the waker, so there's nothing to wake up for
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+    assert_eq!(forwarder.debug_state().bytes_written, 5);
+    assert_eq!(counter.0.load(std::sync::atomic::Ordering::SeqCst), 0);
+}
+
+#[test]
+fn fill_wrapped_space_without_vectored_reads() {
+    let data = test_data(100);
+    let mut writer = TestBuffer::new(10);
+
+    // ChunkedReader only implements poll_read, so its vectored reads only
+    // fill the first slice. A budget of 2 limits each poll to a single read
+    // and write.
+    let mut forwarder = Forwarder::new(ChunkedReader::new(data, 12), &mut writer, vec![0; 16])
+        .with_yield_budget(NonZeroU32::new(2).unwrap());
+
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    // Read 12 bytes, then write 10 of them, leaving free space on both sides
+    // of the ring's wrap point
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+    assert_eq!(forwarder.debug_state().read_head, Some(12));
+
+    // The yield
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+
+    // The read fills the space before the wrap point, then follows up to
+    // fill the space after it
+    assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+    assert_eq!(forwarder.debug_state().bytes_read, 26);
+}
+
+#[test]
+fn forward_with_overflow() {
+    let data = test_data(10_000);
+    let mut writer = TestBuffer::new(50);
+
+    block_on(
+        Forwarder::new(Cursor::new(data.clone()), &mut writer, vec![0; 32]).with_overflow(vec![
+            vec![0; 16].into_boxed_slice(),
+            vec![0; 24].into_boxed_slice(),
+        ]),
+    )
+    .unwrap();
+
+    assert_eq!(writer.data, data);
+
+    // Reads can be as big as the ring plus the overflow, so some writes
+    // should have been bigger than the ring
+    assert!(writer.writes.iter().any(|&n| n > 32));
+}
+
+#[test]
+fn forward_from_pool() {
+    let data = test_data(1000);
+    let pool = VecPool::new(64);
+
+    for _ in 0..3 {
+        let mut writer = TestBuffer::new(50);
+
+        let forwarder = Forwarder::from_pool(Cursor::new(data.clone()), &mut writer, &pool);
+        assert_eq!(pool.available(), 0);
+
+        block_on(forwarder).unwrap();
+        assert_eq!(writer.data, data);
+
+        // The one buffer is reused for every forward
+        assert_eq!(pool.available(), 1);
+    }
+}
+
+#[test]
+fn complete_on_first_poll_at_eof() {
+    let mut writer = TestBuffer::new(50);
+    let mut forwarder = Forwarder::new(Cursor::new(Vec::new()), &mut writer, vec![0; 64]);
+
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert!(matches!(
+        Pin::new(&mut forwarder).poll(&mut cx),
+        Poll::Ready(Ok(0))
+    ));
+
+    drop(forwarder);
+    assert!(writer.writes.is_empty());
+}
+
+#[test]
+fn drain_after_eof_over_several_polls() {
+    let data = test_data(64);
+    let mut writer = TestBuffer::new(10);
+    let mut forwarder = Forwarder::new(Cursor::new(data.clone()), &mut writer, vec![0; 64]);
+
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    // The first poll fills the buffer, writes 10 bytes, and finds the EOF;
+    // after that, each poll writes 10 more bytes until the buffer is drained
+    let mut polls = 1;
+    while Pin::new(&mut forwarder).poll(&mut cx).is_pending() {
+        assert_eq!(forwarder.debug_state().bytes_read, 64);
+        polls += 1;
+    }
+
+    assert_eq!(forwarder.debug_state().phase, ForwarderPhase::Finished);
+    assert_eq!(polls, 6);
+
+    drop(forwarder);
+    assert_eq!(writer.data, data);
+}
+
+#[test]
+fn finished_after_closing() {
+    let data = test_data(20);
+    let mut writer = TestBuffer::new(10);
+    let mut forwarder =
+        Forwarder::new(Cursor::new(data.clone()), &mut writer, vec![0; 64]).half_close_on_eof(true);
+
+    poll_until_ready(&mut forwarder).unwrap();
+    assert_eq!(forwarder.debug_state().phase, ForwarderPhase::Finished);
+
+    drop(forwarder);
+    assert_eq!(writer.data, data);
+    assert!(writer.closed);
+}
+
+#[test]
+fn writer_closes_after_last_byte() {
+    let data = test_data(1000);
+
+    // The writer would fail the next write, but there isn't one
+    block_on(Forwarder::new(
+        Cursor::new(data),
+        ClosingWriter { remaining: 1000 },
+        vec![0; 32],
+    ))
+    .unwrap();
+}
+
+#[test]
+fn forward_prefilled() {
+    let data = test_data(1000);
+    let mut writer = TestBuffer::new(50);
+
+    let forwarder = Forwarder::new_with_prefilled(
+        Cursor::new(data[100..].to_vec()),
+        &mut writer,
+        UninitBuffer::new(128),
+        &data[..100],
+    )
+    .unwrap();
+    block_on(forwarder).unwrap();
+
+    assert_eq!(writer.data, data);
+
+    let result = Forwarder::new_with_prefilled(Cursor::new(Vec::new()), Vec::new(), [0; 16], &data);
+    assert!(matches!(
+        result,
+        Err(PrefillTooLarge {
+            len: 1000,
+            capacity: 16
+        })
+    ));
+}
+
+/// A writer that's never ready for more data
+struct NotReadyWriter;
+
+impl AsyncWrite for NotReadyWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Pending
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl WriteProbe for NotReadyWriter {
+    fn poll_write_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Pending
+    }
+}
+
+#[test]
+fn grow_adaptive_buffer_while_full() {
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    let mut forwarder =
+        Forwarder::adaptive_buffer(Cursor::new(test_data(1000)), NotReadyWriter, 16, 64);
+
+    // The writer never takes anything, so the buffer keeps filling up, and
+    // it doubles every few polls until it reaches the cap
+    let mut capacities = Vec::new();
+    for _ in 0..30 {
+        assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+        capacities.push(forwarder.debug_state().capacity);
+    }
+    capacities.dedup();
+
+    assert_eq!(capacities, [16, 32, 64]);
+    assert_eq!(forwarder.debug_state().bytes_read, 64);
+}
+
+#[test]
+fn shrink_adaptive_buffer_when_idle() {
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    let data = test_data(200);
+    let mut writer = StallingWriter {
+        data: Vec::new(),
+        stalls: 12,
+    };
+    let reader = Cursor::new(data.clone()).chain(StalledReader);
+
+    let mut forwarder = Forwarder::adaptive_buffer(reader, &mut writer, 16, 64)
+        .shrink_when_idle(true)
+        .with_write_probe();
+
+    // The buffer grows while the writer is stalled, then shrinks back down
+    // once everything has been written and the reader is idle
+    let mut max_capacity = 0;
+    while forwarder.debug_state().bytes_written < 200 {
+        assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+        max_capacity = max_capacity.max(forwarder.debug_state().capacity);
+    }
+
+    assert!(max_capacity > 16);
+    assert_eq!(forwarder.debug_state().capacity, 16);
+
+    drop(forwarder);
+    assert_eq!(writer.data, data);
+}
+
+#[test]
+fn skip_reads_while_writer_not_ready() {
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    let mut forwarder = Forwarder::new(
+        ChunkedReader::new(test_data(100), 10),
+        NotReadyWriter,
+        vec![0; 64],
+    )
+    .with_write_probe();
+    assert!(Pin::new(&mut forwarder)
+        .poll_writer_ready(&mut cx)
+        .is_pending());
+
+    // The first read happens while the buffer is empty, but after that,
+    // the buffer isn't filled any further
+    for _ in 0..3 {
+        assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+    }
+
+    assert_eq!(forwarder.debug_state().bytes_read, 10);
+
+    // Without the probe, reads continue until the buffer is full
+    let mut forwarder = Forwarder::new(
+        ChunkedReader::new(test_data(100), 10),
+        NotReadyWriter,
+        vec![0; 64],
+    );
+    assert!(Pin::new(&mut forwarder)
+        .poll_writer_ready(&mut cx)
+        .is_ready());
+
+    for _ in 0..10 {
+        assert!(Pin::new(&mut forwarder).poll(&mut cx).is_pending());
+    }
+
+    assert_eq!(forwarder.debug_state().bytes_read, 64);
+}
+
+#[test]
+fn forwarder_is_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    assert_send_sync(&Forwarder::new(&b"hello"[..], Vec::new(), [0; 8]));
+}
+
+#[test]
+fn cancel_with_local_signal() {
+    // The signal doesn't need to be Send, as long as the forward isn't
+    let signal = std::rc::Rc::new(());
+    let reader = Cursor::new(test_data(100)).chain(StalledReader);
+
+    block_on(
+        Forwarder::new(reader, Vec::new(), vec![0; 64])
+            .with_cancel(async move {
+                drop(signal);
+            })
+            .boxed_local(),
+    )
+    .unwrap();
 }
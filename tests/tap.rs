@@ -0,0 +1,81 @@
+use std::pin::Pin;
+
+use async_forward::ForwardTap;
+use futures::{
+    executor::block_on, future::poll_fn, io::Cursor, AsyncBufRead, AsyncBufReadExt, AsyncReadExt,
+};
+
+#[test]
+fn sniff_then_forward() {
+    block_on(async {
+        let data = b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n".to_vec();
+        let mut out = Vec::new();
+        let mut tap = ForwardTap::new(Cursor::new(data.clone()), &mut out, [0; 16]);
+
+        // Peek at the method without consuming it
+        let peeked = tap.fill_buf().await.unwrap();
+        assert!(peeked.starts_with(b"GET "));
+        assert_eq!(tap.released(), 0);
+
+        // Consume everything, which lets it all through to the writer
+        let mut inspected = Vec::new();
+        tap.read_to_end(&mut inspected).await.unwrap();
+        assert_eq!(inspected, data);
+
+        poll_fn(|cx| Pin::new(&mut tap).poll_finish(cx))
+            .await
+            .unwrap();
+        assert_eq!(tap.released(), 0);
+
+        drop(tap);
+        assert_eq!(out, data);
+    })
+}
+
+#[test]
+fn unconsumed_data_is_not_written() {
+    block_on(async {
+        let mut out = Vec::new();
+        let mut tap = ForwardTap::new(Cursor::new(b"hello, world".to_vec()), &mut out, [0; 32]);
+
+        assert_eq!(tap.fill_buf().await.unwrap(), b"hello, world");
+        Pin::new(&mut tap).consume(5);
+
+        // Only the consumed data is released; the rest is still available
+        // to be inspected
+        assert_eq!(tap.fill_buf().await.unwrap(), b", world");
+
+        poll_fn(|cx| Pin::new(&mut tap).poll_finish(cx))
+            .await
+            .unwrap();
+
+        drop(tap);
+        assert_eq!(out, b"hello");
+    })
+}
+
+#[test]
+fn tap_wraps_ring() {
+    block_on(async {
+        let data: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let mut out = Vec::new();
+        let mut tap = ForwardTap::new(Cursor::new(data.clone()), &mut out, [0; 7]);
+
+        // Consume a few bytes at a time, so that the inspected region moves
+        // around the ring
+        loop {
+            let n = tap.fill_buf().await.unwrap().len().min(3);
+            if n == 0 {
+                break;
+            }
+            Pin::new(&mut tap).consume(n);
+        }
+
+        poll_fn(|cx| Pin::new(&mut tap).poll_finish(cx))
+            .await
+            .unwrap();
+
+        drop(tap);
+        assert_eq!(out, data);
+    })
+}
@@ -0,0 +1,106 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_forward::{ForwarderError, TeeForwarder};
+use futures::{executor::block_on, io, io::Cursor, AsyncWrite, Future};
+
+fn test_data(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+/// A writer that only accepts up to `chunk` bytes per write, and that can
+/// be made to stop accepting anything after `limit` bytes
+struct SlowWriter {
+    data: Vec<u8>,
+    chunk: usize,
+    limit: usize,
+    flushed: bool,
+}
+
+impl SlowWriter {
+    fn new(chunk: usize) -> Self {
+        Self {
+            data: Vec::new(),
+            chunk,
+            limit: usize::MAX,
+            flushed: false,
+        }
+    }
+}
+
+impl AsyncWrite for SlowWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let room = self.limit - self.data.len();
+        let n = buf.len().min(self.chunk).min(room);
+        self.data.extend_from_slice(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.flushed = true;
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[test]
+fn tee_to_every_writer() {
+    let data = test_data(1000);
+    let writers = [1, 7, 64].map(SlowWriter::new);
+
+    // The buffer is smaller than the data, so it has to wrap, and the fast
+    // writers have to wait for the slow one
+    let mut tee = TeeForwarder::new(Cursor::new(data.clone()), writers, [0; 32]);
+    let forwarded = block_on(&mut tee).unwrap();
+    assert_eq!(forwarded, 1000);
+
+    for writer in tee.into_writers() {
+        assert_eq!(writer.data, data);
+        assert!(writer.flushed);
+    }
+}
+
+#[test]
+fn tee_fails_if_any_writer_closes() {
+    let data = test_data(1000);
+    let mut closing = SlowWriter::new(16);
+    closing.limit = 100;
+
+    let writers = vec![SlowWriter::new(16), closing];
+    let err = block_on(TeeForwarder::new(
+        Cursor::new(data.clone()),
+        writers,
+        [0; 32],
+    ))
+    .unwrap_err();
+
+    assert_eq!(err.writer, Some(1));
+    match err.source {
+        ForwarderError::WriteClosedEarly { forwarded, .. } => assert_eq!(forwarded, 100),
+        err => panic!("unexpected error: {err:?}"),
+    }
+}
+
+#[test]
+fn tee_yields_to_executor() {
+    // The reader and writers are always ready, so the forward never ends,
+    // but each poll has to
+    let mut tee = TeeForwarder::new(io::repeat(7), vec![io::sink(), io::sink()], vec![0; 64]);
+
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    for _ in 0..3 {
+        assert!(Pin::new(&mut tee).poll(&mut cx).is_pending());
+    }
+    assert!(tee.bytes_read() > 0);
+}
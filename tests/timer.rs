@@ -0,0 +1,182 @@
+#![cfg(feature = "futures-timer")]
+
+use std::{
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use async_forward::{Forwarder, ForwarderBuilder, ForwarderError};
+use futures::{executor::block_on, AsyncRead, AsyncWrite};
+
+/// A reader that never produces any data
+struct StalledReader;
+
+impl AsyncRead for StalledReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Pending
+    }
+}
+
+#[test]
+fn deadline_exceeded() {
+    let mut writer = Vec::new();
+    let start = Instant::now();
+
+    let result = block_on(
+        Forwarder::new(StalledReader, &mut writer, [0; 16])
+            .with_deadline(start + Duration::from_millis(20)),
+    );
+
+    assert!(matches!(result, Err(ForwarderError::DeadlineExceeded)));
+    assert!(start.elapsed() >= Duration::from_millis(20));
+}
+
+#[test]
+fn deadline_not_reached() {
+    let mut writer = Vec::new();
+
+    block_on(
+        Forwarder::new(&b"hello"[..], &mut writer, [0; 16])
+            .with_deadline(Instant::now() + Duration::from_secs(60)),
+    )
+    .unwrap();
+
+    assert_eq!(writer, b"hello");
+}
+
+#[test]
+fn idle_timeout() {
+    let mut writer = Vec::new();
+    let reader = futures::AsyncReadExt::chain(&b"hello"[..], StalledReader);
+    let start = Instant::now();
+
+    let result = block_on(
+        Forwarder::new(reader, &mut writer, [0; 16]).idle_timeout(Duration::from_millis(20)),
+    );
+
+    assert!(matches!(result, Err(ForwarderError::IdleTimeout)));
+    assert!(start.elapsed() >= Duration::from_millis(20));
+    assert_eq!(writer, b"hello");
+}
+
+#[test]
+fn complete_on_idle() {
+    let mut writer = Vec::new();
+    let reader = futures::AsyncReadExt::chain(&b"hello"[..], StalledReader);
+    let start = Instant::now();
+
+    block_on(
+        Forwarder::new(reader, &mut writer, [0; 16]).complete_on_idle(Duration::from_millis(20)),
+    )
+    .unwrap();
+
+    assert_eq!(writer, b"hello");
+    assert!(start.elapsed() >= Duration::from_millis(20));
+}
+
+/// A writer that never accepts any data
+struct StalledWriter;
+
+impl AsyncWrite for StalledWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Pending
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[test]
+fn stall_warning() {
+    let warnings = Arc::new(Mutex::new(Vec::new()));
+    let log = warnings.clone();
+
+    let result = block_on(
+        Forwarder::new(&[1; 100][..], StalledWriter, [0; 16])
+            .stall_warn_after(Duration::from_millis(10), move |buffered, elapsed| {
+                log.lock().unwrap().push((buffered, elapsed))
+            })
+            .with_deadline(Instant::now() + Duration::from_millis(50)),
+    );
+
+    // The stall doesn't fail the forward by itself
+    assert!(matches!(result, Err(ForwarderError::DeadlineExceeded)));
+
+    let warnings = warnings.lock().unwrap();
+    assert!(!warnings.is_empty());
+
+    for &(buffered, elapsed) in warnings.iter() {
+        assert_eq!(buffered, 16);
+        assert!(elapsed >= Duration::from_millis(10));
+    }
+
+    // A continuing stall reports the total time, not just the last interval
+    for pair in warnings.windows(2) {
+        assert!(pair[1].1 >= pair[0].1 + Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn complete_on_idle_with_read_aggregation() {
+    let mut writer = Vec::new();
+    let reader = futures::AsyncReadExt::chain(&b"hi"[..], StalledReader);
+
+    // The reader goes quiet before the aggregation threshold is reached, so
+    // the tail is only written once the reader is considered finished
+    block_on(
+        Forwarder::new(reader, &mut writer, [0; 64])
+            .with_read_aggregation(16)
+            .complete_on_idle(Duration::from_millis(20)),
+    )
+    .unwrap();
+
+    assert_eq!(writer, b"hi");
+}
+
+#[test]
+fn complete_on_idle_starts_at_first_poll() {
+    let mut writer = Vec::new();
+    let reader = futures::AsyncReadExt::chain(&b"hello"[..], StalledReader);
+
+    let forwarder =
+        Forwarder::new(reader, &mut writer, [0; 16]).complete_on_idle(Duration::from_millis(20));
+
+    // Wait longer than the idle timeout before polling
+    std::thread::sleep(Duration::from_millis(40));
+    block_on(forwarder).unwrap();
+
+    assert_eq!(writer, b"hello");
+}
+
+#[test]
+fn builder_timeout_is_per_forwarder() {
+    let builder = ForwarderBuilder::new().timeout(Duration::from_millis(20));
+
+    // Outlive the timeout before building, to make sure the deadline is
+    // computed when each forwarder is built rather than when it's configured
+    std::thread::sleep(Duration::from_millis(40));
+
+    let mut writer = Vec::new();
+    block_on(builder.build(&b"hello"[..], &mut writer, [0; 16]).unwrap()).unwrap();
+    assert_eq!(writer, b"hello");
+
+    let mut writer = Vec::new();
+    let result = block_on(builder.build(StalledReader, &mut writer, [0; 16]).unwrap());
+    assert!(matches!(result, Err(ForwarderError::DeadlineExceeded)));
+}
@@ -0,0 +1,200 @@
+#![cfg(feature = "tokio")]
+
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use async_forward::{forward_from_framed, forward_into_framed, Forwarder, ForwarderError};
+use futures::{executor::block_on, io::Cursor, TryStreamExt};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio_util::codec::{BytesCodec, FramedRead, FramedWrite, LengthDelimitedCodec};
+
+/// A tokio reader that returns at most `chunk` bytes per read
+struct ChunkedReader {
+    data: Vec<u8>,
+    chunk: usize,
+}
+
+impl AsyncRead for ChunkedReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let n = self.chunk.min(self.data.len()).min(buf.remaining());
+        buf.put_slice(&self.data[..n]);
+        self.data.drain(..n);
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A tokio reader that never produces any data
+struct StalledReader;
+
+impl AsyncRead for StalledReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Poll::Pending
+    }
+}
+
+/// A tokio writer that records whether it was shut down
+#[derive(Default)]
+struct Writer {
+    data: Vec<u8>,
+    shutdown: bool,
+}
+
+impl AsyncWrite for Writer {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.data.extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.shutdown = true;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[test]
+fn forward_tokio_streams() {
+    let data: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+    let reader = ChunkedReader {
+        data: data.clone(),
+        chunk: 100,
+    };
+    let mut writer = Writer::default();
+
+    let written =
+        block_on(Forwarder::from_tokio(reader, &mut writer, [0; 64]).half_close_on_eof(true))
+            .unwrap();
+
+    assert_eq!(written, 1000);
+    assert_eq!(writer.data, data);
+    assert!(writer.shutdown);
+}
+
+#[test]
+fn tokio_idle_timeout() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap();
+
+    let mut writer = Writer::default();
+    let start = Instant::now();
+
+    let result = runtime.block_on(
+        Forwarder::from_tokio(StalledReader, &mut writer, [0; 16])
+            .tokio_idle_timeout(Duration::from_millis(20)),
+    );
+
+    assert!(matches!(result, Err(ForwarderError::IdleTimeout)));
+    assert!(start.elapsed() >= Duration::from_millis(20));
+}
+
+#[test]
+fn forward_from_frames() {
+    let data: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+    let reader = ChunkedReader {
+        data: data.clone(),
+        chunk: 100,
+    };
+
+    let mut writer = Vec::new();
+    let written = block_on(forward_from_framed(
+        FramedRead::new(reader, BytesCodec::new()),
+        &mut writer,
+    ))
+    .unwrap();
+
+    assert_eq!(written, 1000);
+    assert_eq!(writer, data);
+}
+
+#[test]
+fn forward_into_frames() {
+    let data: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+    let mut encoded = Vec::new();
+
+    block_on(forward_into_framed(
+        Cursor::new(data.clone()),
+        FramedWrite::new(&mut encoded, LengthDelimitedCodec::new()),
+        [0; 64],
+    ))
+    .unwrap();
+
+    // Each chunk that was read became its own frame
+    let frames: Vec<_> =
+        block_on(FramedRead::new(encoded.as_slice(), LengthDelimitedCodec::new()).try_collect())
+            .unwrap();
+
+    assert!(frames
+        .iter()
+        .all(|frame: &bytes::BytesMut| frame.len() <= 64));
+    assert_eq!(frames.concat(), data);
+}
+
+#[test]
+fn forward_udp_datagrams() {
+    use async_forward::DatagramForwarder;
+    use std::num::NonZeroU64;
+    use tokio::net::UdpSocket;
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()
+        .unwrap();
+
+    runtime.block_on(async {
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let proxy_in = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let proxy_out = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        client
+            .connect(proxy_in.local_addr().unwrap())
+            .await
+            .unwrap();
+        proxy_in
+            .connect(client.local_addr().unwrap())
+            .await
+            .unwrap();
+        proxy_out
+            .connect(server.local_addr().unwrap())
+            .await
+            .unwrap();
+
+        let messages: [&[u8]; 3] = [b"one", b"two two", b"three three three"];
+        for message in messages {
+            client.send(message).await.unwrap();
+        }
+
+        let forwarded = DatagramForwarder::new(proxy_in, proxy_out, vec![0; 65_536])
+            .with_max_messages(NonZeroU64::new(3).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(forwarded, 3);
+
+        let mut buf = [0; 64];
+        for message in messages {
+            let len = server.recv(&mut buf).await.unwrap();
+            assert_eq!(&buf[..len], message);
+        }
+    });
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "tracing")]
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use async_forward::Forwarder;
+use futures::executor::block_on;
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+
+/// A subscriber that records the names of new spans and the messages of
+/// events
+#[derive(Default, Clone)]
+struct Recorder {
+    spans: Arc<Mutex<Vec<&'static str>>>,
+    messages: Arc<Mutex<Vec<String>>>,
+}
+
+struct MessageVisitor<'a>(&'a mut String);
+
+impl Visit for MessageVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            *self.0 = format!("{value:?}");
+        }
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+        let mut spans = self.spans.lock().unwrap();
+        spans.push(span.metadata().name());
+        span::Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut message = String::new();
+        event.record(&mut MessageVisitor(&mut message));
+        self.messages.lock().unwrap().push(message);
+    }
+
+    fn enter(&self, _span: &span::Id) {}
+
+    fn exit(&self, _span: &span::Id) {}
+}
+
+#[test]
+fn trace_forward_lifecycle() {
+    let recorder = Recorder::default();
+
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let mut writer = Vec::new();
+        block_on(Forwarder::new(&b"hello"[..], &mut writer, [0; 4])).unwrap();
+    });
+
+    assert_eq!(*recorder.spans.lock().unwrap(), ["forward"]);
+
+    let messages = recorder.messages.lock().unwrap();
+    assert_eq!(messages.first().unwrap(), "read first byte");
+    assert!(messages.iter().any(|message| message == "reader EOF"));
+    assert_eq!(messages.last().unwrap(), "forward complete");
+}
+
+#[test]
+fn custom_span() {
+    let recorder = Recorder::default();
+
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let mut writer = Vec::new();
+        let forwarder = Forwarder::new(&b"hello"[..], &mut writer, [0; 4])
+            .with_span(tracing::info_span!("upload", user = "test"));
+        block_on(forwarder).unwrap();
+    });
+
+    assert_eq!(recorder.spans.lock().unwrap().last(), Some(&"upload"));
+}
@@ -0,0 +1,106 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_forward::{Forwarder, Transform};
+use futures::{executor::block_on, io::Cursor};
+
+/// Escapes backslashes and newlines, and ends the output with a newline
+struct Escape {
+    // An escape sequence that didn't fit in the output, waiting to be
+    // produced
+    pending: Option<u8>,
+    trailer_written: bool,
+}
+
+impl Transform for Escape {
+    fn poll_transform(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Poll<io::Result<(usize, usize)>> {
+        let mut consumed = 0;
+        let mut produced = 0;
+
+        for slot in output.iter_mut() {
+            if let Some(byte) = self.pending.take() {
+                *slot = byte;
+            } else if let Some(&byte) = input.get(consumed) {
+                consumed += 1;
+
+                *slot = match byte {
+                    b'\\' => {
+                        self.pending = Some(b'\\');
+                        b'\\'
+                    }
+                    b'\n' => {
+                        self.pending = Some(b'n');
+                        b'\\'
+                    }
+                    byte => byte,
+                };
+            } else {
+                break;
+            }
+
+            produced += 1;
+        }
+
+        Poll::Ready(Ok((consumed, produced)))
+    }
+
+    fn poll_finish(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        output: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if let Some(byte) = self.pending.take() {
+            output[0] = byte;
+            return Poll::Ready(Ok(1));
+        }
+
+        if self.trailer_written {
+            return Poll::Ready(Ok(0));
+        }
+
+        self.trailer_written = true;
+        output[0] = b'\n';
+        Poll::Ready(Ok(1))
+    }
+}
+
+#[test]
+fn escape_in_flight() {
+    let data = b"one\\two\nthree\n".repeat(50);
+    let mut expected = Vec::new();
+    for &byte in &data {
+        match byte {
+            b'\\' => expected.extend_from_slice(b"\\\\"),
+            b'\n' => expected.extend_from_slice(b"\\n"),
+            byte => expected.push(byte),
+        }
+    }
+    expected.push(b'\n');
+
+    let mut out = Vec::new();
+    let transform = Escape {
+        pending: None,
+        trailer_written: false,
+    };
+
+    // A tiny staging buffer means escape sequences get split across writes
+    let written = block_on(Forwarder::with_transform(
+        Cursor::new(data.clone()),
+        &mut out,
+        transform,
+        [0; 16],
+        3,
+    ))
+    .unwrap();
+
+    assert_eq!(written, data.len() as u64);
+    assert_eq!(out, expected);
+}